@@ -0,0 +1,408 @@
+use crate::ray_tracer::{matrices::Matrix, rays::Ray, tuples::Point, utils::EPSILON};
+
+/// An axis aligned bounding box.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Bounds {
+    min: Point,
+    max: Point,
+}
+
+impl Bounds {
+    pub fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+    /// A box containing nothing. Adding points or other boxes grows it from there.
+    pub fn empty() -> Self {
+        Self {
+            min: Point::new_point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point::new_point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+    /// A box containing everything, used for shapes like planes that extend forever.
+    pub fn infinite() -> Self {
+        Self {
+            min: Point::new_point(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            max: Point::new_point(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+        }
+    }
+    pub fn get_min(&self) -> Point {
+        self.min
+    }
+    pub fn get_max(&self) -> Point {
+        self.max
+    }
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+    fn is_finite(&self) -> bool {
+        [
+            self.min.x, self.min.y, self.min.z, self.max.x, self.max.y, self.max.z,
+        ]
+        .iter()
+        .all(|c| c.is_finite())
+    }
+
+    pub fn add_point(&mut self, point: &Point) {
+        self.min.x = self.min.x.min(point.x);
+        self.min.y = self.min.y.min(point.y);
+        self.min.z = self.min.z.min(point.z);
+        self.max.x = self.max.x.max(point.x);
+        self.max.y = self.max.y.max(point.y);
+        self.max.z = self.max.z.max(point.z);
+    }
+    pub fn merge(&mut self, other: &Bounds) {
+        if other.is_empty() {
+            return;
+        }
+        self.add_point(&other.min);
+        self.add_point(&other.max);
+    }
+
+    pub fn contains_point(&self, point: &Point) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x)
+            && (self.min.y..=self.max.y).contains(&point.y)
+            && (self.min.z..=self.max.z).contains(&point.z)
+    }
+    pub fn contains_bounds(&self, other: &Bounds) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    /// Transforms all eight corners of the box and returns a new axis aligned box containing
+    /// them.
+    pub fn transform(&self, transformation: &Matrix) -> Bounds {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            // Transforming infinite corners produces NaN, so stay conservative.
+            return Bounds::infinite();
+        }
+
+        let corners = [
+            Point::new_point(self.min.x, self.min.y, self.min.z),
+            Point::new_point(self.min.x, self.min.y, self.max.z),
+            Point::new_point(self.min.x, self.max.y, self.min.z),
+            Point::new_point(self.min.x, self.max.y, self.max.z),
+            Point::new_point(self.max.x, self.min.y, self.min.z),
+            Point::new_point(self.max.x, self.min.y, self.max.z),
+            Point::new_point(self.max.x, self.max.y, self.min.z),
+            Point::new_point(self.max.x, self.max.y, self.max.z),
+        ];
+
+        let mut bounds = Bounds::empty();
+        for corner in corners {
+            bounds.add_point(&(*transformation * corner));
+        }
+        bounds
+    }
+
+    /// Splits the box in two halves along its longest axis.
+    pub fn split(&self) -> (Bounds, Bounds) {
+        let dx = self.max.x - self.min.x;
+        let dy = self.max.y - self.min.y;
+        let dz = self.max.z - self.min.z;
+        let greatest = dx.max(dy).max(dz);
+
+        let (mut x0, mut y0, mut z0) = (self.min.x, self.min.y, self.min.z);
+        let (mut x1, mut y1, mut z1) = (self.max.x, self.max.y, self.max.z);
+
+        if greatest == dx {
+            x0 += dx / 2.0;
+            x1 = x0;
+        } else if greatest == dy {
+            y0 += dy / 2.0;
+            y1 = y0;
+        } else {
+            z0 += dz / 2.0;
+            z1 = z0;
+        }
+
+        let mid_min = Point::new_point(x0, y0, z0);
+        let mid_max = Point::new_point(x1, y1, z1);
+
+        (
+            Bounds::new(self.min, mid_max),
+            Bounds::new(mid_min, self.max),
+        )
+    }
+
+    /// Checks whether the ray hits the box at all. See [`Ray::intersects_bounds`].
+    pub(crate) fn intersects(&self, ray: &Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let (tmin, tmax) = self.slab_times(ray);
+        tmin <= tmax
+    }
+
+    /// Finds where the ray enters and leaves the box with the slab method.
+    /// The ray misses the box if the first time is greater than the second.
+    pub(crate) fn slab_times(&self, ray: &Ray) -> (f64, f64) {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+
+        (xtmin.max(ytmin).max(ztmin), xtmax.min(ytmax).min(ztmax))
+    }
+}
+
+fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    let tmin_numerator = min - origin;
+    let tmax_numerator = max - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f64::INFINITY,
+            tmax_numerator * f64::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Default for Bounds {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{PI, SQRT_2};
+
+    use super::*;
+    use crate::ray_tracer::{
+        transformations::Transform,
+        tuples::{Tuple, Vector},
+    };
+
+    #[test]
+    fn creating_an_empty_bounding_box() {
+        let b = Bounds::empty();
+        // Tuple equality subtracts the components, which doesn't work for infinities.
+        let (min, max) = (b.get_min(), b.get_max());
+        assert_eq!(
+            (min.x, min.y, min.z),
+            (f64::INFINITY, f64::INFINITY, f64::INFINITY)
+        );
+        assert_eq!(
+            (max.x, max.y, max.z),
+            (f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY)
+        );
+        assert!(b.is_empty());
+    }
+
+    #[test]
+    fn adding_points_to_an_empty_bounding_box() {
+        let mut b = Bounds::empty();
+        b.add_point(&Point::new_point(-5.0, 2.0, 0.0));
+        b.add_point(&Point::new_point(7.0, 0.0, -3.0));
+        assert_eq!(b.get_min(), Point::new_point(-5.0, 0.0, -3.0));
+        assert_eq!(b.get_max(), Point::new_point(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn adding_one_bounding_box_to_another() {
+        let mut b1 = Bounds::new(
+            Point::new_point(-5.0, -2.0, 0.0),
+            Point::new_point(7.0, 4.0, 4.0),
+        );
+        let b2 = Bounds::new(
+            Point::new_point(8.0, -7.0, -2.0),
+            Point::new_point(14.0, 2.0, 8.0),
+        );
+        b1.merge(&b2);
+        assert_eq!(b1.get_min(), Point::new_point(-5.0, -7.0, -2.0));
+        assert_eq!(b1.get_max(), Point::new_point(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_point() {
+        let b = Bounds::new(
+            Point::new_point(5.0, -2.0, 0.0),
+            Point::new_point(11.0, 4.0, 7.0),
+        );
+        let examples = [
+            (Point::new_point(5.0, -2.0, 0.0), true),
+            (Point::new_point(11.0, 4.0, 7.0), true),
+            (Point::new_point(8.0, 1.0, 3.0), true),
+            (Point::new_point(3.0, 0.0, 3.0), false),
+            (Point::new_point(8.0, -4.0, 3.0), false),
+            (Point::new_point(8.0, 1.0, -1.0), false),
+            (Point::new_point(13.0, 1.0, 3.0), false),
+            (Point::new_point(8.0, 5.0, 3.0), false),
+            (Point::new_point(8.0, 1.0, 8.0), false),
+        ];
+        for (point, result) in examples {
+            assert_eq!(b.contains_point(&point), result);
+        }
+    }
+
+    #[test]
+    fn checking_to_see_if_a_box_contains_a_given_box() {
+        let b = Bounds::new(
+            Point::new_point(5.0, -2.0, 0.0),
+            Point::new_point(11.0, 4.0, 7.0),
+        );
+        let examples = [
+            (
+                Point::new_point(5.0, -2.0, 0.0),
+                Point::new_point(11.0, 4.0, 7.0),
+                true,
+            ),
+            (
+                Point::new_point(6.0, -1.0, 1.0),
+                Point::new_point(10.0, 3.0, 6.0),
+                true,
+            ),
+            (
+                Point::new_point(4.0, -3.0, -1.0),
+                Point::new_point(10.0, 3.0, 6.0),
+                false,
+            ),
+            (
+                Point::new_point(6.0, -1.0, 1.0),
+                Point::new_point(12.0, 5.0, 8.0),
+                false,
+            ),
+        ];
+        for (min, max, result) in examples {
+            assert_eq!(b.contains_bounds(&Bounds::new(min, max)), result);
+        }
+    }
+
+    #[test]
+    fn transforming_a_bounding_box() {
+        let b = Bounds::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        );
+        let matrix = Transform::rotation_x(PI / 4.0) * Transform::rotation_y(PI / 4.0);
+        let b2 = b.transform(&matrix);
+        assert_eq!(b2.get_min(), Point::new_point(-SQRT_2, -1.70710, -1.70710));
+        assert_eq!(b2.get_max(), Point::new_point(SQRT_2, 1.70710, 1.70710));
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_bounding_box_at_the_origin() {
+        let b = Bounds::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        );
+        let examples = [
+            (
+                Tuple::new_point(5.0, 0.5, 0.0),
+                Tuple::new_vector(-1.0, 0.0, 0.0),
+                true,
+            ),
+            (
+                Tuple::new_point(-5.0, 0.5, 0.0),
+                Tuple::new_vector(1.0, 0.0, 0.0),
+                true,
+            ),
+            (
+                Tuple::new_point(0.5, 5.0, 0.0),
+                Tuple::new_vector(0.0, -1.0, 0.0),
+                true,
+            ),
+            (
+                Tuple::new_point(0.5, -5.0, 0.0),
+                Tuple::new_vector(0.0, 1.0, 0.0),
+                true,
+            ),
+            (
+                Tuple::new_point(0.5, 0.0, 5.0),
+                Tuple::new_vector(0.0, 0.0, -1.0),
+                true,
+            ),
+            (
+                Tuple::new_point(0.5, 0.0, -5.0),
+                Tuple::new_vector(0.0, 0.0, 1.0),
+                true,
+            ),
+            (
+                Tuple::new_point(0.0, 0.5, 0.0),
+                Tuple::new_vector(0.0, 0.0, 1.0),
+                true,
+            ),
+            (
+                Tuple::new_point(-2.0, 0.0, 0.0),
+                Tuple::new_vector(2.0, 4.0, 6.0),
+                false,
+            ),
+            (
+                Tuple::new_point(0.0, -2.0, 0.0),
+                Tuple::new_vector(6.0, 2.0, 4.0),
+                false,
+            ),
+            (
+                Tuple::new_point(0.0, 0.0, -2.0),
+                Tuple::new_vector(4.0, 6.0, 2.0),
+                false,
+            ),
+            (
+                Tuple::new_point(2.0, 0.0, 2.0),
+                Tuple::new_vector(0.0, 0.0, -1.0),
+                false,
+            ),
+            (
+                Tuple::new_point(0.0, 2.0, 2.0),
+                Tuple::new_vector(0.0, -1.0, 0.0),
+                false,
+            ),
+            (
+                Tuple::new_point(2.0, 2.0, 0.0),
+                Tuple::new_vector(-1.0, 0.0, 0.0),
+                false,
+            ),
+        ];
+        for (origin, direction, result) in examples {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), result);
+        }
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_an_infinite_bounding_box() {
+        let b = Bounds::infinite();
+        let r = Ray::new(
+            Point::new_point(0.0, 5.0, 0.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        assert!(b.intersects(&r));
+    }
+
+    #[test]
+    fn splitting_a_perfect_cube() {
+        let b = Bounds::new(
+            Point::new_point(-1.0, -4.0, -5.0),
+            Point::new_point(9.0, 6.0, 5.0),
+        );
+        let (left, right) = b.split();
+        assert_eq!(left.get_min(), Point::new_point(-1.0, -4.0, -5.0));
+        assert_eq!(left.get_max(), Point::new_point(4.0, 6.0, 5.0));
+        assert_eq!(right.get_min(), Point::new_point(4.0, -4.0, -5.0));
+        assert_eq!(right.get_max(), Point::new_point(9.0, 6.0, 5.0));
+    }
+
+    #[test]
+    fn splitting_a_y_wide_box() {
+        let b = Bounds::new(
+            Point::new_point(-1.0, -2.0, -3.0),
+            Point::new_point(5.0, 8.0, 3.0),
+        );
+        let (left, right) = b.split();
+        assert_eq!(left.get_min(), Point::new_point(-1.0, -2.0, -3.0));
+        assert_eq!(left.get_max(), Point::new_point(5.0, 3.0, 3.0));
+        assert_eq!(right.get_min(), Point::new_point(-1.0, 3.0, -3.0));
+        assert_eq!(right.get_max(), Point::new_point(5.0, 8.0, 3.0));
+    }
+}
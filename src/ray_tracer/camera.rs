@@ -1,13 +1,23 @@
 use std::{
+    f64::consts::PI,
     sync::{mpsc, Arc, Mutex},
     thread,
 };
 
 use crate::ray_tracer::{
-    canvas::Canvas, colors::Color, matrices::Matrix, rays::Ray, tuples::Tuple, world::World,
+    canvas::Canvas,
+    colors::Color,
+    error::RayTracerError,
+    materials::Material,
+    matrices::Matrix,
+    rays::Ray,
+    sampler::{Sampler, SplitMix64},
+    tuples::Tuple,
+    world::World,
 };
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Camera {
     hsize: usize,
     vsize: usize,
@@ -16,43 +26,160 @@ pub struct Camera {
     pixel_size: f64,
     half_width: f64,
     half_height: f64,
+    shutter_open: f64,
+    shutter_close: f64,
+    exposure: f64,
+    seed: u64,
 }
 
 impl Camera {
     pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
-        let half_view = f64::tan(field_of_view / 2.0);
-        let aspect: f64 = hsize as f64 / vsize as f64;
-        let mut _half_width = 0.0;
-        let mut _half_height = 0.0;
-        if aspect >= 1.0 {
-            _half_width = half_view;
-            _half_height = half_view / aspect;
-        } else {
-            _half_width = half_view * aspect;
-            _half_height = half_view;
+        Self::try_new(hsize, vsize, field_of_view).unwrap()
+    }
+
+    /// Creates a camera, or returns an error if the image would have no pixels or the field of
+    /// view is NaN or not strictly between 0 and π radians. Wider views would flip the image
+    /// or leave it black.
+    pub fn try_new(hsize: usize, vsize: usize, field_of_view: f64) -> Result<Self, RayTracerError> {
+        if hsize == 0 || vsize == 0 {
+            return Err(RayTracerError::InvalidDimensions);
+        }
+        if field_of_view.is_nan() {
+            return Err(RayTracerError::NaNInput);
+        }
+        if field_of_view <= 0.0 || field_of_view >= PI {
+            return Err(RayTracerError::InvalidFieldOfView);
         }
+
         let mut transform = Matrix::new_identity();
         transform.calculate_inverse().unwrap();
-        Camera {
+        let mut camera = Camera {
             hsize,
             vsize,
             field_of_view,
             transform,
-            pixel_size: (_half_width * 2.0) / hsize as f64,
-            half_height: _half_height,
-            half_width: _half_width,
+            pixel_size: 0.0,
+            half_height: 0.0,
+            half_width: 0.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
+            exposure: 1.0,
+            seed: 0,
+        };
+        camera.update_view_size();
+        Ok(camera)
+    }
+
+    /// Works out the size of the view and of its pixels from the field of view.
+    fn update_view_size(&mut self) {
+        let half_view = f64::tan(self.field_of_view / 2.0);
+        let aspect: f64 = self.hsize as f64 / self.vsize as f64;
+        if aspect >= 1.0 {
+            self.half_width = half_view;
+            self.half_height = half_view / aspect;
+        } else {
+            self.half_width = half_view * aspect;
+            self.half_height = half_view;
         }
+        self.pixel_size = (self.half_width * 2.0) / self.hsize as f64;
+    }
+
+    pub fn get_field_of_view(&self) -> f64 {
+        self.field_of_view
+    }
+
+    pub fn zoom(&mut self, factor: f64) {
+        self.try_zoom(factor).unwrap();
+    }
+
+    /// Magnifies the view by `factor`, like turning the zoom ring of a lens: 2.0 makes everything
+    /// twice as big, 0.5 half as big. The field of view narrows or widens to match, always
+    /// staying between 0 and π. Returns an error if the factor isn't a positive number.
+    pub fn try_zoom(&mut self, factor: f64) -> Result<(), RayTracerError> {
+        if factor.is_nan() {
+            return Err(RayTracerError::NaNInput);
+        }
+        let field_of_view = 2.0 * f64::atan(f64::tan(self.field_of_view / 2.0) / factor);
+        if factor <= 0.0 || field_of_view <= 0.0 || field_of_view >= PI {
+            return Err(RayTracerError::InvalidFieldOfView);
+        }
+        self.field_of_view = field_of_view;
+        self.update_view_size();
+        Ok(())
     }
 
     pub fn set_transform(&mut self, transformation: Matrix) {
+        self.try_set_transform(transformation).unwrap();
+    }
+
+    /// Sets the view transformation, or returns an error if the matrix contains NaN or can't be
+    /// inverted.
+    pub fn try_set_transform(&mut self, transformation: Matrix) -> Result<(), RayTracerError> {
+        transformation.validate_transform()?;
         self.transform = transformation;
-        self.transform.calculate_inverse().unwrap();
+        self.transform.calculate_inverse()?;
+        Ok(())
+    }
+
+    /// Sets the window of time the shutter is open for in [`Camera::render_with_shutter`].
+    /// Both ends are clamped to the 0 to 1 range objects move over, see
+    /// [`Object::set_motion`](crate::ray_tracer::shapes::Object::set_motion).
+    pub fn set_shutter(&mut self, open: f64, close: f64) {
+        self.shutter_open = open.clamp(0.0, 1.0);
+        self.shutter_close = close.clamp(self.shutter_open, 1.0);
+    }
+    pub fn get_shutter(&self) -> (f64, f64) {
+        (self.shutter_open, self.shutter_close)
+    }
+
+    /// Sets the factor every rendered color is multiplied by before it's written to the canvas.
+    /// The default of 1.0 leaves colors as they are. Above 1 brightens the whole render and below
+    /// 1 darkens it, without touching the lights; pair it with [`Canvas::tone_map_reinhard`] to
+    /// keep the highlights.
+    pub fn set_exposure(&mut self, exposure: f64) {
+        self.exposure = exposure;
+    }
+    pub fn get_exposure(&self) -> f64 {
+        self.exposure
+    }
+
+    /// Sets the seed for the camera's random sampling: the shutter times of
+    /// [`Camera::render_with_shutter`], the subpixel samples of [`Camera::render_antialiased`]
+    /// and the sample points on area lights, which are mixed with each light's own seed.
+    /// Rendering the same world with the same seed always gives the same image.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Checks the world with [`World::try_prepare`], which every renderer does before it
+    /// casts a ray, and returns the world as the camera samples it: every light's seed is mixed
+    /// with the camera's, so area light samples change with the camera seed too. `None` when
+    /// the world can be used as it is.
+    fn prepare(&self, w: &World) -> Result<Option<World>, RayTracerError> {
+        w.try_prepare()?;
+        Ok((self.seed != 0).then(|| w.with_light_seed(self.seed)))
+    }
+
+    /// The color seen along `ray`, scaled by the exposure.
+    fn color_at(&self, w: &World, ray: &Ray, num_reflections: usize) -> Color {
+        w.get_stats().add_primary_ray();
+        w.color_at(ray, num_reflections) * self.exposure
     }
 
     pub(crate) fn ray_for_pixel(&self, px: usize, py: usize) -> Ray {
-        // The offset from the edge of the canvas to the pixel's center
-        let xoffset = (px as f64 + 0.5) * self.pixel_size;
-        let yoffset = (py as f64 + 0.5) * self.pixel_size;
+        self.ray_for_subpixel(px, py, 0.5, 0.5)
+    }
+
+    /// Creates a ray through a point inside the pixel.
+    /// `dx` and `dy` are the offsets from the pixel's top left corner, as a fraction of the
+    /// pixel size, so (0.5, 0.5) is the pixel's center.
+    pub(crate) fn ray_for_subpixel(&self, px: usize, py: usize, dx: f64, dy: f64) -> Ray {
+        // The offset from the edge of the canvas to the sample point
+        let xoffset = (px as f64 + dx) * self.pixel_size;
+        let yoffset = (py as f64 + dy) * self.pixel_size;
 
         // The untransformed coordinates of the pixel in world space.
         // (Remember that the camera looks toward -z, so +x is to the *left*)
@@ -71,16 +198,206 @@ impl Camera {
     }
 
     pub fn render(&self, w: &World, num_reflections: usize) -> Canvas {
+        self.try_render(w, num_reflections).unwrap()
+    }
+
+    /// Renders the world, or returns an error if the world isn't fit for rendering.
+    /// See [`World::try_prepare`].
+    pub fn try_render(&self, w: &World, num_reflections: usize) -> Result<Canvas, RayTracerError> {
+        let seeded = self.prepare(w)?;
+        let w = seeded.as_ref().unwrap_or(w);
+
         let mut image = Canvas::new(self.hsize, self.vsize);
 
         for y in 0..self.vsize {
             for x in 0..self.hsize {
                 let ray = self.ray_for_pixel(x, y);
-                let color = w.color_at(&ray, num_reflections);
+                let color = self.color_at(w, &ray, num_reflections);
                 image.write_pixel(x, y, color);
             }
         }
 
+        Ok(image)
+    }
+
+    /// Renders the world straight into tightly packed RGBA bytes, for callers that draw the image
+    /// themselves, like a web page. See [`Canvas::to_rgba8`] for the layout.
+    pub fn render_to_rgba8(&self, w: &World, num_reflections: usize) -> Vec<u8> {
+        self.render_to_rgba8_gamma(w, num_reflections, 1.0)
+    }
+
+    /// Like [`Camera::render_to_rgba8`], but gamma corrects the colors.
+    pub fn render_to_rgba8_gamma(&self, w: &World, num_reflections: usize, gamma: f64) -> Vec<u8> {
+        self.render(w, num_reflections).to_rgba8(gamma)
+    }
+
+    /// Renders the world with every pixel multiplied by the color of the same pixel in `mask`,
+    /// e.g. for a vignette or a circular frame. Pixels where the mask is black are left black
+    /// without being traced.
+    ///
+    /// # Panics
+    /// Panics if the mask doesn't have the same size as the camera.
+    pub fn render_masked(&self, w: &World, num_reflections: usize, mask: &Canvas) -> Canvas {
+        self.try_render_masked(w, num_reflections, mask).unwrap()
+    }
+
+    /// Like [`Camera::render_masked`], but returns an error instead of panicking.
+    pub fn try_render_masked(
+        &self,
+        w: &World,
+        num_reflections: usize,
+        mask: &Canvas,
+    ) -> Result<Canvas, RayTracerError> {
+        if mask.width() != self.hsize || mask.height() != self.vsize {
+            return Err(RayTracerError::InvalidDimensions);
+        }
+        let seeded = self.prepare(w)?;
+        let w = seeded.as_ref().unwrap_or(w);
+
+        let black = Color::new(0.0, 0.0, 0.0);
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let weight = *mask.pixel_at(x, y);
+                if weight == black {
+                    continue;
+                }
+                let ray = self.ray_for_pixel(x, y);
+                image.write_pixel(x, y, self.color_at(w, &ray, num_reflections) * weight);
+            }
+        }
+
+        Ok(image)
+    }
+
+    /// Renders the world as if every object was made of `material`, keeping the geometry and
+    /// the lights. Useful for "clay" previews where only the shapes and lighting matter.
+    pub fn render_with_override_material(
+        &self,
+        w: &World,
+        num_reflections: usize,
+        material: &Material,
+    ) -> Canvas {
+        self.render(&w.with_material_override(material), num_reflections)
+    }
+
+    /// Renders the world with motion blur. The shutter is open from time 0 to
+    /// `shutter_duration`, and `time_samples` frames spread evenly over that window, from its
+    /// start to its end, each contribute `1 / time_samples` to the image. See
+    /// [`Object::set_motion`](crate::ray_tracer::shapes::Object::set_motion) for making objects
+    /// move.
+    /// A single time sample renders the world at time 0, which gives the same image as
+    /// [`Camera::render`]. [`Camera::render_with_shutter`] samples the camera's own shutter
+    /// window at random times instead.
+    pub fn render_motion_blur(
+        &self,
+        w: &World,
+        time_samples: usize,
+        shutter_duration: f64,
+        num_reflections: usize,
+    ) -> Canvas {
+        let time_samples = time_samples.max(1);
+        let times: Vec<f64> = (0..time_samples)
+            .map(|sample| match time_samples {
+                1 => 0.0,
+                _ => shutter_duration * sample as f64 / (time_samples - 1) as f64,
+            })
+            .collect();
+        self.render_at_times(w, &times, num_reflections)
+    }
+
+    /// Renders the world with motion blur over the camera's shutter window, see
+    /// [`Camera::set_shutter`]. The window is split into `time_samples` equal slices, and each
+    /// sample is rendered at a random time within its slice, drawn from the camera's seed.
+    /// With the shutter open for no time at all this gives the same image as [`Camera::render`].
+    pub fn render_with_shutter(
+        &self,
+        w: &World,
+        time_samples: usize,
+        num_reflections: usize,
+    ) -> Canvas {
+        let mut sampler = SplitMix64::new(self.seed);
+        self.render_with_shutter_sampler(w, time_samples, num_reflections, &mut sampler)
+    }
+
+    /// Like [`Camera::render_with_shutter`], but the sample times are drawn from `sampler`
+    /// instead of the camera's seed.
+    pub fn render_with_shutter_sampler(
+        &self,
+        w: &World,
+        time_samples: usize,
+        num_reflections: usize,
+        sampler: &mut dyn Sampler,
+    ) -> Canvas {
+        let time_samples = time_samples.max(1);
+        let duration = self.shutter_close - self.shutter_open;
+        let times: Vec<f64> = (0..time_samples)
+            .map(|sample| {
+                let offset = (sample as f64 + sampler.next_f64()) / time_samples as f64;
+                self.shutter_open + duration * offset
+            })
+            .collect();
+        self.render_at_times(w, &times, num_reflections)
+    }
+
+    /// Averages the frames rendered with the world at each of `times`.
+    fn render_at_times(&self, w: &World, times: &[f64], num_reflections: usize) -> Canvas {
+        let weight = 1.0 / times.len() as f64;
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for &time in times {
+            let frame = self.render(&w.at_time(time), num_reflections);
+            for y in 0..self.vsize {
+                for x in 0..self.hsize {
+                    let color = *image.pixel_at(x, y) + *frame.pixel_at(x, y) * weight;
+                    image.write_pixel(x, y, color);
+                }
+            }
+        }
+
+        image
+    }
+
+    /// Renders the world with supersampling to smooth out jagged edges.
+    /// Each pixel is split into a grid of `samples_per_axis` x `samples_per_axis` cells, a ray is
+    /// cast through a random point in every cell, drawn from the camera's seed, and the pixel
+    /// gets the average color.
+    /// One sample per axis casts a single ray through the pixel's center, which gives the same
+    /// image as [`Camera::render`]. A `samples_per_axis` of 0 is treated as 1.
+    /// Like every renderer, panics if the world isn't fit for rendering, see
+    /// [`World::try_prepare`].
+    pub fn render_antialiased(
+        &self,
+        w: &World,
+        samples_per_axis: usize,
+        num_reflections: usize,
+    ) -> Canvas {
+        let seeded = self.prepare(w).unwrap();
+        let w = seeded.as_ref().unwrap_or(w);
+        let samples_per_axis = samples_per_axis.max(1);
+        let num_samples = (samples_per_axis * samples_per_axis) as f64;
+        let mut sampler = SplitMix64::new(self.seed);
+        let mut offset = |cell: usize| match samples_per_axis {
+            1 => 0.5,
+            _ => (cell as f64 + sampler.next_f64()) / samples_per_axis as f64,
+        };
+        let mut image = Canvas::new(self.hsize, self.vsize);
+
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let mut color = Color::new(0.0, 0.0, 0.0);
+                for sy in 0..samples_per_axis {
+                    for sx in 0..samples_per_axis {
+                        let dx = offset(sx);
+                        let dy = offset(sy);
+                        let ray = self.ray_for_subpixel(x, y, dx, dy);
+                        color = color + self.color_at(w, &ray, num_reflections);
+                    }
+                }
+                image.write_pixel(x, y, color * (1.0 / num_samples));
+            }
+        }
+
         image
     }
 
@@ -90,15 +407,13 @@ impl Camera {
         thread_num: usize,
         num_reflections: usize,
     ) -> Canvas {
+        let seeded = self.prepare(w).unwrap();
+        let w = seeded.as_ref().unwrap_or(w);
         let image = Arc::new(Mutex::new(Canvas::new(self.hsize, self.vsize)));
 
-        let mut pixels_per_thread = self.vsize;
-        if thread_num > 0 {
-            pixels_per_thread = self.vsize / thread_num;
-        }
+        let pixels_per_thread = self.vsize.checked_div(thread_num).unwrap_or(self.vsize);
         let (tx, rx) = mpsc::channel();
         thread::scope(|s| {
-            let mut thread_handles = Vec::new();
             let mut pixels_not_allocated = self.vsize;
             let mut last_allocated_pixels = 0;
 
@@ -108,17 +423,16 @@ impl Camera {
                 let end_pixels = start_pixels + pixels_per_thread;
                 last_allocated_pixels = end_pixels;
                 pixels_not_allocated -= pixels_per_thread;
-                let handle = s.spawn(move || {
+                s.spawn(move || {
                     //
                     for y in start_pixels..end_pixels {
                         for x in 0..self.hsize {
                             let ray = self.ray_for_pixel(x, y);
-                            let color = w.color_at(&ray, num_reflections);
+                            let color = self.color_at(w, &ray, num_reflections);
                             tx_clone.send((x, y, color)).unwrap();
                         }
                     }
                 });
-                thread_handles.push(handle);
             }
 
             // If there are more pixels left to start a thread for, create one for that.
@@ -126,34 +440,26 @@ impl Camera {
                 let start_pixels = last_allocated_pixels;
                 let end_pixels = last_allocated_pixels + pixels_not_allocated;
                 let tx_clone = tx.clone();
-                let handle = s.spawn(move || {
+                s.spawn(move || {
                     for y in start_pixels..end_pixels {
                         for x in 0..self.hsize {
                             let ray = self.ray_for_pixel(x, y);
-                            let color = w.color_at(&ray, num_reflections);
+                            let color = self.color_at(w, &ray, num_reflections);
                             tx_clone.send((x, y, color)).unwrap();
                         }
                     }
                 });
-                thread_handles.push(handle);
             }
 
+            // Only the workers hold senders now, so the channel disconnects and ends the
+            // collector once the last of them is done.
+            drop(tx);
+
             let thread_image = Arc::clone(&image);
-            s.spawn(move || loop {
-                if thread_handles.len() > thread_handles.iter().filter(|x| x.is_finished()).count()
-                {
-                    let values: Result<(usize, usize, Color), mpsc::TryRecvError> = rx.try_recv();
-                    match values {
-                        Ok((x, y, color)) => {
-                            let mut internal_image = thread_image.lock().unwrap();
-                            internal_image.write_pixel(x, y, color);
-                        }
-                        _ => {
-                            continue;
-                        }
-                    }
-                } else {
-                    break;
+            s.spawn(move || {
+                let mut internal_image = thread_image.lock().unwrap();
+                for (x, y, color) in rx {
+                    internal_image.write_pixel(x, y, color);
                 }
             });
         });
@@ -168,6 +474,8 @@ impl Camera {
         thread_num: usize,
         num_reflections: usize,
     ) -> Canvas {
+        let seeded = self.prepare(w).unwrap();
+        let w = seeded.as_ref().unwrap_or(w);
         let image = Arc::new(Mutex::new(Canvas::new(self.hsize, self.vsize)));
 
         let (tx, rx) = mpsc::channel();
@@ -178,45 +486,36 @@ impl Camera {
             let pixel_rows_to_render: Vec<usize> = (0..self.vsize).collect();
             let pixel_rows_to_render = Arc::new(Mutex::new(pixel_rows_to_render));
 
-            let mut thread_handles = Vec::new();
             for _thread in 0..thread_num {
                 let tx_clone = tx.clone();
                 let pixel_rows = Arc::clone(&pixel_rows_to_render);
-                let handle = s.spawn(move || loop {
+                s.spawn(move || loop {
                     // While there are still pixel rows to render, render them.
                     // Otherwise, break out of the loop.
                     let mut pixel_rows_to_render = pixel_rows.lock().unwrap();
-                    if pixel_rows_to_render.len() > 0 {
+                    if !pixel_rows_to_render.is_empty() {
                         let row = pixel_rows_to_render.pop().unwrap();
                         drop(pixel_rows_to_render);
                         for x in 0..self.hsize {
                             let ray = self.ray_for_pixel(x, row);
-                            let color = w.color_at(&ray, num_reflections);
+                            let color = self.color_at(w, &ray, num_reflections);
                             tx_clone.send((x, row, color)).unwrap();
                         }
                     } else {
                         break;
                     }
                 });
-                thread_handles.push(handle);
             }
 
+            // Only the workers hold senders now, so the channel disconnects and ends the
+            // collector once the last of them is done.
+            drop(tx);
+
             let thread_image = Arc::clone(&image);
-            s.spawn(move || loop {
-                if thread_handles.len() > thread_handles.iter().filter(|x| x.is_finished()).count()
-                {
-                    let values: Result<(usize, usize, Color), mpsc::TryRecvError> = rx.try_recv();
-                    match values {
-                        Ok((x, y, color)) => {
-                            let mut internal_image = thread_image.lock().unwrap();
-                            internal_image.write_pixel(x, y, color);
-                        }
-                        _ => {
-                            continue;
-                        }
-                    }
-                } else {
-                    break;
+            s.spawn(move || {
+                let mut internal_image = thread_image.lock().unwrap();
+                for (x, y, color) in rx {
+                    internal_image.write_pixel(x, y, color);
                 }
             });
         });
@@ -224,6 +523,82 @@ impl Camera {
         let ret_img = image.lock().unwrap().clone();
         ret_img
     }
+
+    /// Splits the image into `tile_size` by `tile_size` tiles and renders them in parallel,
+    /// one thread per available core. Tiles along the right and bottom edges may be smaller.
+    /// Produces the same image as [`Camera::render`]. A tile is never larger than the image.
+    pub fn render_tiles(&self, w: &World, tile_size: usize, num_reflections: usize) -> Canvas {
+        let seeded = self.prepare(w).unwrap();
+        let w = seeded.as_ref().unwrap_or(w);
+        let tile_size = tile_size.clamp(1, self.hsize.max(self.vsize).max(1));
+        let thread_num = thread::available_parallelism().map_or(1, |n| n.get());
+
+        // The corner of each tile, taken from the back by whichever thread is free.
+        let mut tiles = Vec::new();
+        for y in (0..self.vsize).step_by(tile_size) {
+            for x in (0..self.hsize).step_by(tile_size) {
+                tiles.push((x, y));
+            }
+        }
+        tiles.reverse();
+        let tiles = Arc::new(Mutex::new(tiles));
+
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        let (tx, rx) = mpsc::channel();
+        thread::scope(|s| {
+            for _thread in 0..thread_num {
+                let tx_clone = tx.clone();
+                let tiles = Arc::clone(&tiles);
+                s.spawn(move || loop {
+                    let tile = tiles.lock().unwrap().pop();
+                    let Some((tile_x, tile_y)) = tile else {
+                        break;
+                    };
+                    let mut pixels = Vec::with_capacity(tile_size * tile_size);
+                    for y in tile_y..usize::min(tile_y + tile_size, self.vsize) {
+                        for x in tile_x..usize::min(tile_x + tile_size, self.hsize) {
+                            let ray = self.ray_for_pixel(x, y);
+                            pixels.push((x, y, self.color_at(w, &ray, num_reflections)));
+                        }
+                    }
+                    tx_clone.send(pixels).unwrap();
+                });
+            }
+            drop(tx);
+
+            for pixels in rx {
+                for (x, y, color) in pixels {
+                    image.write_pixel(x, y, color);
+                }
+            }
+        });
+
+        image
+    }
+
+    /// Renders the pixels in parallel on rayon's global thread pool.
+    /// Produces the same image as [`Camera::render`].
+    #[cfg(feature = "rayon")]
+    pub fn render_rayon(&self, w: &World, num_reflections: usize) -> Canvas {
+        let seeded = self.prepare(w).unwrap();
+        let w = seeded.as_ref().unwrap_or(w);
+        use rayon::prelude::*;
+
+        let colors: Vec<Color> = (0..self.hsize * self.vsize)
+            .into_par_iter()
+            .map(|i| {
+                let ray = self.ray_for_pixel(i % self.hsize, i / self.hsize);
+                self.color_at(w, &ray, num_reflections)
+            })
+            .collect();
+
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for (i, color) in colors.into_iter().enumerate() {
+            image.write_pixel(i % self.hsize, i / self.hsize, color);
+        }
+
+        image
+    }
 }
 
 #[cfg(test)]
@@ -231,8 +606,15 @@ mod tests {
     use std::f64::consts::PI;
 
     use crate::ray_tracer::{
-        canvas::Canvas, colors::Color, matrices::Matrix, transformations::Transform, tuples::Tuple,
-        utils::is_float_equal, world::World,
+        canvas::Canvas,
+        colors::Color,
+        lights::Light,
+        matrices::Matrix,
+        shapes::{new_plane, Object, Sphere},
+        transformations::Transform,
+        tuples::Tuple,
+        utils::is_float_equal,
+        world::World,
     };
 
     use super::*;
@@ -250,6 +632,87 @@ mod tests {
         assert_eq!(c.transform, Matrix::new_identity());
     }
     #[test]
+    fn constructing_a_camera_without_pixels_is_an_error() {
+        assert_eq!(
+            Camera::try_new(0, 120, PI / 2.0),
+            Err(RayTracerError::InvalidDimensions)
+        );
+        assert_eq!(
+            Camera::try_new(160, 0, PI / 2.0),
+            Err(RayTracerError::InvalidDimensions)
+        );
+    }
+    #[test]
+    fn constructing_a_camera_with_nan_field_of_view_is_an_error() {
+        assert_eq!(
+            Camera::try_new(160, 120, f64::NAN),
+            Err(RayTracerError::NaNInput)
+        );
+    }
+    #[test]
+    fn constructing_a_camera_with_an_out_of_range_field_of_view_is_an_error() {
+        for field_of_view in [0.0, -0.5, PI, 60.0] {
+            assert_eq!(
+                Camera::try_new(160, 120, field_of_view),
+                Err(RayTracerError::InvalidFieldOfView)
+            );
+        }
+        assert!(Camera::try_new(160, 120, PI - 0.01).is_ok());
+    }
+    #[test]
+    fn zooming_scales_the_view_around_the_current_field_of_view() {
+        let mut c = Camera::new(200, 125, PI / 2.0);
+        c.zoom(2.0);
+        assert!(is_float_equal(&c.get_field_of_view(), 2.0 * f64::atan(0.5)));
+        assert!(is_float_equal(&c.pixel_size, 0.005));
+        c.zoom(0.5);
+        assert!(is_float_equal(&c.get_field_of_view(), PI / 2.0));
+        assert!(is_float_equal(&c.pixel_size, 0.01));
+
+        assert_eq!(c.try_zoom(0.0), Err(RayTracerError::InvalidFieldOfView));
+        assert_eq!(c.try_zoom(-2.0), Err(RayTracerError::InvalidFieldOfView));
+        assert_eq!(c.try_zoom(f64::NAN), Err(RayTracerError::NaNInput));
+        assert!(is_float_equal(&c.get_field_of_view(), PI / 2.0));
+    }
+    #[test]
+    fn setting_a_singular_view_transformation_is_an_error() {
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        assert_eq!(
+            c.try_set_transform(Transform::scaling(1.0, 0.0, 1.0)),
+            Err(RayTracerError::SingularTransform)
+        );
+        assert_eq!(c.transform, Matrix::new_identity());
+    }
+    #[test]
+    fn rendering_a_world_containing_nan_is_an_error() {
+        let mut w = World::new_default_world();
+        let mut m = w.objects[0].get_material();
+        m.diffuse = f64::NAN;
+        w.objects[0].set_material(&m);
+        let c = Camera::new(11, 11, PI / 2.0);
+        assert!(matches!(c.try_render(&w, 1), Err(RayTracerError::NaNInput)));
+    }
+    #[test]
+    fn every_renderer_checks_the_world_first() {
+        let mut w = World::new_default_world();
+        let mut m = w.objects[0].get_material();
+        m.diffuse = f64::NAN;
+        w.objects[0].set_material(&m);
+        let c = Camera::new(3, 3, PI / 2.0);
+        let renders: [&dyn Fn() -> Canvas; 5] = [
+            &|| c.render(&w, 1),
+            &|| c.render_antialiased(&w, 2, 1),
+            &|| c.render_multithreaded(&w, 2, 1),
+            &|| c.render_multithreaded_improved(&w, 2, 1),
+            &|| c.render_tiles(&w, 2, 1),
+        ];
+        for render in renders {
+            assert!(std::panic::catch_unwind(std::panic::AssertUnwindSafe(render)).is_err());
+        }
+        #[cfg(feature = "rayon")]
+        assert!(std::panic::catch_unwind(|| c.render_rayon(&w, 1)).is_err());
+    }
+    #[test]
     fn the_pixel_size_for_a_horizontal_canvas() {
         let c = Camera::new(200, 125, PI / 2.0);
         assert!(is_float_equal(&c.pixel_size, 0.01));
@@ -295,6 +758,371 @@ mod tests {
         let image: Canvas = c.render(&w, 1);
         assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+    #[cfg(feature = "stats")]
+    #[test]
+    fn rendering_one_pixel_of_a_sphere_counts_its_rays() {
+        let mut w = World::new();
+        w.objects.push(Object::Sphere(Sphere::new()));
+        w.lights.push(Light::point_light(
+            &Tuple::new_point(-10.0, 10.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut c = Camera::new(1, 1, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+
+        c.render(&w, 1);
+        let stats = w.get_stats();
+        assert_eq!(stats.primary_rays(), 1);
+        assert_eq!(stats.shadow_rays(), 1);
+        assert_eq!(stats.reflection_rays(), 0);
+        assert_eq!(stats.refraction_rays(), 0);
+        // One test for the camera ray and one for the shadow ray
+        assert_eq!(stats.intersection_tests(), 2);
+    }
+    #[test]
+    fn rendering_to_rgba_bytes() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 7, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        let bytes = c.render_to_rgba8(&w, 1);
+        assert_eq!(bytes.len(), 11 * 7 * 4);
+        assert_eq!(bytes, c.render(&w, 1).to_rgba8(1.0));
+        assert!(bytes.iter().skip(3).step_by(4).all(|&alpha| alpha == 255));
+        assert_eq!(c.render_to_rgba8_gamma(&w, 1, 2.2).len(), bytes.len());
+    }
+    #[test]
+    fn exposure_scales_every_rendered_color() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        assert_eq!(c.get_exposure(), 1.0);
+        let normal = c.render(&w, 1);
+
+        c.set_exposure(2.0);
+        let doubled = c.render(&w, 1);
+        assert_eq!(doubled.pixel_at(5, 5), Color::new(0.76132, 0.95166, 0.5710));
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(doubled.pixel_at(x, y), *normal.pixel_at(x, y) * 2.0);
+            }
+        }
+        assert_eq!(c.render_tiles(&w, 4, 1), doubled);
+    }
+    #[test]
+    fn constructing_a_ray_through_a_subpixel() {
+        let c = Camera::new(201, 101, PI / 2.0);
+        assert_eq!(
+            c.ray_for_subpixel(100, 50, 0.5, 0.5),
+            c.ray_for_pixel(100, 50)
+        );
+        let r = c.ray_for_subpixel(0, 0, 0.0, 0.0);
+        assert_eq!(r.origin, Tuple::new_point(0.0, 0.0, 0.0));
+        assert_eq!(r.direction, Tuple::new_vector(0.6663, 0.33481, -0.6663));
+    }
+    #[test]
+    fn rendering_with_one_sample_per_axis_matches_render() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        let plain = c.render(&w, 1);
+        let antialiased = c.render_antialiased(&w, 1, 1);
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(plain.pixel_at(x, y), antialiased.pixel_at(x, y));
+            }
+        }
+    }
+    #[test]
+    fn antialiasing_blends_pixels_on_the_edge_of_a_shape() {
+        let mut w = World::new_default_world();
+        w.objects.truncate(1);
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+
+        // Find a pixel where the plain render misses the sphere but a subpixel hits it
+        let plain = c.render(&w, 1);
+        let antialiased = c.render_antialiased(&w, 4, 1);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let blended = (0..11)
+            .flat_map(|y| (0..11).map(move |x| (x, y)))
+            .any(|(x, y)| plain.pixel_at(x, y) == black && antialiased.pixel_at(x, y) != black);
+        assert!(blended);
+    }
+    #[test]
+    fn antialiasing_jitters_the_samples_with_the_seed() {
+        let mut w = World::new_default_world();
+        w.objects.truncate(1);
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+
+        c.set_seed(7);
+        let first = c.render_antialiased(&w, 2, 1);
+        assert_eq!(c.render_antialiased(&w, 2, 1), first);
+        c.set_seed(8);
+        assert_ne!(c.render_antialiased(&w, 2, 1), first);
+        assert_eq!(c.render_antialiased(&w, 0, 1), c.render(&w, 1));
+    }
+    #[test]
+    fn rendering_with_an_override_material() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        let mut clay = Material::new();
+        clay.color = Color::new(0.8, 0.8, 0.8);
+        clay.specular = 0.0;
+
+        let normal = c.render(&w, 1);
+        let clay_image = c.render_with_override_material(&w, 1, &clay);
+
+        // The same world, with the material set by hand on both spheres
+        let mut expected_world = World::new_default_world();
+        for object in &mut expected_world.objects {
+            object.set_material(&clay);
+        }
+        let expected = c.render(&expected_world, 1);
+
+        assert_ne!(clay_image.pixel_at(5, 5), normal.pixel_at(5, 5));
+        let (red, green, blue) = {
+            let p = clay_image.pixel_at(5, 5);
+            (p.red, p.green, p.blue)
+        };
+        assert!(is_float_equal(&red, green) && is_float_equal(&green, blue));
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(clay_image.pixel_at(x, y), expected.pixel_at(x, y));
+            }
+        }
+        // The original world is left untouched
+        assert_eq!(w.objects[0].get_material().color, Color::new(0.8, 1.0, 0.6));
+    }
+    #[test]
+    fn a_circular_mask_blacks_out_the_corners() {
+        let mut w = World::new_default_world();
+        let mut wall = new_plane();
+        wall.set_transform(
+            &(Transform::translate(0.0, 0.0, 10.0) * Transform::rotation_x(PI / 2.0)),
+        );
+        w.objects.push(wall);
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+
+        let mut mask = Canvas::new(11, 11);
+        for y in 0..11 {
+            for x in 0..11 {
+                let (dx, dy) = (x as f64 - 5.0, y as f64 - 5.0);
+                if dx * dx + dy * dy <= 25.0 {
+                    mask.write_pixel(x, y, Color::new(1.0, 1.0, 1.0));
+                }
+            }
+        }
+
+        let plain = c.render(&w, 1);
+        let masked = c.render_masked(&w, 1, &mask);
+        assert_eq!(masked.pixel_at(5, 5), plain.pixel_at(5, 5));
+        assert_eq!(masked.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        for (x, y) in [(0, 0), (10, 0), (0, 10), (10, 10)] {
+            assert_ne!(plain.pixel_at(x, y), Color::new(0.0, 0.0, 0.0));
+            assert_eq!(masked.pixel_at(x, y), Color::new(0.0, 0.0, 0.0));
+        }
+    }
+    #[test]
+    fn a_mask_must_match_the_camera_size() {
+        let w = World::new_default_world();
+        let c = Camera::new(11, 11, PI / 2.0);
+        let mask = Canvas::new(10, 11);
+        assert!(matches!(
+            c.try_render_masked(&w, 1, &mask),
+            Err(RayTracerError::InvalidDimensions)
+        ));
+    }
+    fn glowing_sphere_world() -> World {
+        let mut sphere = Object::Sphere(Sphere::new());
+        let mut material = Material::new();
+        material.color = Color::new(1.0, 1.0, 1.0);
+        material.ambient = 1.0;
+        material.diffuse = 0.0;
+        material.specular = 0.0;
+        sphere.set_material(&material);
+
+        let mut w = World::new();
+        w.objects.push(sphere);
+        w.lights.push(Light::point_light(
+            &Tuple::new_point(0.0, 0.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        ));
+        w
+    }
+    #[test]
+    fn a_moving_object_is_spread_over_the_pixels_along_its_path() {
+        // The five pixel centers look at x = 8, 4, 0, -4 and -8 at z = -10
+        let c = Camera::new(5, 1, PI / 2.0);
+        let mut w = glowing_sphere_world();
+        w.objects[0].set_motion(
+            &Transform::translate(8.0, 0.0, -10.0),
+            &Transform::translate(-8.0, 0.0, -10.0),
+        );
+
+        // The sphere is at x = 8, 4, 0, -4 and -8 in the five time samples, including the end
+        let image = c.render_motion_blur(&w, 5, 1.0, 1);
+        for x in 0..5 {
+            assert_eq!(image.pixel_at(x, 0), Color::new(0.2, 0.2, 0.2));
+        }
+        let brightness: f64 = (0..5).map(|x| image.pixel_at(x, 0).red).sum();
+        assert!(is_float_equal(&brightness, 1.0));
+    }
+    #[test]
+    fn a_half_turn_can_be_motion_blurred() {
+        let c = Camera::new(5, 1, PI / 2.0);
+        let mut w = glowing_sphere_world();
+        let start = Transform::translate(0.0, 0.0, -10.0) * Transform::scaling(3.0, 1.0, 1.0);
+        w.objects[0].set_motion(&start, &(start * Transform::rotation_y(PI)));
+        let image = c.render_motion_blur(&w, 3, 1.0, 1);
+        assert_eq!(image.pixel_at(2, 0), Color::new(1.0, 1.0, 1.0));
+    }
+    #[test]
+    fn motion_blur_of_still_objects_matches_render() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        let still = c.render(&w, 1);
+        let blurred = c.render_motion_blur(&w, 4, 1.0, 1);
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(still.pixel_at(x, y), blurred.pixel_at(x, y));
+            }
+        }
+    }
+    #[test]
+    fn a_single_time_sample_matches_render() {
+        let c = Camera::new(5, 1, PI / 2.0);
+        let mut w = glowing_sphere_world();
+        w.objects[0].set_motion(
+            &Transform::translate(8.0, 0.0, -10.0),
+            &Transform::translate(-8.0, 0.0, -10.0),
+        );
+        let still = c.render(&w, 1);
+        let blurred = c.render_motion_blur(&w, 1, 1.0, 1);
+        for x in 0..5 {
+            assert_eq!(still.pixel_at(x, 0), blurred.pixel_at(x, 0));
+        }
+        assert_eq!(still.pixel_at(0, 0), Color::new(1.0, 1.0, 1.0));
+    }
+    #[test]
+    fn a_closed_shutter_matches_render() {
+        let mut c = Camera::new(5, 1, PI / 2.0);
+        let mut w = glowing_sphere_world();
+        w.objects[0].set_motion(
+            &Transform::translate(8.0, 0.0, -10.0),
+            &Transform::translate(-8.0, 0.0, -10.0),
+        );
+        assert_eq!(c.get_shutter(), (0.0, 0.0));
+        assert_eq!(c.render_with_shutter(&w, 4, 1), c.render(&w, 1));
+
+        c.set_shutter(0.5, 0.5);
+        w.objects[0].set_transform(&Transform::translate(0.0, 0.0, -10.0));
+        assert_eq!(c.render_with_shutter(&w, 4, 1), c.render(&w, 1));
+    }
+    #[test]
+    fn an_open_shutter_streaks_a_moving_object() {
+        let mut c = Camera::new(5, 1, PI / 2.0);
+        c.set_shutter(0.0, 1.0);
+        let mut w = glowing_sphere_world();
+        w.objects[0].set_motion(
+            &Transform::translate(8.0, 0.0, -10.0),
+            &Transform::translate(-8.0, 0.0, -10.0),
+        );
+        let image = c.render_with_shutter(&w, 8, 1);
+        let lit = (0..5).filter(|&x| image.pixel_at(x, 0).red > 0.0).count();
+        assert!(lit > 1);
+        assert!(image.pixel_at(0, 0).red < 1.0);
+    }
+    #[test]
+    fn the_seed_decides_the_shutter_samples() {
+        let mut c = Camera::new(21, 1, PI / 2.0);
+        c.set_shutter(0.0, 1.0);
+        let mut w = glowing_sphere_world();
+        w.objects[0].set_motion(
+            &Transform::translate(8.0, 0.0, -10.0),
+            &Transform::translate(-8.0, 0.0, -10.0),
+        );
+        assert_eq!(c.get_seed(), 0);
+
+        c.set_seed(7);
+        let first = c.render_with_shutter(&w, 3, 1);
+        let second = c.render_with_shutter(&w, 3, 1);
+        assert_eq!(first, second);
+
+        c.set_seed(8);
+        let other = c.render_with_shutter(&w, 3, 1);
+        assert_ne!(first, other);
+
+        let mut sampler = SplitMix64::new(7);
+        assert_eq!(c.render_with_shutter_sampler(&w, 3, 1, &mut sampler), first);
+    }
+    #[test]
+    fn the_seed_decides_the_area_light_samples() {
+        let mut w = World::new_default_world();
+        let mut floor = new_plane();
+        floor.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        w.objects.push(floor);
+        w.lights = vec![Light::area_light(
+            &Tuple::new_point(-3.0, 4.0, -3.0),
+            &Tuple::new_vector(2.0, 0.0, 0.0),
+            4,
+            &Tuple::new_vector(0.0, 2.0, 0.0),
+            4,
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+        let mut c = Camera::new(21, 21, PI / 2.0);
+        c.set_transform(Transform::view_transform(
+            &Tuple::new_point(0.0, 3.0, -4.0),
+            &Tuple::new_point(0.0, -1.0, 0.0),
+            &Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+
+        c.set_seed(7);
+        let first = c.render(&w, 1);
+        assert_eq!(c.render(&w, 1), first);
+        assert_eq!(c.render_tiles(&w, 4, 1), first);
+
+        c.set_seed(8);
+        assert_ne!(c.render(&w, 1), first);
+
+        // Seed 0 leaves the lights' own seeds alone
+        c.set_seed(0);
+        let unseeded = c.render(&w, 1);
+        w.lights[0].set_seed(7);
+        assert_ne!(c.render(&w, 1), unseeded);
+        w.lights[0].set_seed(0);
+        assert_eq!(c.render(&w, 1), unseeded);
+    }
     #[test]
     fn rendering_a_world_with_a_camera_with_one_thread() {
         let w = World::new_default_world();
@@ -328,4 +1156,49 @@ mod tests {
         let image: Canvas = c.render_multithreaded_improved(&w, 2, 1);
         assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
     }
+    #[test]
+    fn rendering_in_tiles_matches_a_single_threaded_render() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 7, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        let expected = c.render(&w, 1);
+        for tile_size in [0, 1, 3, 4, 16, usize::MAX] {
+            assert_eq!(c.render_tiles(&w, tile_size, 1), expected);
+        }
+    }
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn rendering_a_world_with_rayon_matches_a_single_threaded_render() {
+        let w = World::new_default_world();
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        let from = Tuple::new_point(0.0, 0.0, -5.0);
+        let to = Tuple::new_point(0.0, 0.0, 0.0);
+        let up = Tuple::new_vector(0.0, 1.0, 0.0);
+        c.set_transform(Transform::view_transform(&from, &to, &up));
+        let image: Canvas = c.render_rayon(&w, 1);
+        assert_eq!(image.pixel_at(5, 5), Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(image, c.render(&w, 1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_camera_survives_a_json_round_trip() {
+        let mut c = Camera::new(201, 101, PI / 2.0);
+        c.set_transform(Transform::view_transform(
+            &Tuple::new_point(0.0, 2.0, -5.0),
+            &Tuple::new_point(0.0, 0.0, 0.0),
+            &Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+
+        let json = serde_json::to_string(&c).unwrap();
+        let loaded: Camera = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, c);
+        assert_eq!(
+            loaded.ray_for_pixel(100, 50).direction,
+            c.ray_for_pixel(100, 50).direction
+        );
+    }
 }
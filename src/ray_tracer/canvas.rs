@@ -1,27 +1,56 @@
-use std::{fs::File, io::Write};
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::ray_tracer::{colors::Color, error::RayTracerError};
+
+/// Errors from reading a PPM image.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PpmError {
+    /// The file doesn't start with `P3`.
+    BadMagicNumber,
+    /// A value in the file isn't a non-negative integer.
+    InvalidNumber(String),
+    /// The file ends before all the pixels are read.
+    MissingData,
+    /// The maximum color value is 0.
+    InvalidMaxValue,
+}
+
+impl fmt::Display for PpmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PpmError::BadMagicNumber => write!(f, "not a plain PPM (P3) file"),
+            PpmError::InvalidNumber(value) => write!(f, "invalid number '{value}'"),
+            PpmError::MissingData => write!(f, "unexpected end of file"),
+            PpmError::InvalidMaxValue => write!(f, "the maximum color value must be positive"),
+        }
+    }
+}
 
-use crate::ray_tracer::colors::Color;
+impl std::error::Error for PpmError {}
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Canvas {
     pixels: Vec<Vec<Color>>,
     width: usize,
     height: usize,
+    #[cfg_attr(feature = "serde", serde(skip))]
     ppm: String,
 }
 
 impl Canvas {
     pub fn new(width: usize, height: usize) -> Self {
         // Instantiate the canvas with defined dimensions and empty fields
-        // Calculate a string size for the image.
-        // Each pixel has 3 colors, each with up to 5 characters
-        // Let's also add a little bit of overhead
-        let strlen = (width * height * 3 * 5) + 128;
         Canvas {
             pixels: vec![vec![Color::new(0.0, 0.0, 0.0); width]; height],
             width,
             height,
-            ppm: String::with_capacity(strlen),
+            ppm: String::new(),
         }
     }
     pub fn pixel_at(&self, x: usize, y: usize) -> &Color {
@@ -35,47 +64,104 @@ impl Canvas {
         *pixel = color;
     }
 
-    /// Save the canvas to a file
-    fn canvas_to_ppm(&mut self) {
+    /// Renders the canvas as a plain (P3) PPM image.
+    /// Color values are clamped to [0, 1] and scaled to 0-255, and lines are wrapped to stay
+    /// within the 70 characters the format allows.
+    pub fn to_ppm(&self) -> String {
+        self.to_ppm_gamma(1.0)
+    }
+
+    /// Like [`Canvas::to_ppm`], but gamma corrects each clamped channel with
+    /// `channel.powf(1.0 / gamma)` first. A gamma of 2.2 brightens the linear light the
+    /// renderer produces to about what a screen expects.
+    pub fn to_ppm_gamma(&self, gamma: f64) -> String {
+        let inverse_gamma = 1.0 / gamma;
+
+        // Each pixel has 3 colors, each with up to 4 characters
+        // Let's also add a little bit of overhead
+        let mut ppm = String::with_capacity((self.width * self.height * 3 * 4) + 128);
+
         // Set up the PPM header
-        self.ppm.push_str("P3\n");
-        self.ppm
-            .push_str(format!("{} {}\n", self.width, self.height).as_str());
-        self.ppm.push_str("255\n");
-
-        let mut _color_str_array: [String; 3] = [
-            String::with_capacity(5),
-            String::with_capacity(5),
-            String::with_capacity(5),
-        ];
+        ppm.push_str("P3\n");
+        ppm.push_str(format!("{} {}\n", self.width, self.height).as_str());
+        ppm.push_str("255\n");
 
         // Insert pixel elements from the canvas
         for row in &self.pixels {
             let mut num_chars = 0;
             for column in row {
-                _color_str_array = [
-                    format!("{} ", Color::float_to_u8(&column.red)),
-                    format!("{} ", Color::float_to_u8(&column.green)),
-                    format!("{} ", Color::float_to_u8(&column.blue)),
-                ];
+                let color_str_array = gamma_corrected_rgb8(column, inverse_gamma)
+                    .map(|channel| format!("{} ", channel));
 
                 // PPM lines should stop at 70 characters
-                for color_pixel in &_color_str_array {
+                for color_pixel in &color_str_array {
                     if num_chars + color_pixel.len() >= 70 {
                         // Replace trailing whitespace with a newline
-                        self.ppm.pop().unwrap();
-                        self.ppm.push('\n');
+                        ppm.pop().unwrap();
+                        ppm.push('\n');
                         num_chars = 0;
                     }
-                    self.ppm.push_str(color_pixel.as_str());
+                    ppm.push_str(color_pixel.as_str());
                     num_chars += color_pixel.len();
                 }
             }
 
             // Replace trailing whitespace with a newline
-            self.ppm.pop().unwrap();
-            self.ppm.push('\n');
+            ppm.pop().unwrap();
+            ppm.push('\n');
+        }
+        ppm
+    }
+
+    /// Reads a plain (P3) PPM image.
+    /// Comments and any amount of whitespace are allowed between values, and the color values
+    /// are scaled by the maximum value declared in the header.
+    pub fn from_ppm(contents: &str) -> Result<Canvas, PpmError> {
+        // The whole file is whitespace separated tokens, minus the comments
+        let mut tokens = contents
+            .lines()
+            .map(|line| line.split('#').next().unwrap())
+            .flat_map(str::split_whitespace);
+        if tokens.next() != Some("P3") {
+            return Err(PpmError::BadMagicNumber);
+        }
+
+        let mut values = tokens.map(|value| {
+            value
+                .parse::<usize>()
+                .map_err(|_| PpmError::InvalidNumber(value.to_string()))
+        });
+        let width = values.next().unwrap_or(Err(PpmError::MissingData))?;
+        let height = values.next().unwrap_or(Err(PpmError::MissingData))?;
+        let max_value = values.next().unwrap_or(Err(PpmError::MissingData))?;
+        if max_value == 0 {
+            return Err(PpmError::InvalidMaxValue);
+        }
+        let scale = max_value as f64;
+
+        // Make sure the pixels are actually there before allocating room for them
+        let mut values = values.collect::<Vec<_>>().into_iter();
+        let needed = width.checked_mul(height).and_then(|n| n.checked_mul(3));
+        if needed.is_none_or(|n| n > values.len()) {
+            return Err(PpmError::MissingData);
+        }
+        let mut next_value = || values.next().unwrap_or(Err(PpmError::MissingData));
+
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let red = next_value()? as f64 / scale;
+                let green = next_value()? as f64 / scale;
+                let blue = next_value()? as f64 / scale;
+                canvas.write_pixel(x, y, Color::new(red, green, blue));
+            }
         }
+        Ok(canvas)
+    }
+
+    /// Save the canvas to a file
+    fn canvas_to_ppm(&mut self) {
+        self.ppm = self.to_ppm();
     }
 
     pub fn fill(&mut self, color: Color) {
@@ -86,6 +172,186 @@ impl Canvas {
         }
     }
 
+    /// Counts the pixels of each color channel into `bins` equally wide bins.
+    /// Values are clamped to [0, 1] first, so out of range values end up in the outermost bins.
+    /// Returns the counts for red, green and blue, in that order.
+    pub fn histogram(&self, bins: usize) -> [Vec<usize>; 3] {
+        let mut histogram = [vec![0; bins], vec![0; bins], vec![0; bins]];
+        if bins == 0 {
+            return histogram;
+        }
+
+        for pixel in self.pixels.iter().flatten() {
+            for (channel, value) in [pixel.red, pixel.green, pixel.blue].iter().enumerate() {
+                let bin = (value.clamp(0.0, 1.0) * bins as f64) as usize;
+                histogram[channel][bin.min(bins - 1)] += 1;
+            }
+        }
+        histogram
+    }
+
+    /// Stretches each color channel so its darkest value becomes 0 and its brightest becomes 1.
+    /// Channels where every pixel has the same value are left alone.
+    pub fn auto_levels(&mut self) {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for pixel in self.pixels.iter().flatten() {
+            for (channel, value) in [pixel.red, pixel.green, pixel.blue].iter().enumerate() {
+                min[channel] = min[channel].min(*value);
+                max[channel] = max[channel].max(*value);
+            }
+        }
+
+        let stretch = |value: f64, channel: usize| {
+            let range = max[channel] - min[channel];
+            if range > 0.0 {
+                (value - min[channel]) / range
+            } else {
+                value
+            }
+        };
+        for pixel in self.pixels.iter_mut().flatten() {
+            pixel.red = stretch(pixel.red, 0);
+            pixel.green = stretch(pixel.green, 1);
+            pixel.blue = stretch(pixel.blue, 2);
+        }
+    }
+
+    /// Maps every channel `c` to `c / (1 + c)`, the simple Reinhard operator.
+    /// Bright values are squeezed below 1 instead of clipping to flat white when they're turned
+    /// into 8-bit values, while dark values are nearly unchanged. Negative values become 0.
+    pub fn tone_map_reinhard(&self) -> Canvas {
+        let reinhard = |value: f64| {
+            let value = value.max(0.0);
+            value / (1.0 + value)
+        };
+        let mut mapped = self.clone();
+        for pixel in mapped.pixels.iter_mut().flatten() {
+            *pixel = Color::new(
+                reinhard(pixel.red),
+                reinhard(pixel.green),
+                reinhard(pixel.blue),
+            );
+        }
+        mapped
+    }
+
+    /// Arranges equally sized tiles into a grid with `cols` columns, left to right and top to
+    /// bottom, with `gap` pixels of `background` between neighbouring tiles. Returns an error if
+    /// `cols` is 0 or if the tiles don't all have the same dimensions.
+    pub fn contact_sheet(
+        tiles: &[Canvas],
+        cols: usize,
+        gap: usize,
+        background: Color,
+    ) -> Result<Canvas, RayTracerError> {
+        if cols == 0 {
+            return Err(RayTracerError::InvalidDimensions);
+        }
+        let Some(first) = tiles.first() else {
+            return Ok(Canvas::new(0, 0));
+        };
+        let (tile_width, tile_height) = (first.width, first.height);
+        if tiles
+            .iter()
+            .any(|t| t.width != tile_width || t.height != tile_height)
+        {
+            return Err(RayTracerError::InvalidDimensions);
+        }
+
+        let cols = cols.min(tiles.len());
+        let rows = tiles.len().div_ceil(cols);
+        let mut sheet = Canvas::new(
+            cols * tile_width + (cols - 1) * gap,
+            rows * tile_height + (rows - 1) * gap,
+        );
+        sheet.fill(background);
+
+        for (i, tile) in tiles.iter().enumerate() {
+            let x_offset = (i % cols) * (tile_width + gap);
+            let y_offset = (i / cols) * (tile_height + gap);
+            for y in 0..tile_height {
+                for x in 0..tile_width {
+                    sheet.write_pixel(x_offset + x, y_offset + y, *tile.pixel_at(x, y));
+                }
+            }
+        }
+        Ok(sheet)
+    }
+
+    /// Copies the `w` by `h` region with its top left corner at (`x`, `y`) into a new canvas.
+    /// The region is clamped to the canvas, so the result may be smaller than asked for.
+    pub fn crop(&self, x: usize, y: usize, w: usize, h: usize) -> Canvas {
+        let x = x.min(self.width);
+        let y = y.min(self.height);
+        let w = w.min(self.width - x);
+        let h = h.min(self.height - y);
+
+        let mut cropped = Canvas::new(w, h);
+        for (row, pixels) in self.pixels[y..y + h].iter().enumerate() {
+            cropped.pixels[row].copy_from_slice(&pixels[x..x + w]);
+        }
+        cropped
+    }
+
+    /// Copies `other` onto this canvas with its top left corner at (`x`, `y`).
+    /// Pixels that would land outside this canvas are left out.
+    pub fn paste(&mut self, other: &Canvas, x: usize, y: usize) {
+        let w = other.width.min(self.width.saturating_sub(x));
+        let h = other.height.min(self.height.saturating_sub(y));
+        for row in 0..h {
+            self.pixels[y + row][x..x + w].copy_from_slice(&other.pixels[row][..w]);
+        }
+    }
+
+    /// The largest difference between any channel of any pixel and the same one in `other`.
+    /// 0.0 means the images are identical, which makes it handy for comparing a render against
+    /// a known good image with some tolerance. Channels that are NaN in either image count as
+    /// infinitely different, so a broken render never passes as a match.
+    ///
+    /// # Panics
+    /// Panics if the canvases don't have the same dimensions.
+    pub fn max_channel_diff(&self, other: &Canvas) -> f64 {
+        self.diff_image(other)
+            .pixels
+            .iter()
+            .flatten()
+            .flat_map(|pixel| [pixel.red, pixel.green, pixel.blue])
+            .fold(0.0, |max, diff| {
+                if diff.is_nan() {
+                    f64::INFINITY
+                } else {
+                    max.max(diff)
+                }
+            })
+    }
+
+    /// A canvas where every channel is the absolute difference between this canvas and `other`.
+    /// Identical pixels are black, so whatever changed stands out.
+    ///
+    /// # Panics
+    /// Panics if the canvases don't have the same dimensions.
+    pub fn diff_image(&self, other: &Canvas) -> Canvas {
+        assert!(
+            self.width == other.width && self.height == other.height,
+            "Only canvases with the same dimensions can be compared"
+        );
+        let mut diff = Canvas::new(self.width, self.height);
+        let pairs = self
+            .pixels
+            .iter()
+            .flatten()
+            .zip(other.pixels.iter().flatten());
+        for (pixel, (a, b)) in diff.pixels.iter_mut().flatten().zip(pairs) {
+            *pixel = Color::new(
+                (a.red - b.red).abs(),
+                (a.green - b.green).abs(),
+                (a.blue - b.blue).abs(),
+            );
+        }
+        diff
+    }
+
     pub fn width(&self) -> usize {
         self.width
     }
@@ -94,6 +360,40 @@ impl Canvas {
         self.height
     }
 
+    /// Writes the canvas to `path` as a PNG image, with colors clamped like in
+    /// [`Canvas::to_ppm`].
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        self.save_png_gamma(path, 1.0)
+    }
+
+    /// Like [`Canvas::save_png`], but gamma corrects the colors like [`Canvas::to_ppm_gamma`].
+    pub fn save_png_gamma(&self, path: &Path, gamma: f64) -> io::Result<()> {
+        let inverse_gamma = 1.0 / gamma;
+        let image = image::RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            image::Rgb(gamma_corrected_rgb8(
+                self.pixel_at(x as usize, y as usize),
+                inverse_gamma,
+            ))
+        });
+        image
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(io::Error::other)
+    }
+
+    /// Packs the pixels row by row, top to bottom, as 4 bytes of red, green, blue and alpha,
+    /// which is the layout of e.g. an HTML canvas' `ImageData`. Colors are clamped and gamma
+    /// corrected like in [`Canvas::to_ppm_gamma`]; a gamma of 1.0 leaves them linear. Alpha is
+    /// always 255.
+    pub fn to_rgba8(&self, gamma: f64) -> Vec<u8> {
+        let inverse_gamma = 1.0 / gamma;
+        let mut bytes = Vec::with_capacity(self.width * self.height * 4);
+        for pixel in self.pixels.iter().flatten() {
+            bytes.extend(gamma_corrected_rgb8(pixel, inverse_gamma));
+            bytes.push(u8::MAX);
+        }
+        bytes
+    }
+
     pub fn save(&mut self, file: &str) {
         // Save to a file
         self.canvas_to_ppm();
@@ -102,6 +402,24 @@ impl Canvas {
     }
 }
 
+/// Clamps the color, raises each channel to `inverse_gamma` and scales it to 0-255.
+fn gamma_corrected_rgb8(color: &Color, inverse_gamma: f64) -> [u8; 3] {
+    let c = color.clamp();
+    Color::new(
+        c.red.powf(inverse_gamma),
+        c.green.powf(inverse_gamma),
+        c.blue.powf(inverse_gamma),
+    )
+    .to_rgb8()
+}
+
+/// Canvases are equal when their pixels are; the PPM buffer is only scratch space for saving.
+impl PartialEq for Canvas {
+    fn eq(&self, other: &Self) -> bool {
+        self.width == other.width && self.height == other.height && self.pixels == other.pixels
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -137,6 +455,88 @@ mod tests {
         assert_eq!(c.pixel_at(2, 3), red);
     }
 
+    #[test]
+    fn histogram_bins_sum_to_the_pixel_count() {
+        let mut c = Canvas::new(4, 3);
+        c.write_pixel(0, 0, Color::new(1.0, 0.5, 0.25));
+        c.write_pixel(1, 2, Color::new(1.5, -0.5, 0.75));
+
+        let histogram = c.histogram(4);
+        for channel in &histogram {
+            assert_eq!(channel.len(), 4);
+            assert_eq!(channel.iter().sum::<usize>(), 12);
+        }
+        assert_eq!(histogram[0], vec![10, 0, 0, 2]);
+        assert_eq!(histogram[1], vec![11, 0, 1, 0]);
+        assert_eq!(histogram[2], vec![10, 1, 0, 1]);
+    }
+
+    #[test]
+    fn auto_levels_stretches_a_low_contrast_canvas() {
+        let mut c = Canvas::new(2, 2);
+        c.fill(Color::new(0.4, 0.3, 0.5));
+        c.write_pixel(0, 0, Color::new(0.2, 0.3, 0.4));
+        c.write_pixel(1, 1, Color::new(0.6, 0.5, 0.45));
+
+        c.auto_levels();
+        assert_eq!(c.pixel_at(0, 0), Color::new(0.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(1, 1), Color::new(1.0, 1.0, 0.5));
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.5, 0.0, 1.0));
+    }
+
+    #[test]
+    fn auto_levels_on_a_full_range_canvas_is_a_no_op() {
+        let mut c = Canvas::new(3, 1);
+        c.write_pixel(0, 0, Color::new(0.0, 0.0, 0.0));
+        c.write_pixel(1, 0, Color::new(0.25, 0.5, 0.75));
+        c.write_pixel(2, 0, Color::new(1.0, 1.0, 1.0));
+
+        let before = c.clone();
+        c.auto_levels();
+        for x in 0..3 {
+            assert_eq!(c.pixel_at(x, 0), before.pixel_at(x, 0));
+        }
+    }
+
+    #[test]
+    fn packing_tiles_into_a_contact_sheet() {
+        let background = Color::new(0.2, 0.2, 0.2);
+        let tiles: Vec<Canvas> = (0..4)
+            .map(|i| {
+                let mut tile = Canvas::new(2, 2);
+                tile.fill(Color::new(i as f64 / 4.0, 1.0, 0.0));
+                tile.write_pixel(1, 1, Color::new(0.0, 0.0, 1.0));
+                tile
+            })
+            .collect();
+
+        let sheet = Canvas::contact_sheet(&tiles, 2, 1, background).unwrap();
+        assert_eq!(sheet.width(), 5);
+        assert_eq!(sheet.height(), 5);
+
+        // The fourth tile sits in the second column of the second row
+        assert_eq!(sheet.pixel_at(3, 3), Color::new(0.75, 1.0, 0.0));
+        assert_eq!(sheet.pixel_at(4, 4), Color::new(0.0, 0.0, 1.0));
+        assert_eq!(sheet.pixel_at(3, 0), Color::new(0.25, 1.0, 0.0));
+        // Gaps are filled with the background
+        assert_eq!(sheet.pixel_at(2, 0), background);
+        assert_eq!(sheet.pixel_at(0, 2), background);
+    }
+
+    #[test]
+    fn contact_sheet_tiles_must_share_dimensions() {
+        let black = Color::new(0.0, 0.0, 0.0);
+        let tiles = [Canvas::new(2, 2), Canvas::new(3, 2)];
+        assert_eq!(
+            Canvas::contact_sheet(&tiles, 2, 1, black).unwrap_err(),
+            RayTracerError::InvalidDimensions
+        );
+        assert_eq!(
+            Canvas::contact_sheet(&tiles[..1], 0, 1, black).unwrap_err(),
+            RayTracerError::InvalidDimensions
+        );
+    }
+
     #[test]
     fn constructing_a_ppm_header() {
         let mut c = Canvas::new(5, 3);
@@ -203,6 +603,145 @@ mod tests {
         }
     }
 
+    #[test]
+    fn exporting_a_canvas_as_ppm() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, -0.5));
+
+        let ppm = c.to_ppm();
+        let mut lines = ppm.lines();
+        assert_eq!(lines.next(), Some("P3"));
+        assert_eq!(lines.next(), Some("2 1"));
+        assert_eq!(lines.next(), Some("255"));
+        assert_eq!(lines.next(), Some("255 0 0 0 0 0"));
+        assert_eq!(lines.next(), None);
+    }
+
+    #[test]
+    fn exporting_with_a_gamma_of_one_matches_the_uncorrected_output() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, -0.5));
+        c.write_pixel(1, 0, Color::new(0.25, 0.5, 0.75));
+        c.write_pixel(2, 1, Color::new(0.1, 0.33, 0.9));
+
+        assert_eq!(c.to_ppm_gamma(1.0), c.to_ppm());
+    }
+
+    #[test]
+    fn exporting_with_gamma_correction_brightens_midtones() {
+        let mut c = Canvas::new(1, 1);
+        c.write_pixel(0, 0, Color::new(0.0, 0.5, 1.0));
+
+        let ppm = c.to_ppm_gamma(2.2);
+        // 0.5^(1/2.2) = 0.7297
+        assert_eq!(ppm.lines().nth(3), Some("0 186 255"));
+    }
+
+    #[test]
+    fn reading_a_file_with_the_wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0\n";
+        assert_eq!(Canvas::from_ppm(ppm), Err(PpmError::BadMagicNumber));
+    }
+
+    #[test]
+    fn reading_a_ppm_returns_a_canvas_of_the_right_size() {
+        let mut ppm = "P3\n10 2\n255\n".to_string();
+        for _ in 0..2 {
+            ppm.push_str("0 0 0  0 0 0  0 0 0  0 0 0  0 0 0\n");
+            ppm.push_str("0 0 0  0 0 0  0 0 0  0 0 0  0 0 0\n");
+        }
+        let c = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(c.width(), 10);
+        assert_eq!(c.height(), 2);
+    }
+
+    #[test]
+    fn reading_pixel_data_from_a_ppm_file() {
+        let ppm = "P3\n4 3\n255\n\
+            255 127 0  0 127 255  127 255 0  255 255 255\n\
+            0 0 0  255 0 0  0 255 0  0 0 255\n\
+            255 255 0  0 255 255  255 0 255  127 127 127\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        let expected = [
+            (0, 0, Color::new(1.0, 0.49804, 0.0)),
+            (1, 0, Color::new(0.0, 0.49804, 1.0)),
+            (2, 0, Color::new(0.49804, 1.0, 0.0)),
+            (3, 0, Color::new(1.0, 1.0, 1.0)),
+            (0, 1, Color::new(0.0, 0.0, 0.0)),
+            (1, 1, Color::new(1.0, 0.0, 0.0)),
+            (2, 1, Color::new(0.0, 1.0, 0.0)),
+            (3, 1, Color::new(0.0, 0.0, 1.0)),
+            (0, 2, Color::new(1.0, 1.0, 0.0)),
+            (1, 2, Color::new(0.0, 1.0, 1.0)),
+            (2, 2, Color::new(1.0, 0.0, 1.0)),
+            (3, 2, Color::new(0.49804, 0.49804, 0.49804)),
+        ];
+        for (x, y, color) in expected {
+            assert_eq!(c.pixel_at(x, y), color);
+        }
+    }
+
+    #[test]
+    fn ppm_parsing_ignores_comment_lines() {
+        let ppm = "P3\n# this is a comment\n2 1\n# this, too\n255\n# another comment\n\
+            255 255 255\n# oh, no, comments in the pixel data!\n255 0 255\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.0, 1.0, 1.0));
+        assert_eq!(c.pixel_at(1, 0), Color::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn ppm_parsing_allows_an_rgb_triple_to_span_lines() {
+        let ppm = "P3\n1 1\n255\n51\n153\n\n204\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn ppm_parsing_respects_the_scale_setting() {
+        let ppm = "P3\n2 2\n100\n100 100 100  50 50 50\n75 50 25  0 0 0\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(c.pixel_at(0, 1), Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn ppm_parsing_rejects_bad_data() {
+        assert_eq!(
+            Canvas::from_ppm("P3\n1 1\n255\n0 red 0\n"),
+            Err(PpmError::InvalidNumber("red".to_string()))
+        );
+        assert_eq!(
+            Canvas::from_ppm("P3\n2 1\n255\n0 0 0\n"),
+            Err(PpmError::MissingData)
+        );
+        assert_eq!(
+            Canvas::from_ppm("P3\n100000000 100000000\n255\n0 0 0\n"),
+            Err(PpmError::MissingData)
+        );
+        assert_eq!(
+            Canvas::from_ppm(&format!("P3\n{} 2\n255\n0 0 0\n", usize::MAX)),
+            Err(PpmError::MissingData)
+        );
+    }
+
+    #[test]
+    fn ppm_parsing_reads_the_magic_number_like_any_other_value() {
+        let c = Canvas::from_ppm("P3 2 1 255 255 0 0 0 0 255").unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(1, 0), Color::new(0.0, 0.0, 1.0));
+
+        let c = Canvas::from_ppm("P3 # plain PPM\n1 1\n255\n51 153 204\n").unwrap();
+        assert_eq!(c.pixel_at(0, 0), Color::new(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn a_canvas_survives_a_round_trip_through_ppm() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(1, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(2, 1, Color::new(0.0, 0.0, 1.0));
+        assert_eq!(Canvas::from_ppm(&c.to_ppm()).unwrap(), c);
+    }
+
     #[test]
     fn ppm_files_are_terminated_by_a_newline_character() {
         let mut c = Canvas::new(5, 3);
@@ -210,4 +749,141 @@ mod tests {
 
         assert!(c.ppm.as_str().ends_with('\n'));
     }
+
+    fn numbered_canvas(width: usize, height: usize) -> Canvas {
+        let mut c = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                c.write_pixel(x, y, Color::new(x as f64, y as f64, 0.0));
+            }
+        }
+        c
+    }
+
+    #[test]
+    fn cropping_the_center_of_a_canvas() {
+        let c = numbered_canvas(5, 4);
+        let center = c.crop(1, 1, 3, 2);
+        assert_eq!(center.width(), 3);
+        assert_eq!(center.height(), 2);
+        assert_eq!(center.pixel_at(0, 0), &Color::new(1.0, 1.0, 0.0));
+        assert_eq!(center.pixel_at(2, 1), &Color::new(3.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn cropping_past_the_edge_is_clamped() {
+        let c = numbered_canvas(5, 4);
+        let corner = c.crop(3, 2, 10, 10);
+        assert_eq!(corner.width(), 2);
+        assert_eq!(corner.height(), 2);
+        assert_eq!(corner.pixel_at(1, 1), &Color::new(4.0, 3.0, 0.0));
+
+        let outside = c.crop(7, 7, 2, 2);
+        assert_eq!(outside.width(), 0);
+        assert_eq!(outside.height(), 0);
+    }
+
+    #[test]
+    fn pasting_a_cropped_region_back_leaves_the_canvas_unchanged() {
+        let original = numbered_canvas(5, 4);
+        let center = original.crop(1, 1, 3, 2);
+
+        let mut c = original.clone();
+        c.fill(Color::new(0.0, 0.0, 0.0));
+        c.paste(&center, 1, 1);
+        assert_eq!(c.pixel_at(2, 2), original.pixel_at(2, 2));
+        assert_eq!(c.pixel_at(0, 0), &Color::new(0.0, 0.0, 0.0));
+
+        let mut c = original.clone();
+        c.paste(&center, 1, 1);
+        assert_eq!(c, original);
+    }
+
+    #[test]
+    fn pasting_past_the_edge_is_clipped() {
+        let mut c = Canvas::new(3, 3);
+        c.paste(&numbered_canvas(4, 4), 2, 1);
+        assert_eq!(c.pixel_at(2, 1), &Color::new(0.0, 0.0, 0.0));
+        assert_eq!(c.pixel_at(2, 2), &Color::new(0.0, 1.0, 0.0));
+        c.paste(&numbered_canvas(2, 2), 5, 5);
+    }
+
+    #[test]
+    fn reinhard_tone_mapping_compresses_bright_values() {
+        let mut c = Canvas::new(2, 1);
+        c.write_pixel(0, 0, Color::new(3.0, 1.0, 0.0));
+        c.write_pixel(1, 0, Color::new(-1.0, 0.25, 9.0));
+        let mapped = c.tone_map_reinhard();
+        assert_eq!(mapped.pixel_at(0, 0), &Color::new(0.75, 0.5, 0.0));
+        assert_eq!(mapped.pixel_at(1, 0), &Color::new(0.0, 0.2, 0.9));
+        // The original is left alone
+        assert_eq!(c.pixel_at(0, 0), &Color::new(3.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn packing_a_canvas_as_rgba_bytes() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(1, 0, Color::new(1.5, 0.0, -0.5));
+        c.write_pixel(0, 1, Color::new(0.25, 0.5, 1.0));
+        let bytes = c.to_rgba8(1.0);
+        assert_eq!(bytes.len(), 2 * 2 * 4);
+        assert_eq!(
+            bytes,
+            [0, 0, 0, 255, 255, 0, 0, 255, 64, 128, 255, 255, 0, 0, 0, 255]
+        );
+
+        let bytes = c.to_rgba8(2.0);
+        assert_eq!(&bytes[8..12], &[128, 180, 255, 255]);
+    }
+
+    #[test]
+    fn comparing_canvases() {
+        let mut a = Canvas::new(4, 3);
+        a.fill(Color::new(0.5, 0.5, 0.5));
+        let mut b = a.clone();
+        assert_eq!(a.max_channel_diff(&b), 0.0);
+        assert_eq!(a.diff_image(&b), Canvas::new(4, 3));
+
+        b.write_pixel(2, 1, Color::new(0.5, 0.25, 0.75));
+        assert_eq!(a.max_channel_diff(&b), 0.25);
+        let diff = a.diff_image(&b);
+        assert_eq!(*diff.pixel_at(2, 1), Color::new(0.0, 0.25, 0.25));
+        let mut others = diff.clone();
+        others.write_pixel(2, 1, Color::new(0.0, 0.0, 0.0));
+        assert_eq!(others, Canvas::new(4, 3));
+    }
+    #[test]
+    fn nan_channels_are_infinitely_different() {
+        let a = Canvas::new(2, 2);
+        let mut b = a.clone();
+        b.write_pixel(1, 0, Color::new(0.0, f64::NAN, 0.0));
+        assert_eq!(a.max_channel_diff(&b), f64::INFINITY);
+        assert_eq!(b.max_channel_diff(&b), f64::INFINITY);
+
+        let mut c = a.clone();
+        c.fill(Color::new(f64::NAN, f64::NAN, f64::NAN));
+        assert_eq!(c.max_channel_diff(&a), f64::INFINITY);
+    }
+    #[test]
+    #[should_panic(expected = "same dimensions")]
+    fn comparing_canvases_of_different_sizes_panics() {
+        Canvas::new(2, 2).max_channel_diff(&Canvas::new(2, 3));
+    }
+
+    #[test]
+    fn saving_a_canvas_as_png() {
+        let mut c = Canvas::new(4, 3);
+        c.write_pixel(0, 0, Color::new(1.5, 0.0, -0.5));
+        c.write_pixel(3, 2, Color::new(0.0, 0.8, 0.6));
+        let path = std::env::temp_dir().join(format!("canvas_save_png_{}.png", std::process::id()));
+
+        c.save_png(&path).unwrap();
+        let png = image::open(&path).unwrap().to_rgb8();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(png.dimensions(), (4, 3));
+        assert_eq!(png.get_pixel(0, 0).0, [255, 0, 0]);
+        assert_eq!(png.get_pixel(3, 2).0, [0, 204, 153]);
+        assert_eq!(png.get_pixel(1, 1).0, [0, 0, 0]);
+    }
 }
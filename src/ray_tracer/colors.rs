@@ -1,14 +1,66 @@
-use std::ops::{Add, Mul, Sub};
+use std::{
+    fmt,
+    ops::{Add, Mul, Sub},
+};
 
 use crate::ray_tracer::utils::is_float_equal;
 
+/// Errors from parsing a color.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorError {
+    /// The hex string doesn't have exactly 6 digits.
+    InvalidLength(usize),
+    /// The hex string contains something that isn't a hex digit.
+    InvalidDigit(String),
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorError::InvalidLength(len) => {
+                write!(f, "expected 6 hex digits, got {len}")
+            }
+            ColorError::InvalidDigit(value) => write!(f, "invalid hex color '{value}'"),
+        }
+    }
+}
+
+impl std::error::Error for ColorError {}
+
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color {
     pub red: f64,
     pub green: f64,
     pub blue: f64,
 }
 impl Color {
+    pub const BLACK: Color = Color {
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+    };
+    pub const WHITE: Color = Color {
+        red: 1.0,
+        green: 1.0,
+        blue: 1.0,
+    };
+    pub const RED: Color = Color {
+        red: 1.0,
+        green: 0.0,
+        blue: 0.0,
+    };
+    pub const GREEN: Color = Color {
+        red: 0.0,
+        green: 1.0,
+        blue: 0.0,
+    };
+    pub const BLUE: Color = Color {
+        red: 0.0,
+        green: 0.0,
+        blue: 1.0,
+    };
+
     pub fn new(r: f64, g: f64, b: f64) -> Self {
         Color {
             red: r,
@@ -17,31 +69,80 @@ impl Color {
         }
     }
 
+    /// Parses a color written as 6 hex digits, like `"#ff8800"`. The `#` is optional.
+    pub fn from_hex(hex: &str) -> Result<Color, ColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if digits.len() != 6 {
+            return Err(ColorError::InvalidLength(digits.len()));
+        }
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorError::InvalidDigit(hex.to_string()));
+        }
+
+        let channel = |start: usize| {
+            let value = u8::from_str_radix(&digits[start..start + 2], 16).unwrap();
+            value as f64 / 255.0
+        };
+
+        Ok(Color::new(channel(0), channel(2), channel(4)))
+    }
+
     pub fn float_to_u8(color: &f64) -> u8 {
         let col = 255_f64 * *color;
         col.ceil() as u8
     }
-}
 
-impl PartialEq<Color> for Color {
-    fn eq(&self, other: &Color) -> bool {
+    /// Squashes each channel into [0, 1].
+    pub fn clamp(&self) -> Color {
+        Color {
+            red: self.red.clamp(0.0, 1.0),
+            green: self.green.clamp(0.0, 1.0),
+            blue: self.blue.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Checks whether every channel is within [`EPSILON`](crate::ray_tracer::utils::EPSILON) of
+    /// `other`'s. This is what `==` uses.
+    ///
+    /// ```
+    /// use ray_tracer_rust::ray_tracer::colors::Color;
+    ///
+    /// let color_a = Color::new(0.1 + 0.2, 0.5, 1.0);
+    /// let color_b = Color::new(0.3, 0.5, 1.0);
+    /// assert!(color_a.approx_eq(&color_b));
+    /// assert!(!color_a.approx_eq(&Color::new(0.31, 0.5, 1.0)));
+    /// ```
+    pub fn approx_eq(&self, other: &Color) -> bool {
         is_float_equal(&self.red, other.red)
             && is_float_equal(&self.green, other.green)
             && is_float_equal(&self.blue, other.blue)
     }
+
+    /// Interpolates channel by channel between `self` at `t = 0` and `other` at `t = 1`.
+    pub fn lerp(&self, other: &Color, t: f64) -> Color {
+        *self + (*other - *self) * t
+    }
+
+    /// Converts to 8-bit channels, clamping first and rounding to the nearest value.
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        let c = self.clamp();
+        [c.red, c.green, c.blue].map(|channel| (channel * 255.0).round() as u8)
+    }
+}
+
+impl PartialEq<Color> for Color {
+    fn eq(&self, other: &Color) -> bool {
+        self.approx_eq(other)
+    }
 }
 impl PartialEq<Color> for &Color {
     fn eq(&self, other: &Color) -> bool {
-        is_float_equal(&self.red, other.red)
-            && is_float_equal(&self.green, other.green)
-            && is_float_equal(&self.blue, other.blue)
+        self.approx_eq(other)
     }
 }
 impl PartialEq<&Color> for Color {
     fn eq(&self, other: &&Color) -> bool {
-        is_float_equal(&self.red, other.red)
-            && is_float_equal(&self.green, other.green)
-            && is_float_equal(&self.blue, other.blue)
+        self.approx_eq(other)
     }
 }
 impl Add<Color> for Color {
@@ -101,6 +202,48 @@ mod tests {
         assert!(is_float_equal(&c.blue, 1.7));
     }
 
+    #[test]
+    fn interpolating_between_colors() {
+        let a = Color::new(1.0, 0.0, 0.2);
+        let b = Color::new(0.0, 1.0, 0.6);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Color::new(0.5, 0.5, 0.4));
+    }
+
+    #[test]
+    fn clamping_a_color_squashes_each_channel() {
+        let c = Color::new(-0.5, 0.4, 1.7);
+        assert_eq!(c.clamp(), Color::new(0.0, 0.4, 1.0));
+    }
+
+    #[test]
+    fn converting_a_color_to_8_bit_channels() {
+        assert_eq!(Color::new(-0.5, 0.5, 1.5).to_rgb8(), [0, 128, 255]);
+        assert_eq!(Color::new(0.0, 0.8, 0.6).to_rgb8(), [0, 204, 153]);
+    }
+
+    #[test]
+    fn parsing_colors_from_hex() {
+        assert_eq!(Color::from_hex("#000000"), Ok(Color::BLACK));
+        assert_eq!(Color::from_hex("#ffffff"), Ok(Color::WHITE));
+        assert_eq!(Color::from_hex("FF0000"), Ok(Color::RED));
+        assert_eq!(Color::from_hex("#ff8800").unwrap().to_rgb8(), [255, 136, 0]);
+    }
+
+    #[test]
+    fn parsing_malformed_hex_colors() {
+        assert_eq!(Color::from_hex("#fff"), Err(ColorError::InvalidLength(3)));
+        assert_eq!(
+            Color::from_hex("#ff88zz"),
+            Err(ColorError::InvalidDigit(String::from("#ff88zz")))
+        );
+        assert_eq!(
+            Color::from_hex("#+f8800"),
+            Err(ColorError::InvalidDigit(String::from("#+f8800")))
+        );
+    }
+
     #[test]
     fn adding_colors_adds_each_element() {
         let c1 = Color::new(0.9, 0.6, 0.75);
@@ -0,0 +1,105 @@
+use std::fmt;
+
+#[cfg(feature = "scene")]
+use crate::ray_tracer::scene::SceneError;
+use crate::ray_tracer::{canvas::PpmError, colors::ColorError, matrices::MatrixError};
+
+/// Errors that can occur while setting up or rendering a scene.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RayTracerError {
+    /// A transformation matrix can't be inverted, e.g. a scaling by zero.
+    SingularTransform,
+    /// An image or matrix was given a size it can't have.
+    InvalidDimensions,
+    /// Input data couldn't be parsed. Contains a description of what went wrong.
+    ParseFailure(String),
+    /// A value that should be a number was NaN.
+    NaNInput,
+    /// A camera's field of view isn't strictly between 0 and π radians.
+    InvalidFieldOfView,
+}
+
+impl fmt::Display for RayTracerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RayTracerError::SingularTransform => {
+                write!(f, "transformation matrix is not invertible")
+            }
+            RayTracerError::InvalidDimensions => write!(f, "invalid dimensions"),
+            RayTracerError::ParseFailure(msg) => write!(f, "failed to parse input: {msg}"),
+            RayTracerError::NaNInput => write!(f, "input contains NaN"),
+            RayTracerError::InvalidFieldOfView => write!(
+                f,
+                "field of view must be between 0 and π radians (was it given in degrees?)"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RayTracerError {}
+
+impl From<MatrixError> for RayTracerError {
+    fn from(err: MatrixError) -> Self {
+        match err {
+            MatrixError::NonInvertible | MatrixError::NotInverted => {
+                RayTracerError::SingularTransform
+            }
+            MatrixError::AsymmetricMatrix | MatrixError::InvalidSize => {
+                RayTracerError::InvalidDimensions
+            }
+        }
+    }
+}
+
+impl From<PpmError> for RayTracerError {
+    fn from(err: PpmError) -> Self {
+        RayTracerError::ParseFailure(err.to_string())
+    }
+}
+
+impl From<ColorError> for RayTracerError {
+    fn from(err: ColorError) -> Self {
+        RayTracerError::ParseFailure(err.to_string())
+    }
+}
+
+#[cfg(feature = "scene")]
+impl From<SceneError> for RayTracerError {
+    fn from(err: SceneError) -> Self {
+        match err {
+            SceneError::InvalidScene(err) => err,
+            err => RayTracerError::ParseFailure(err.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_errors_convert_to_ray_tracer_errors() {
+        assert_eq!(
+            RayTracerError::from(MatrixError::NonInvertible),
+            RayTracerError::SingularTransform
+        );
+        assert_eq!(
+            RayTracerError::from(MatrixError::InvalidSize),
+            RayTracerError::InvalidDimensions
+        );
+    }
+
+    #[test]
+    fn ppm_errors_convert_to_parse_failures() {
+        assert_eq!(
+            RayTracerError::from(PpmError::BadMagicNumber),
+            RayTracerError::ParseFailure(String::from("not a plain PPM (P3) file"))
+        );
+    }
+
+    #[test]
+    fn errors_have_a_readable_description() {
+        let err = RayTracerError::ParseFailure(String::from("unexpected token"));
+        assert_eq!(err.to_string(), "failed to parse input: unexpected token");
+    }
+}
@@ -1,33 +1,77 @@
+use std::{cmp::Ordering, sync::Arc};
+
 use crate::ray_tracer::{
     rays::Ray,
     shapes::Object,
     tuples::{Point, Tuple, Vector},
-    utils,
 };
 
+/// Where a ray hits an object.
+/// The object is shared, so copying intersections around while shading doesn't copy the object,
+/// which may be a big group.
 #[derive(Debug, PartialEq, Clone)]
-pub(crate) struct Intersection {
+pub struct Intersection {
     t: f64,
-    object: Object,
+    object: Arc<Object>,
 }
 impl Intersection {
     pub(crate) fn new(time: f64, object: Object) -> Self {
-        Intersection { t: time, object }
+        Intersection {
+            t: time,
+            object: Arc::new(object),
+        }
+    }
+    /// Like `new`, for several intersections with the same object that can share one copy of it.
+    pub(crate) fn new_shared(time: f64, object: &Arc<Object>) -> Self {
+        Intersection {
+            t: time,
+            object: Arc::clone(object),
+        }
     }
-    pub(crate) fn get_time(&self) -> f64 {
+    /// Intersections with one object at each of `times`, all sharing one copy of the object.
+    /// The object is only made when there is at least one intersection, so a shape that is
+    /// missed isn't copied at all.
+    pub(crate) fn new_all(times: &[f64], object: impl FnOnce() -> Object) -> Vec<Self> {
+        if times.is_empty() {
+            return Vec::new();
+        }
+        let object = Arc::new(object());
+        times
+            .iter()
+            .map(|&t| Intersection::new_shared(t, &object))
+            .collect()
+    }
+    pub fn get_time(&self) -> f64 {
         self.t
     }
     #[cfg(test)]
     pub(crate) fn get_object_raw(&self) -> &Object {
         &self.object
     }
-    pub(crate) fn get_object(&self) -> &Object {
+    pub fn get_object(&self) -> &Object {
         &self.object
     }
+
+    /// Whether `other` is a copy of this very intersection. Copies share the object they hit,
+    /// so this neither compares whole objects, whose materials may hold floats like NaN that
+    /// never compare equal, nor mixes up clones of a shape hit at the same `t`.
+    fn is_same_hit(&self, other: &Intersection) -> bool {
+        self.t == other.t && Arc::ptr_eq(&self.object, &other.object)
+    }
+
+    fn compare(a: &Intersection, b: &Intersection) -> Ordering {
+        // NaN sorts last whatever its sign, which `total_cmp` alone doesn't do
+        let by_time =
+            a.t.is_nan()
+                .cmp(&b.t.is_nan())
+                .then_with(|| a.t.total_cmp(&b.t));
+        by_time.then_with(|| a.object.get_id().cmp(&b.object.get_id()))
+    }
 }
 
+/// All intersections of a ray, sorted by `t`.
 #[derive(Debug, PartialEq, Clone)]
-pub(crate) struct Intersections {
+pub struct Intersections {
     pub(crate) list: Vec<Intersection>,
 }
 impl Intersections {
@@ -39,27 +83,40 @@ impl Intersections {
         i.sort();
         i
     }
+    /// Sorts by `t`, breaking ties on the object id so the order never depends on the order the
+    /// intersections were found in.
+    /// NaN `t` values, e.g. from a degenerate ray, are sorted last instead of panicking.
     fn sort(&mut self) {
-        self.list
-            .sort_unstable_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+        self.list.sort_unstable_by(Intersection::compare);
     }
     #[cfg(test)]
     pub(crate) fn count(&self) -> usize {
         self.list.len()
     }
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+    /// Iterates over the intersections in order of increasing `t`.
+    pub fn iter(&self) -> std::slice::Iter<'_, Intersection> {
+        self.list.iter()
+    }
     #[cfg(test)]
     pub(crate) fn get_element(&self, index: usize) -> Option<Intersection> {
-        if index <= self.list.len() {
+        if index < self.list.len() {
             Some(self.list[index].clone())
         } else {
             None
         }
     }
-    pub(crate) fn hit(&self) -> Option<Intersection> {
-        let mut list = self.list.clone();
-        list.retain(|x| x.t.is_sign_positive());
-        list.iter()
-            .min_by(|&x, &y| x.t.partial_cmp(&y.t).unwrap())
+    /// The intersection with the lowest non-negative `t`, if any.
+    pub fn hit(&self) -> Option<Intersection> {
+        self.list
+            .iter()
+            .filter(|x| x.t.is_sign_positive() && !x.t.is_nan())
+            .min_by(|&x, &y| Intersection::compare(x, y))
             .cloned()
     }
     pub(crate) fn put_elements(&mut self, intersection: &[Intersection]) {
@@ -68,10 +125,31 @@ impl Intersections {
     }
 }
 
+impl IntoIterator for Intersections {
+    type Item = Intersection;
+    type IntoIter = std::vec::IntoIter<Intersection>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Intersections {
+    type Item = &'a Intersection;
+    type IntoIter = std::slice::Iter<'a, Intersection>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.iter()
+    }
+}
+
+/// What is needed to shade a hit, see [`World::prepare_computations`].
+///
+/// [`World::prepare_computations`]: crate::ray_tracer::world::World::prepare_computations
 #[derive(Debug, PartialEq)]
-pub(crate) struct IntersectComp {
+pub struct IntersectComp {
     pub(crate) t: f64,
-    pub(crate) object: Object,
+    pub(crate) object: Arc<Object>,
     pub(crate) point: Point,
     pub(crate) eyev: Vector,
     pub(crate) normalv: Vector,
@@ -82,19 +160,36 @@ pub(crate) struct IntersectComp {
     pub(crate) n1: f64,
     pub(crate) n2: f64,
 }
+/// Prepares the shading of a hit, moving `over_point` and `under_point` the default
+/// [`EPSILON`](crate::ray_tracer::utils::EPSILON) off the surface.
+#[cfg(test)]
 pub(crate) fn prepare_computations(
     intersection: &Intersection,
     ray: &Ray,
     intersections: &Intersections,
 ) -> IntersectComp {
+    use crate::ray_tracer::utils::EPSILON;
+    prepare_computations_with_epsilon(intersection, ray, intersections, EPSILON)
+}
+/// Like [`prepare_computations`], but moves `over_point` and `under_point` `epsilon` off the
+/// surface, unless the object has an epsilon of its own.
+pub(crate) fn prepare_computations_with_epsilon(
+    intersection: &Intersection,
+    ray: &Ray,
+    intersections: &Intersections,
+    epsilon: f64,
+) -> IntersectComp {
+    // The normal can be expensive, e.g. for shapes deep in a group, so it's only found once
+    let point = ray.position(intersection.t);
+    let normalv = intersection.get_object().normal_at(point);
+    let eyev = -(ray.get_direction());
+
     let mut comps = IntersectComp {
         t: intersection.t,
         object: intersection.object.clone(),
-        point: ray.position(intersection.t),
-        eyev: -(ray.get_direction()),
-        normalv: intersection
-            .get_object()
-            .normal_at(ray.position(intersection.t)),
+        point,
+        eyev,
+        normalv,
         reflectv: Vector::new_vector(0.0, 0.0, 0.0),
         inside: false,
         over_point: Point::new_point(0.0, 0.0, 0.0),
@@ -103,9 +198,6 @@ pub(crate) fn prepare_computations(
         n2: 0.0,
     };
 
-    let point = ray.position(intersection.t);
-    let normalv = intersection.get_object().normal_at(point);
-    let eyev = -(ray.get_direction());
     if Tuple::dot(&normalv, &eyev) < 0.0 {
         comps.inside = true;
         comps.normalv = -comps.normalv;
@@ -113,8 +205,9 @@ pub(crate) fn prepare_computations(
 
     comps.reflectv = Vector::reflect(&ray.direction, &comps.normalv);
 
-    comps.over_point = comps.point + comps.normalv * utils::EPSILON;
-    comps.under_point = comps.point - comps.normalv * utils::EPSILON;
+    let epsilon = comps.object.get_epsilon().unwrap_or(epsilon);
+    comps.over_point = comps.point + comps.normalv * epsilon;
+    comps.under_point = comps.point - comps.normalv * epsilon;
 
     (comps.n1, comps.n2) = get_refractive_index_from_intersections(intersection, intersections);
 
@@ -126,28 +219,34 @@ pub(crate) fn prepare_computations(
 /// n2: The object where a ray is "entering".
 ///
 /// Returns (n1, n2)
+///
+/// Objects are told apart by id, so two identical objects in the same place, e.g. coincident
+/// glass surfaces, are still entered and left in the order the intersections are sorted in,
+/// and an object is recognised whatever values its material holds.
 fn get_refractive_index_from_intersections(
     intersected_object: &Intersection,
     intersection_collection: &Intersections,
 ) -> (f64, f64) {
-    let mut containers: Vec<Object> = Vec::new();
+    let mut containers: Vec<Arc<Object>> = Vec::new();
     let mut n1 = 1.0;
     let mut n2 = 1.0;
 
     for i in &intersection_collection.list {
-        if i == intersected_object && !containers.is_empty() {
-            n1 = containers.last().unwrap().get_material().refractive_index;
+        let is_hit = i.is_same_hit(intersected_object);
+        if is_hit && !containers.is_empty() {
+            n1 = containers.last().unwrap().material().refractive_index;
         }
 
-        if containers.contains(&i.object) {
-            containers.retain(|item| *item != i.object);
+        let id = i.object.get_id();
+        if containers.iter().any(|item| item.get_id() == id) {
+            containers.retain(|item| item.get_id() != id);
         } else {
             containers.push(i.object.clone());
         }
 
-        if i == intersected_object {
+        if is_hit {
             if !containers.is_empty() {
-                n2 = containers.last().unwrap().get_material().refractive_index;
+                n2 = containers.last().unwrap().material().refractive_index;
             }
 
             break;
@@ -157,8 +256,16 @@ fn get_refractive_index_from_intersections(
     (n1, n2)
 }
 
+/// The reflectance of a boundary between refractive indices `n1` and `n2` when looking straight
+/// at it, e.g. 0.04 between air and glass.
+pub(crate) fn schlick_r0(n1: f64, n2: f64) -> f64 {
+    ((n1 - n2) / (n1 + n2)).powi(2)
+}
+
 /// Calculate the `reflectance` of an intersection.
 /// Reflectance describes a fraction of light being reflected.
+/// The reflectance at normal incidence is only worked out once total internal reflection has
+/// been ruled out.
 pub(crate) fn schlick(comps: &IntersectComp) -> f64 {
     // Find the cosine of the angle between the eye and normal vectors
     let mut cos = Tuple::dot(&comps.eyev, &comps.normalv);
@@ -179,8 +286,7 @@ pub(crate) fn schlick(comps: &IntersectComp) -> f64 {
         }
     }
 
-    let r0 = ((comps.n1 - comps.n2) / (comps.n1 + comps.n2)).powi(2);
-
+    let r0 = schlick_r0(comps.n1, comps.n2);
     r0 + (1.0 - r0) * (1.0 - cos).powi(5)
 }
 
@@ -191,6 +297,7 @@ mod tests {
         shapes::*,
         transformations::Transform,
         utils::{is_float_equal, EPSILON},
+        world::World,
     };
 
     use super::*;
@@ -201,24 +308,92 @@ mod tests {
         let i = Intersection::new(3.5, s.clone());
 
         assert!(is_float_equal(&i.t, 3.5));
-        assert_eq!(i.object, s);
+        assert_eq!(*i.object, s);
+    }
+    #[test]
+    fn cloning_an_intersection_shares_its_object() {
+        let mut g = Group::new();
+        for _ in 0..1000 {
+            g.add_child(new_sphere());
+        }
+        let i = Intersection::new(1.0, new_group(g));
+        let copy = i.clone();
+        assert!(Arc::ptr_eq(&i.object, &copy.object));
+
+        let xs = Intersections::new(&[i.clone(), copy]);
+        let hit = xs.hit().unwrap();
+        assert!(Arc::ptr_eq(&hit.object, &i.object));
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let sphere_hit = Intersection::new(4.0, new_sphere());
+        let xs = Intersections::new(std::slice::from_ref(&sphere_hit));
+        let comps = prepare_computations(&sphere_hit, &r, &xs);
+        assert!(Arc::ptr_eq(&comps.object, &sphere_hit.object));
+    }
+    #[test]
+    fn every_hit_on_a_shape_shares_one_copy_of_it() {
+        let r = Ray::new(
+            Point::new_point(0.0, 0.3, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut cone = new_cone_capped(-1.0, 1.0);
+        cone.set_transform(&Transform::rotation_x(std::f64::consts::FRAC_PI_2));
+        let mut cylinder = new_cylinder_capped(-1.0, 1.0);
+        cylinder.set_transform(&Transform::rotation_x(0.1));
+        for shape in [new_sphere(), new_cube(), cylinder, cone] {
+            let xs = r.intersect(&shape);
+            assert!(xs.len() >= 2);
+            assert!(xs
+                .iter()
+                .all(|x| Arc::ptr_eq(&x.object, &xs[0].object) && *x.object == shape));
+        }
+
+        let miss = Ray::new(
+            Point::new_point(0.0, 10.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        assert!(Intersection::new_all(&[], || unreachable!()).is_empty());
+        assert!(miss.intersect(&new_cube()).is_empty());
     }
     #[test]
     fn aggregating_intersections() {
         let s = new_sphere();
         let i1 = Intersection::new(1.0, s.clone());
         let i2 = Intersection::new(2.0, s);
-        let xs = Intersections::new(&vec![i1, i2]);
+        let xs = Intersections::new(&[i1, i2]);
         assert_eq!(xs.count(), 2);
         assert!(is_float_equal(&xs.get_element(0).unwrap().t, 1.0));
         assert!(is_float_equal(&xs.get_element(1).unwrap().t, 2.0));
     }
     #[test]
+    fn iterating_over_intersections_in_order() {
+        let s = new_sphere();
+        let xs = Intersections::new(&[
+            Intersection::new(2.0, s.clone()),
+            Intersection::new(-1.0, s.clone()),
+            Intersection::new(1.0, s),
+        ]);
+        assert_eq!(xs.len(), 3);
+        assert!(!xs.is_empty());
+        let times: Vec<f64> = xs.iter().map(|i| i.get_time()).collect();
+        assert_eq!(times, vec![-1.0, 1.0, 2.0]);
+        assert_eq!((&xs).into_iter().filter(|i| i.get_time() > 0.0).count(), 2);
+        assert_eq!(xs.into_iter().count(), 3);
+    }
+    #[test]
+    fn getting_an_element_past_the_end_gives_none() {
+        let s = new_sphere();
+        let xs = Intersections::new(&[Intersection::new(1.0, s)]);
+        assert!(xs.get_element(xs.count()).is_none());
+    }
+    #[test]
     fn the_hit_when_all_intersections_have_positive_t() {
         let s = new_sphere();
         let i1 = Intersection::new(1.0, s.clone());
         let i2 = Intersection::new(2.0, s);
-        let xs = Intersections::new(&vec![i2, i1.clone()]);
+        let xs = Intersections::new(&[i2, i1.clone()]);
         let i = xs.hit();
         assert_eq!(i, Some(i1));
     }
@@ -227,7 +402,7 @@ mod tests {
         let s = new_sphere();
         let i1 = Intersection::new(-1.0, s.clone());
         let i2 = Intersection::new(1.0, s);
-        let xs = Intersections::new(&vec![i1, i2.clone()]);
+        let xs = Intersections::new(&[i1, i2.clone()]);
         let i = xs.hit();
         assert_eq!(i, Some(i2));
     }
@@ -236,7 +411,7 @@ mod tests {
         let s = new_sphere();
         let i1 = Intersection::new(-2.0, s.clone());
         let i2 = Intersection::new(-1.0, s);
-        let xs = Intersections::new(&vec![i2, i1]);
+        let xs = Intersections::new(&[i2, i1]);
         let i = xs.hit();
         assert_eq!(i, None);
     }
@@ -247,11 +422,119 @@ mod tests {
         let i2 = Intersection::new(7.0, s.clone());
         let i3 = Intersection::new(-3.0, s.clone());
         let i4 = Intersection::new(2.0, s);
-        let xs = Intersections::new(&vec![i1, i2, i3, i4.clone()]);
+        let xs = Intersections::new(&[i1, i2, i3, i4.clone()]);
         let i = xs.hit();
         assert_eq!(i, Some(i4));
     }
     #[test]
+    fn intersections_with_equal_t_are_sorted_by_object_id() {
+        let s1 = new_sphere();
+        let s2 = new_sphere();
+        assert!(s1.get_id() < s2.get_id());
+
+        let forward = Intersections::new(&[
+            Intersection::new(1.0, s1.clone()),
+            Intersection::new(1.0, s2.clone()),
+        ]);
+        let backward = Intersections::new(&[
+            Intersection::new(1.0, s2.clone()),
+            Intersection::new(1.0, s1.clone()),
+        ]);
+        for xs in [forward, backward] {
+            assert_eq!(xs.list[0].get_object().get_id(), s1.get_id());
+            assert_eq!(xs.list[1].get_object().get_id(), s2.get_id());
+        }
+    }
+    #[test]
+    fn coincident_surfaces_give_the_same_refractive_indices_in_any_order() {
+        let mut a = glass_sphere();
+        let mut a_mat = a.get_material();
+        a_mat.refractive_index = 1.5;
+        a.set_material(&a_mat);
+        let mut b = glass_sphere();
+        let mut b_mat = b.get_material();
+        b_mat.refractive_index = 2.0;
+        b.set_material(&b_mat);
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let found = [
+            Intersection::new(4.0, a.clone()),
+            Intersection::new(6.0, a.clone()),
+            Intersection::new(4.0, b.clone()),
+            Intersection::new(6.0, b.clone()),
+        ];
+        let forward = Intersections::new(&found);
+        let mut reversed = found.clone();
+        reversed.reverse();
+        let backward = Intersections::new(&reversed);
+        assert_eq!(forward, backward);
+
+        let indices = |xs: &Intersections| -> Vec<(f64, f64)> {
+            xs.iter()
+                .map(|i| {
+                    let comps = prepare_computations(i, &r, xs);
+                    (comps.n1, comps.n2)
+                })
+                .collect()
+        };
+        // `a` was created first, so it is entered first and is inside `b` until it's left
+        let expected = vec![(1.0, 1.5), (1.5, 2.0), (2.0, 2.0), (2.0, 1.0)];
+        assert_eq!(indices(&forward), expected);
+        assert_eq!(indices(&backward), expected);
+    }
+    #[test]
+    fn coincident_clones_that_only_differ_in_material_are_told_apart() {
+        let mut a = glass_sphere();
+        let mut a_mat = a.get_material();
+        a_mat.refractive_index = 1.5;
+        a.set_material(&a_mat);
+        let mut b = a.clone();
+        let mut b_mat = b.get_material();
+        b_mat.refractive_index = 2.0;
+        b.set_material(&b_mat);
+        let mut w = World::new();
+        w.add_object(a);
+        w.add_object(b);
+        assert_ne!(w.objects[0].get_id(), w.objects[1].get_id());
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        // `a` was added first, so it is entered first and is inside `b` until it's left
+        let expected = vec![(1.0, 1.5), (1.5, 2.0), (2.0, 2.0), (2.0, 1.0)];
+        for _ in 0..3 {
+            let xs = w.intersect(&r);
+            let ids: Vec<usize> = xs.iter().map(|i| i.get_object().get_id()).collect();
+            let (a_id, b_id) = (w.objects[0].get_id(), w.objects[1].get_id());
+            assert_eq!(ids, [a_id, b_id, a_id, b_id]);
+            let indices: Vec<(f64, f64)> = xs
+                .iter()
+                .map(|i| {
+                    let comps = prepare_computations(i, &r, &xs);
+                    (comps.n1, comps.n2)
+                })
+                .collect();
+            assert_eq!(indices, expected);
+        }
+    }
+    #[test]
+    fn a_nan_t_is_sorted_last_without_panicking() {
+        let s = new_sphere();
+        let xs = Intersections::new(&[
+            Intersection::new(f64::NAN, s.clone()),
+            Intersection::new(2.0, s.clone()),
+            Intersection::new(-1.0, s.clone()),
+        ]);
+        assert_eq!(xs.list[0].get_time(), -1.0);
+        assert_eq!(xs.list[1].get_time(), 2.0);
+        assert!(xs.list[2].get_time().is_nan());
+        assert_eq!(xs.hit().unwrap().get_time(), 2.0);
+    }
+    #[test]
     fn precomputing_the_state_of_an_intersection() {
         let r = Ray::new(
             Tuple::new_point(0.0, 0.0, -5.0),
@@ -308,7 +591,7 @@ mod tests {
         shape.set_transform(&Transform::translate(0.0, 0.0, 1.0));
         let i = Intersection::new(5.0, shape);
         let comps = prepare_computations(&i.clone(), &r, &Intersections { list: vec![i] });
-        assert!(comps.over_point.z < -utils::EPSILON / 2.0);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
         assert!(comps.point.z > comps.over_point.z);
     }
 
@@ -332,17 +615,23 @@ mod tests {
         #[allow(non_snake_case)]
         let mut A = glass_sphere();
         A.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
-        A.get_material().refractive_index = 1.5;
+        let mut a_mat = A.get_material();
+        a_mat.refractive_index = 1.5;
+        A.set_material(&a_mat);
 
         #[allow(non_snake_case)]
         let mut B = glass_sphere();
         B.set_transform(&Transform::translate(0.0, 0.0, -0.25));
-        B.get_material().refractive_index = 2.0;
+        let mut b_mat = B.get_material();
+        b_mat.refractive_index = 2.0;
+        B.set_material(&b_mat);
 
         #[allow(non_snake_case)]
         let mut C = glass_sphere();
         C.set_transform(&Transform::translate(0.0, 0.0, 0.25));
-        C.get_material().refractive_index = 2.5;
+        let mut c_mat = C.get_material();
+        c_mat.refractive_index = 2.5;
+        C.set_material(&c_mat);
 
         let r = Ray::new(
             Point::new_point(0.0, 0.0, -4.0),
@@ -375,6 +664,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn finding_n1_and_n2_in_a_glass_sphere_nested_in_its_own_clone() {
+        let mut outer = glass_sphere();
+        outer.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut inner = outer.clone();
+        inner.set_transform(&Transform::scaling(1.0, 1.0, 1.0));
+        let mut inner_mat = inner.get_material();
+        inner_mat.refractive_index = 2.0;
+        inner.set_material(&inner_mat);
+        let mut w = World::new();
+        w.add_object(outer);
+        w.add_object(inner);
+        assert_ne!(w.objects[0].get_id(), w.objects[1].get_id());
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -4.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect(&r);
+        let times: Vec<f64> = xs.iter().map(Intersection::get_time).collect();
+        assert_eq!(times, [2.0, 3.0, 5.0, 6.0]);
+
+        let results = [(1.0, 1.5), (1.5, 2.0), (2.0, 1.5), (1.5, 1.0)];
+        for (i, (n1, n2)) in xs.iter().zip(results) {
+            let comps = prepare_computations(i, &r, &xs);
+            assert_eq!((comps.n1, comps.n2), (n1, n2));
+        }
+    }
+    #[test]
+    fn finding_n1_and_n2_where_glass_spheres_overlap() {
+        let mut a = glass_sphere();
+        a.set_transform(&Transform::translate(0.0, 0.0, -0.5));
+        let mut a_mat = a.get_material();
+        a_mat.refractive_index = 1.5;
+        a.set_material(&a_mat);
+
+        let mut b = a.clone();
+        b.set_transform(&Transform::translate(0.0, 0.0, 0.5));
+        let mut b_mat = b.get_material();
+        b_mat.refractive_index = 2.0;
+        // NaN never equals itself, so `b` can only be recognised by its id
+        b_mat.specular = f64::NAN;
+        b.set_material(&b_mat);
+        let mut w = World::new();
+        w.add_object(a);
+        w.add_object(b);
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect(&r);
+        let times: Vec<f64> = xs.iter().map(Intersection::get_time).collect();
+        assert_eq!(times, [3.5, 4.5, 5.5, 6.5]);
+
+        let results = [(1.0, 1.5), (1.5, 2.0), (2.0, 2.0), (2.0, 1.0)];
+        for (i, (n1, n2)) in xs.iter().zip(results) {
+            let comps = prepare_computations(i, &r, &xs);
+            assert_eq!((comps.n1, comps.n2), (n1, n2));
+        }
+    }
+
     #[test]
     fn the_under_point_is_offset_below_the_surface() {
         let r = Ray::new(
@@ -384,12 +735,26 @@ mod tests {
         let mut shape = glass_sphere();
         shape.set_transform(&Transform::translate(0.0, 0.0, 1.0));
         let i = Intersection::new(5.0, shape);
-        let xs = Intersections::new(&[i.clone()]);
+        let xs = Intersections::new(std::slice::from_ref(&i));
         let comps = prepare_computations(&i, &r, &xs);
         assert!(comps.under_point.z > EPSILON / 2.0);
         assert!(comps.point.z < comps.under_point.z);
     }
     #[test]
+    fn a_shape_epsilon_overrides_the_default_offset() {
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut shape = glass_sphere();
+        shape.set_epsilon(Some(0.01));
+        let i = Intersection::new(4.0, shape);
+        let xs = Intersections::new(std::slice::from_ref(&i));
+        let comps = prepare_computations(&i, &r, &xs);
+        assert_eq!(comps.over_point, Point::new_point(0.0, 0.0, -1.01));
+        assert_eq!(comps.under_point, Point::new_point(0.0, 0.0, -0.99));
+    }
+    #[test]
     fn the_schlick_approximation_under_total_internal_reflection() {
         let shape = glass_sphere();
         let r = Ray::new(
@@ -431,4 +796,10 @@ mod tests {
         let reflectance = schlick(&comps);
         assert!(is_float_equal(&reflectance, 0.48873));
     }
+    #[test]
+    fn the_reflectance_at_normal_incidence() {
+        assert!(is_float_equal(&schlick_r0(1.0, 1.5), 0.04));
+        assert!(is_float_equal(&schlick_r0(1.5, 1.0), 0.04));
+        assert!(is_float_equal(&schlick_r0(1.33, 1.33), 0.0));
+    }
 }
@@ -1,9 +1,54 @@
-use crate::ray_tracer::{colors::Color, tuples::Tuple};
+use crate::ray_tracer::{
+    colors::Color,
+    sampler,
+    tuples::{Point, Tuple, Vector},
+    utils::EPSILON,
+    world::World,
+};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LightType {
+    /// All light comes from a single point, which gives hard shadows.
+    Point,
+    /// Light comes from a rectangle, sampled as a grid of `usteps` x `vsteps` cells.
+    /// `uvec` and `vvec` are the edges of a single cell.
+    Area {
+        corner: Point,
+        uvec: Vector,
+        usteps: usize,
+        vvec: Vector,
+        vsteps: usize,
+        jitter: bool,
+    },
+    /// Light shines from a point in a cone around `direction`.
+    /// Points within `inner` radians of the direction are fully lit, and the light fades out
+    /// towards `outer` radians.
+    Spot {
+        direction: Vector,
+        inner: f64,
+        outer: f64,
+    },
+    /// Light from infinitely far away, like the sun, travelling along `direction`.
+    /// Every point is lit from the same direction.
+    Directional { direction: Vector },
+}
+
+/// Attenuation coefficients that keep the full intensity at any distance.
+const NO_ATTENUATION: (f64, f64, f64) = (1.0, 0.0, 0.0);
+
+/// How far away a directional light is placed when casting shadow rays towards it.
+const DIRECTIONAL_DISTANCE: f64 = 1.0e9;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Light {
     position: Tuple,
     intensity: Color,
+    light_type: LightType,
+    /// Constant, linear and quadratic falloff coefficients. See [`Light::set_attenuation`].
+    attenuation: (f64, f64, f64),
+    seed: u64,
 }
 
 impl Light {
@@ -11,18 +56,259 @@ impl Light {
         Light {
             position: *position,
             intensity: *intensity,
+            light_type: LightType::Point,
+            attenuation: NO_ATTENUATION,
+            seed: 0,
+        }
+    }
+    /// Creates a rectangular light with one corner at `corner` and edges `full_uvec` and
+    /// `full_vvec`. Shadows are sampled at `usteps` x `vsteps` points on the light, which gives
+    /// soft shadow edges.
+    /// Sample points are jittered within their cells by default, see [`Light::set_jitter`].
+    pub fn area_light(
+        corner: &Point,
+        full_uvec: &Vector,
+        usteps: usize,
+        full_vvec: &Vector,
+        vsteps: usize,
+        intensity: &Color,
+    ) -> Light {
+        let usteps = usteps.max(1);
+        let vsteps = vsteps.max(1);
+        Light {
+            position: *corner + *full_uvec / 2.0 + *full_vvec / 2.0,
+            intensity: *intensity,
+            light_type: LightType::Area {
+                corner: *corner,
+                uvec: *full_uvec / usteps as f64,
+                usteps,
+                vvec: *full_vvec / vsteps as f64,
+                vsteps,
+                jitter: true,
+            },
+            attenuation: NO_ATTENUATION,
+            seed: 0,
+        }
+    }
+    /// Creates a light shining from `position` in a cone around `direction`, like a flashlight.
+    /// `inner` and `outer` are the angles, in radians, between the direction and the edges of
+    /// the fully lit cone and of the fading cone.
+    pub fn spot_light(
+        position: &Point,
+        direction: &Vector,
+        inner: f64,
+        outer: f64,
+        intensity: &Color,
+    ) -> Light {
+        Light {
+            position: *position,
+            intensity: *intensity,
+            light_type: LightType::Spot {
+                direction: direction.normalize(),
+                inner,
+                outer: outer.max(inner),
+            },
+            attenuation: NO_ATTENUATION,
+            seed: 0,
         }
     }
+    /// Creates a light shining along `direction` from infinitely far away, like the sun.
+    /// It has no position, so [`Light::get_position`] returns the origin.
+    pub fn directional_light(direction: &Vector, intensity: &Color) -> Light {
+        Light {
+            position: Tuple::new_point(0.0, 0.0, 0.0),
+            intensity: *intensity,
+            light_type: LightType::Directional {
+                direction: direction.normalize(),
+            },
+            attenuation: NO_ATTENUATION,
+            seed: 0,
+        }
+    }
+    /// The position of a point light, or the center of an area light.
     pub fn get_position(&self) -> Tuple {
         self.position
     }
     pub fn get_intensity(&self) -> Color {
         self.intensity
     }
+    /// Returns a copy of the light with its intensity multiplied by `factor`.
+    /// Channels may go above 1.0, which makes the light brighter than white; the result can be
+    /// brought back into range by tone mapping the rendered canvas.
+    pub fn with_intensity_scale(&self, factor: f64) -> Light {
+        Light {
+            intensity: self.intensity * factor,
+            ..*self
+        }
+    }
+    pub fn get_light_type(&self) -> LightType {
+        self.light_type
+    }
+    /// Makes the light fall off with distance `d` as
+    /// `1 / (constant + linear * d + quadratic * d²)`. Distances below `EPSILON` count as
+    /// `EPSILON`, so a light without a constant term stays finite right next to it.
+    /// The default of `(1.0, 0.0, 0.0)` keeps the full intensity everywhere. Only diffuse and
+    /// specular lighting are attenuated, and directional lights are never attenuated.
+    pub fn set_attenuation(&mut self, constant: f64, linear: f64, quadratic: f64) {
+        self.attenuation = (constant, linear, quadratic);
+    }
+    pub fn get_attenuation(&self) -> (f64, f64, f64) {
+        self.attenuation
+    }
+    /// Turns jittering of an area light's sample points on or off.
+    /// Without jitter every sample is taken at the center of its cell, which is deterministic
+    /// but can give visible banding in the penumbra. Has no effect on other lights.
+    pub fn set_jitter(&mut self, enable: bool) {
+        if let LightType::Area { jitter, .. } = &mut self.light_type {
+            *jitter = enable;
+        }
+    }
+
+    /// Sets the seed an area light's jittered sample points are drawn from.
+    /// Lights with the same seed pick the same sample points for the same point.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Whether any of the light's values is NaN, see [`World::try_prepare`].
+    pub(crate) fn has_nan(&self) -> bool {
+        let (constant, linear, quadratic) = self.attenuation;
+        let mut values = vec![
+            self.position.x,
+            self.position.y,
+            self.position.z,
+            self.intensity.red,
+            self.intensity.green,
+            self.intensity.blue,
+            constant,
+            linear,
+            quadratic,
+        ];
+        match self.light_type {
+            LightType::Point => {}
+            LightType::Area {
+                corner, uvec, vvec, ..
+            } => values.extend([
+                corner.x, corner.y, corner.z, uvec.x, uvec.y, uvec.z, vvec.x, vvec.y, vvec.z,
+            ]),
+            LightType::Spot {
+                direction,
+                inner,
+                outer,
+            } => values.extend([direction.x, direction.y, direction.z, inner, outer]),
+            LightType::Directional { direction } => {
+                values.extend([direction.x, direction.y, direction.z])
+            }
+        }
+        values.iter().any(|v| v.is_nan())
+    }
+
+    /// The points on the light that are used when lighting `point`.
+    /// A point light only has its position, while an area light has one point per cell.
+    pub(crate) fn samples(&self, point: &Point) -> Vec<Point> {
+        match self.light_type {
+            LightType::Point | LightType::Spot { .. } => vec![self.position],
+            // Far enough away that anything between it and the point casts a shadow
+            LightType::Directional { direction } => {
+                vec![*point - direction * DIRECTIONAL_DISTANCE]
+            }
+            LightType::Area {
+                corner,
+                uvec,
+                usteps,
+                vvec,
+                vsteps,
+                jitter,
+            } => {
+                let mut samples = Vec::with_capacity(usteps * vsteps);
+                for v in 0..vsteps {
+                    for u in 0..usteps {
+                        let (ju, jv) = if jitter {
+                            (
+                                jitter_at(point, u, v, 0, self.seed),
+                                jitter_at(point, u, v, 1, self.seed),
+                            )
+                        } else {
+                            (0.5, 0.5)
+                        };
+                        samples.push(corner + uvec * (u as f64 + ju) + vvec * (v as f64 + jv));
+                    }
+                }
+                samples
+            }
+        }
+    }
+
+    /// The direction from `point` towards the light `sample`.
+    /// For a directional light this is the same everywhere.
+    pub(crate) fn direction_to(&self, sample: &Point, point: &Point) -> Vector {
+        match self.light_type {
+            LightType::Directional { direction } => -direction,
+            _ => (*sample - *point).normalize(),
+        }
+    }
+
+    /// How much of the light from `sample` is left after travelling to `point`.
+    pub(crate) fn attenuation_at(&self, sample: &Point, point: &Point) -> f64 {
+        if self.attenuation == NO_ATTENUATION
+            || matches!(self.light_type, LightType::Directional { .. })
+        {
+            return 1.0;
+        }
+        let (constant, linear, quadratic) = self.attenuation;
+        let d = (*sample - *point).magnitude().max(EPSILON);
+        1.0 / (constant + linear * d + quadratic * d * d)
+    }
+
+    /// How much of a spot light's cone covers `point`, from 0.0 outside the outer cone to 1.0
+    /// inside the inner cone. Always 1.0 for other lights.
+    pub(crate) fn spot_factor(&self, point: &Point) -> f64 {
+        match self.light_type {
+            LightType::Spot {
+                direction,
+                inner,
+                outer,
+            } => {
+                let cos_angle = Tuple::dot(&(*point - self.position).normalize(), &direction);
+                let (cos_inner, cos_outer) = (inner.cos(), outer.cos());
+                if cos_angle >= cos_inner {
+                    1.0
+                } else if cos_angle <= cos_outer {
+                    0.0
+                } else {
+                    (cos_angle - cos_outer) / (cos_inner - cos_outer)
+                }
+            }
+            _ => 1.0,
+        }
+    }
+
+    /// How much of the light reaches `point`, from 0.0 (fully shadowed) to 1.0 (fully lit).
+    /// Same as [`World::light_visibility`].
+    pub fn intensity_at(&self, point: &Point, world: &World) -> f64 {
+        world.light_visibility(point, self)
+    }
+}
+
+/// A deterministic pseudo-random offset in [0, 1) for a sample cell, so that renders are
+/// reproducible while neighbouring points still get different sample positions.
+fn jitter_at(point: &Point, u: usize, v: usize, axis: u64, seed: u64) -> f64 {
+    let h = point.x.to_bits()
+        ^ point.y.to_bits().rotate_left(21)
+        ^ point.z.to_bits().rotate_left(42)
+        ^ ((u as u64) << 32 | v as u64).wrapping_mul(sampler::GOLDEN_GAMMA)
+        ^ seed.rotate_left(7).wrapping_mul(sampler::GOLDEN_GAMMA)
+        ^ axis;
+    sampler::to_unit(sampler::mix(h))
 }
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
+
     use super::*;
 
     #[test]
@@ -33,4 +319,240 @@ mod tests {
         assert_eq!(light.get_position(), position);
         assert_eq!(light.get_intensity(), intensity);
     }
+
+    #[test]
+    fn point_lights_evaluate_the_light_intensity_at_a_given_point() {
+        let w = World::new_default_world();
+        let light = w.lights[0];
+        let examples = [
+            (Tuple::new_point(0.0, 1.0001, 0.0), 1.0),
+            (Tuple::new_point(-1.0001, 0.0, 0.0), 1.0),
+            (Tuple::new_point(0.0, 0.0, -1.0001), 1.0),
+            (Tuple::new_point(0.0, 0.0, 1.0001), 0.0),
+            (Tuple::new_point(1.0001, 0.0, 0.0), 0.0),
+            (Tuple::new_point(0.0, -1.0001, 0.0), 0.0),
+            (Tuple::new_point(0.0, 0.0, 0.0), 0.0),
+        ];
+        for (point, result) in examples {
+            assert_eq!(light.intensity_at(&point, &w), result);
+        }
+    }
+
+    #[test]
+    fn attenuation_falls_off_with_distance() {
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+        let mut light = Light::point_light(&position, &Color::new(1.0, 1.0, 1.0));
+        let point = Tuple::new_point(0.0, 0.0, 2.0);
+        assert_eq!(light.get_attenuation(), (1.0, 0.0, 0.0));
+        assert_eq!(light.attenuation_at(&position, &point), 1.0);
+
+        light.set_attenuation(1.0, 0.5, 0.25);
+        // 1 / (1 + 0.5 * 2 + 0.25 * 4)
+        assert_eq!(light.attenuation_at(&position, &point), 1.0 / 3.0);
+    }
+
+    #[test]
+    fn attenuation_without_a_constant_term_stays_finite() {
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+        let mut light = Light::point_light(&position, &Color::new(1.0, 1.0, 1.0));
+        light.set_attenuation(0.0, 1.0, 1.0);
+        assert!(light.attenuation_at(&position, &position).is_finite());
+        let near = Tuple::new_point(0.0, 0.0, 0.5);
+        // 1 / (0.5 + 0.25): near the light the falloff brightens it, as the formula asks
+        assert_eq!(light.attenuation_at(&position, &near), 1.0 / 0.75);
+        let far = Tuple::new_point(0.0, 0.0, 4.0);
+        assert_eq!(light.attenuation_at(&position, &far), 1.0 / 20.0);
+    }
+
+    #[test]
+    fn creating_an_area_light() {
+        let corner = Tuple::new_point(0.0, 0.0, 0.0);
+        let v1 = Tuple::new_vector(2.0, 0.0, 0.0);
+        let v2 = Tuple::new_vector(0.0, 0.0, 1.0);
+        let light = Light::area_light(&corner, &v1, 4, &v2, 2, &Color::new(1.0, 1.0, 1.0));
+
+        assert_eq!(
+            light.get_light_type(),
+            LightType::Area {
+                corner,
+                uvec: Tuple::new_vector(0.5, 0.0, 0.0),
+                usteps: 4,
+                vvec: Tuple::new_vector(0.0, 0.0, 0.5),
+                vsteps: 2,
+                jitter: true,
+            }
+        );
+        assert_eq!(light.get_position(), Tuple::new_point(1.0, 0.0, 0.5));
+        assert_eq!(light.samples(&corner).len(), 8);
+    }
+
+    #[test]
+    fn finding_points_on_an_area_light() {
+        let corner = Tuple::new_point(0.0, 0.0, 0.0);
+        let v1 = Tuple::new_vector(2.0, 0.0, 0.0);
+        let v2 = Tuple::new_vector(0.0, 0.0, 1.0);
+        let mut light = Light::area_light(&corner, &v1, 4, &v2, 2, &Color::new(1.0, 1.0, 1.0));
+        light.set_jitter(false);
+
+        let samples = light.samples(&corner);
+        assert_eq!(samples[0], Tuple::new_point(0.25, 0.0, 0.25));
+        assert_eq!(samples[1], Tuple::new_point(0.75, 0.0, 0.25));
+        assert_eq!(samples[4], Tuple::new_point(0.25, 0.0, 0.75));
+        assert_eq!(samples[6], Tuple::new_point(1.25, 0.0, 0.75));
+        assert_eq!(samples[7], Tuple::new_point(1.75, 0.0, 0.75));
+    }
+
+    #[test]
+    fn the_area_light_intensity_function() {
+        let w = World::new_default_world();
+        let corner = Tuple::new_point(-0.5, -0.5, -5.0);
+        let v1 = Tuple::new_vector(1.0, 0.0, 0.0);
+        let v2 = Tuple::new_vector(0.0, 1.0, 0.0);
+        let mut light = Light::area_light(&corner, &v1, 2, &v2, 2, &Color::new(1.0, 1.0, 1.0));
+        light.set_jitter(false);
+
+        let examples = [
+            (Tuple::new_point(0.0, 0.0, 2.0), 0.0),
+            (Tuple::new_point(1.0, -1.0, 2.0), 0.25),
+            (Tuple::new_point(1.5, 0.0, 2.0), 0.5),
+            (Tuple::new_point(1.25, 1.25, 3.0), 0.75),
+            (Tuple::new_point(0.0, 0.0, -2.0), 1.0),
+        ];
+        for (point, result) in examples {
+            assert_eq!(light.intensity_at(&point, &w), result);
+        }
+    }
+
+    #[test]
+    fn creating_a_spot_light() {
+        let position = Tuple::new_point(0.0, 5.0, 0.0);
+        let light = Light::spot_light(
+            &position,
+            &Tuple::new_vector(0.0, -2.0, 0.0),
+            PI / 8.0,
+            PI / 4.0,
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        assert_eq!(light.get_position(), position);
+        assert_eq!(
+            light.get_light_type(),
+            LightType::Spot {
+                direction: Tuple::new_vector(0.0, -1.0, 0.0),
+                inner: PI / 8.0,
+                outer: PI / 4.0,
+            }
+        );
+        assert_eq!(light.samples(&position), vec![position]);
+    }
+
+    #[test]
+    fn a_spot_light_fades_between_its_inner_and_outer_cone() {
+        let light = Light::spot_light(
+            &Tuple::new_point(0.0, 1.0, 0.0),
+            &Tuple::new_vector(0.0, -1.0, 0.0),
+            PI / 8.0,
+            PI / 4.0,
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        // Points on the plane y = 0, at increasing angles from the direction
+        assert_eq!(light.spot_factor(&Tuple::new_point(0.0, 0.0, 0.0)), 1.0);
+        assert_eq!(light.spot_factor(&Tuple::new_point(0.3, 0.0, 0.0)), 1.0);
+        let between = light.spot_factor(&Tuple::new_point((3.0 * PI / 16.0).tan(), 0.0, 0.0));
+        assert!(between > 0.0 && between < 1.0);
+        assert_eq!(light.spot_factor(&Tuple::new_point(1.5, 0.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn creating_a_directional_light() {
+        let light = Light::directional_light(
+            &Tuple::new_vector(0.0, -2.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        assert_eq!(
+            light.get_light_type(),
+            LightType::Directional {
+                direction: Tuple::new_vector(0.0, -1.0, 0.0)
+            }
+        );
+        let point = Tuple::new_point(3.0, 0.0, -2.0);
+        assert_eq!(
+            light.direction_to(&light.samples(&point)[0], &point),
+            Tuple::new_vector(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn a_directional_light_is_blocked_by_objects_in_its_path() {
+        let w = World::new_default_world();
+        let light = Light::directional_light(
+            &Tuple::new_vector(0.0, -1.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        assert_eq!(
+            light.intensity_at(&Tuple::new_point(0.0, -5.0, 0.0), &w),
+            0.0
+        );
+        assert_eq!(
+            light.intensity_at(&Tuple::new_point(5.0, -5.0, 0.0), &w),
+            1.0
+        );
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let corner = Tuple::new_point(0.0, 0.0, 0.0);
+        let v1 = Tuple::new_vector(2.0, 0.0, 0.0);
+        let v2 = Tuple::new_vector(0.0, 0.0, 1.0);
+        let light = Light::area_light(&corner, &v1, 4, &v2, 2, &Color::new(1.0, 1.0, 1.0));
+        let point = Tuple::new_point(0.3, 4.0, -2.0);
+
+        let samples = light.samples(&point);
+        assert_eq!(samples, light.samples(&point));
+        for (i, sample) in samples.iter().enumerate() {
+            let (u, v) = ((i % 4) as f64, (i / 4) as f64);
+            assert!(sample.x >= u * 0.5 && sample.x < (u + 1.0) * 0.5);
+            assert!(sample.z >= v * 0.5 && sample.z < (v + 1.0) * 0.5);
+        }
+    }
+
+    #[test]
+    fn doubling_a_light_doubles_the_diffuse_contribution() {
+        use crate::ray_tracer::{materials::Material, shapes::new_sphere};
+
+        let mut m = Material::new();
+        m.ambient = 0.0;
+        m.specular = 0.0;
+        let position = Tuple::new_point(0.0, 0.0, 0.0);
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = Light::point_light(
+            &Tuple::new_point(0.0, 0.0, -10.0),
+            &Color::new(1.0, 0.5, 0.25),
+        );
+        let brighter = light.with_intensity_scale(2.0);
+        assert_eq!(brighter.get_intensity(), Color::new(2.0, 1.0, 0.5));
+        assert_eq!(light.get_intensity(), Color::new(1.0, 0.5, 0.25));
+
+        let obj = new_sphere();
+        let single = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
+        let double = m.lighting(&obj, &brighter, &position, &eyev, &normalv, 1.0);
+        assert_eq!(single, Color::new(0.9, 0.45, 0.225));
+        assert_eq!(double, single * 2.0);
+    }
+
+    #[test]
+    fn the_seed_decides_the_jittered_samples() {
+        let corner = Tuple::new_point(0.0, 0.0, 0.0);
+        let v1 = Tuple::new_vector(2.0, 0.0, 0.0);
+        let v2 = Tuple::new_vector(0.0, 0.0, 1.0);
+        let mut light = Light::area_light(&corner, &v1, 4, &v2, 2, &Color::new(1.0, 1.0, 1.0));
+        let point = Tuple::new_point(0.3, 4.0, -2.0);
+        assert_eq!(light.get_seed(), 0);
+        let unseeded = light.samples(&point);
+
+        light.set_seed(3);
+        let seeded = light.samples(&point);
+        assert_eq!(seeded, light.samples(&point));
+        assert_ne!(seeded, unseeded);
+    }
 }
@@ -3,10 +3,11 @@ use crate::ray_tracer::{
     lights::Light,
     patterns::Pattern,
     shapes::*,
-    tuples::{Point, Tuple},
+    tuples::{Point, Vector},
 };
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Material {
     pub color: Color,
     pub ambient: f64,
@@ -17,7 +18,31 @@ pub struct Material {
     pub reflective: f64,
     pub transparency: f64,
     pub refractive_index: f64,
+    /// Color seen by this object's reflected and refracted rays when they don't hit anything.
+    /// When `None`, the world's environment is used.
+    pub environment: Option<Color>,
+    /// Bump map that tilts the surface normal, for detail like dimples or ripples without extra
+    /// geometry. The pattern's color at a point is read as an `(x, y, z)` offset that is added
+    /// to the normal in object space, so black leaves the normal alone.
+    pub normal_pattern: Option<Pattern>,
+    /// Light given off by the surface itself. It is added to the shaded color whatever the
+    /// lighting, so the surface glows even in shadow, but it doesn't light up anything else;
+    /// put a light inside a glowing object for that. Black by default.
+    pub emissive: Color,
 }
+/// The shading inputs of a material at one point on a surface, with patterns applied.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolvedMaterial {
+    pub color: Color,
+    pub ambient: f64,
+    pub diffuse: f64,
+    pub specular: f64,
+    pub shininess: f64,
+    pub reflective: f64,
+    pub transparency: f64,
+    pub refractive_index: f64,
+}
+
 impl Material {
     pub fn new() -> Material {
         Material {
@@ -30,71 +55,102 @@ impl Material {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            environment: None,
+            normal_pattern: None,
+            emissive: Color::new(0.0, 0.0, 0.0),
+        }
+    }
+
+    /// The color of the surface at `position`, i.e. the pattern's color if there is one.
+    fn color_at(&self, object: &Object, position: &Point) -> Color {
+        match &self.pattern {
+            Some(pattern) => Pattern::pattern_at_object(pattern, object, *position),
+            None => self.color,
+        }
+    }
+
+    /// Returns the values used to shade `object` at `position`.
+    pub(crate) fn resolve_at(&self, object: &Object, position: &Point) -> ResolvedMaterial {
+        ResolvedMaterial {
+            color: self.color_at(object, position),
+            ambient: self.ambient,
+            diffuse: self.diffuse,
+            specular: self.specular,
+            shininess: self.shininess,
+            reflective: self.reflective,
+            transparency: self.transparency,
+            refractive_index: self.refractive_index,
         }
     }
 
+    /// Computes the color of a point on a surface, lit by `light`.
+    /// `intensity` is the fraction of the light that reaches the point, from 0.0 when it is fully
+    /// shadowed to 1.0 when nothing is in the way. See [`Light::intensity_at`].
     pub(crate) fn lighting(
         &self,
         object: &Object,
         light: &Light,
         position: &Point,
-        eyev: &Tuple,
-        normalv: &Tuple,
-        in_shadow: bool,
+        eyev: &Vector,
+        normalv: &Vector,
+        intensity: f64,
     ) -> Color {
         // Variables to combine and return
         let mut diffuse = Color::new(0.0, 0.0, 0.0);
         let mut specular = Color::new(0.0, 0.0, 0.0);
         // combine the surface color with the light's color/intensity
-        let mut effective_color = self.color * light.get_intensity();
-        if let Some(pattern) = self.pattern {
-            effective_color =
-                Pattern::pattern_at_object(pattern, object, *position) * light.get_intensity();
-        }
-
-        // find the direction to the light source
-        let mut lightv = light.get_position() - *position;
-        lightv = lightv.normalize();
+        let effective_color = self.color_at(object, position) * light.get_intensity();
 
         // compute the ambient contribution
         let ambient = effective_color * self.ambient;
 
-        // light_dot_normal represents the cosine of the angle between the
-        // light vector and the normal vector. A negative number means the
-        // light is on the other side of the surface.
-        let light_dot_normal = Tuple::dot(&lightv, normalv);
-        if light_dot_normal < 0.0 {
-            // diffuse and specular shall be black.
-            // They are already initialized to black so we do nothing
-        } else {
+        // Area lights are sampled at several points, so average the contribution of each
+        let samples = light.samples(position);
+        for sample in &samples {
+            // find the direction to the light source
+            let lightv = light.direction_to(sample, position);
+
+            // light_dot_normal represents the cosine of the angle between the
+            // light vector and the normal vector. A negative number means the
+            // light is on the other side of the surface.
+            let light_dot_normal = dot(&lightv, normalv);
+            if light_dot_normal < 0.0 {
+                // diffuse and specular shall be black.
+                // Nothing is added for this sample.
+                continue;
+            }
+
+            // Light may get weaker the further it travels
+            let falloff = light.attenuation_at(sample, position);
+
             // Compute the diffuse contribution
-            diffuse = effective_color * self.diffuse * light_dot_normal;
+            diffuse = diffuse + effective_color * self.diffuse * light_dot_normal * falloff;
 
             // reflect_dot_eye represents the cosine of the angle between the
             // reflection vector and the eye vector. A negative number means the
             // light reflects away from the eye.
-            let reflectv = Tuple::reflect(&-lightv, normalv);
-            let reflect_dot_eye = Tuple::dot(&reflectv, eyev);
-            if reflect_dot_eye <= 0.0 {
-                // Light reflects away from the eye, so specular must be black.
-                // Do nothing since it is already initialized to black.
-            } else {
+            let reflectv = -lightv - *normalv * 2.0 * dot(&-lightv, normalv);
+            let reflect_dot_eye = dot(&reflectv, eyev);
+            if reflect_dot_eye > 0.0 {
                 // Compute the specular contribution
                 let factor = f64::powf(reflect_dot_eye, self.shininess);
-                specular = light.get_intensity() * self.specular * factor;
+                specular = specular + light.get_intensity() * self.specular * factor * falloff;
             }
         }
 
-        if !in_shadow {
-            // add the three contributions together to get the final shading
-            ambient + diffuse + specular
-        } else {
-            // Only ambient lighting applies if the zone is in shadow
-            ambient
-        }
+        // add the three contributions together to get the final shading.
+        // Only ambient lighting applies where the light is blocked.
+        let scale = intensity * light.spot_factor(position) / samples.len() as f64;
+        ambient + (diffuse + specular) * scale
     }
 }
 
+/// The dot product of the x, y and z components. Lighting only works with directions, so unlike
+/// [`Tuple::dot`](crate::ray_tracer::tuples::Tuple::dot) this doesn't look at `w` at all.
+fn dot(a: &Vector, b: &Vector) -> f64 {
+    a.x * b.x + a.y * b.y + a.z * b.z
+}
+
 impl Default for Material {
     fn default() -> Self {
         Self::new()
@@ -103,8 +159,13 @@ impl Default for Material {
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, PI};
 
-    use crate::ray_tracer::{tuples::Vector, utils::is_float_equal};
+    use crate::ray_tracer::{
+        tuples::{Tuple, Vector},
+        utils::is_float_equal,
+        world::World,
+    };
 
     use super::*;
 
@@ -132,8 +193,73 @@ mod tests {
             &Color::new(1.0, 1.0, 1.0),
         );
         let obj = new_sphere();
-        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, false);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+    #[test]
+    fn lighting_ignores_a_stray_w_in_its_vectors() {
+        let (m, position) = setup_lighting();
+        let eyev = Tuple::new(0.0, 0.0, -1.0, 1.0);
+        let normalv = Tuple::new(0.0, 0.0, -1.0, 0.5);
+        let light = Light::point_light(
+            &Tuple::new_point(0.0, 0.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        let obj = new_sphere();
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
+        assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+    }
+    #[test]
+    fn lighting_with_an_attenuated_light() {
+        let (m, position) = setup_lighting();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let mut light = Light::point_light(
+            &Tuple::new_point(0.0, 0.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        light.set_attenuation(0.0, 0.0, 0.01);
+        let obj = new_sphere();
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
+        // The ambient part is kept, diffuse and specular are divided by 0.01 * 10²
         assert_eq!(result, Color::new(1.9, 1.9, 1.9));
+
+        light.set_attenuation(1.0, 0.0, 0.01);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
+        assert_eq!(result, Color::new(1.0, 1.0, 1.0));
+    }
+    #[test]
+    fn a_directional_light_shades_distant_points_identically() {
+        let m = Material::new();
+        let eyev = Tuple::new_vector(0.0, 0.0, -1.0);
+        let normalv = Tuple::new_vector(0.0, 0.0, -1.0);
+        let light = Light::directional_light(
+            &Tuple::new_vector(1.0, -1.0, 1.0),
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        let obj = new_sphere();
+        let near = m.lighting(
+            &obj,
+            &light,
+            &Tuple::new_point(0.0, 0.0, 0.0),
+            &eyev,
+            &normalv,
+            1.0,
+        );
+        let far = m.lighting(
+            &obj,
+            &light,
+            &Tuple::new_point(1000.0, -500.0, 0.0),
+            &eyev,
+            &normalv,
+            1.0,
+        );
+        assert_eq!(near, far);
+        // ambient + diffuse * cos(angle between the normal and the light)
+        assert_eq!(
+            near,
+            Color::new(0.1, 0.1, 0.1) + Color::new(0.9, 0.9, 0.9) * (1.0 / 3.0_f64.sqrt())
+        );
     }
     #[test]
     fn lighting_with_the_eye_between_light_and_surface_eye_offset_45_degrees() {
@@ -145,7 +271,7 @@ mod tests {
             &Color::new(1.0, 1.0, 1.0),
         );
         let obj = new_sphere();
-        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, false);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
         assert_eq!(result, Color::new(1.0, 1.0, 1.0));
     }
     #[test]
@@ -158,7 +284,7 @@ mod tests {
             &Color::new(1.0, 1.0, 1.0),
         );
         let obj = new_sphere();
-        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, false);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
         assert_eq!(result, Color::new(0.7364, 0.7364, 0.7364));
     }
     #[test]
@@ -171,7 +297,7 @@ mod tests {
             &Color::new(1.0, 1.0, 1.0),
         );
         let obj = new_sphere();
-        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, false);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
         assert_eq!(result, Color::new(1.63639, 1.63639, 1.63639));
     }
     #[test]
@@ -184,7 +310,7 @@ mod tests {
             &Color::new(1.0, 1.0, 1.0),
         );
         let obj = new_sphere();
-        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, false);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 1.0);
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -197,9 +323,8 @@ mod tests {
             &Tuple::new_point(0.0, 0.0, -10.0),
             &Color::new(1.0, 1.0, 1.0),
         );
-        let in_shadow = true;
         let obj = new_sphere();
-        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, in_shadow);
+        let result = m.lighting(&obj, &light, &position, &eyev, &normalv, 0.0);
         assert_eq!(result, Color::new(0.1, 0.1, 0.1));
     }
 
@@ -215,6 +340,9 @@ mod tests {
             reflective: 0.0,
             transparency: 0.0,
             refractive_index: 1.0,
+            environment: None,
+            normal_pattern: None,
+            emissive: Color::new(0.0, 0.0, 0.0),
         };
         let eyev = Vector::new_vector(0.0, 0.0, -1.0);
         let normalv = Vector::new_vector(0.0, 0.0, -1.0);
@@ -229,7 +357,7 @@ mod tests {
             &Point::new_point(0.9, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
         let c2 = m.lighting(
             &obj,
@@ -237,12 +365,97 @@ mod tests {
             &Point::new_point(1.1, 0.0, 0.0),
             &eyev,
             &normalv,
-            false,
+            1.0,
         );
         assert_eq!(c1, Color::new(1.0, 1.0, 1.0));
         assert_eq!(c2, Color::new(0.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn lighting_uses_light_intensity_to_attenuate_color() {
+        let mut w = World::new_default_world();
+        w.lights = vec![Light::point_light(
+            &Point::new_point(0.0, 0.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+        let mut m = w.objects[0].get_material();
+        m.ambient = 0.1;
+        m.diffuse = 0.9;
+        m.specular = 0.0;
+        m.color = Color::new(1.0, 1.0, 1.0);
+        w.objects[0].set_material(&m);
+        let pt = Point::new_point(0.0, 0.0, -1.0);
+        let eyev = Vector::new_vector(0.0, 0.0, -1.0);
+        let normalv = Vector::new_vector(0.0, 0.0, -1.0);
+
+        let examples = [
+            (1.0, Color::new(1.0, 1.0, 1.0)),
+            (0.5, Color::new(0.55, 0.55, 0.55)),
+            (0.0, Color::new(0.1, 0.1, 0.1)),
+        ];
+        for (intensity, result) in examples {
+            let c = m.lighting(&w.objects[0], &w.lights[0], &pt, &eyev, &normalv, intensity);
+            assert_eq!(c, result);
+        }
+    }
+
+    #[test]
+    fn lighting_samples_the_area_light() {
+        let corner = Point::new_point(-0.5, -0.5, -5.0);
+        let v1 = Vector::new_vector(1.0, 0.0, 0.0);
+        let v2 = Vector::new_vector(0.0, 1.0, 0.0);
+        let mut light = Light::area_light(&corner, &v1, 2, &v2, 2, &Color::new(1.0, 1.0, 1.0));
+        light.set_jitter(false);
+        let mut shape = new_sphere();
+        let mut m = shape.get_material();
+        m.ambient = 0.1;
+        m.diffuse = 0.9;
+        m.specular = 0.0;
+        m.color = Color::new(1.0, 1.0, 1.0);
+        shape.set_material(&m);
+        let eye = Point::new_point(0.0, 0.0, -5.0);
+
+        let examples = [
+            (
+                Point::new_point(0.0, 0.0, -1.0),
+                Color::new(0.9965, 0.9965, 0.9965),
+            ),
+            (
+                Point::new_point(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+                Color::new(0.62318, 0.62318, 0.62318),
+            ),
+        ];
+        for (pt, result) in examples {
+            let eyev = (eye - pt).normalize();
+            let normalv = Vector::new_vector(pt.x, pt.y, pt.z);
+            let c = m.lighting(&shape, &light, &pt, &eyev, &normalv, 1.0);
+            assert_eq!(c, result);
+        }
+    }
+
+    #[test]
+    fn a_spot_light_only_lights_points_inside_its_cone() {
+        let (m, _) = setup_lighting();
+        let light = Light::spot_light(
+            &Point::new_point(0.0, 5.0, 0.0),
+            &Vector::new_vector(0.0, -1.0, 0.0),
+            PI / 8.0,
+            PI / 6.0,
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        let floor = new_plane();
+        let eyev = Vector::new_vector(0.0, 1.0, 0.0);
+        let normalv = Vector::new_vector(0.0, 1.0, 0.0);
+
+        let inside = Point::new_point(0.0, 0.0, 0.0);
+        let c = m.lighting(&floor, &light, &inside, &eyev, &normalv, 1.0);
+        assert_eq!(c, Color::new(1.9, 1.9, 1.9));
+
+        let outside = Point::new_point(5.0, 0.0, 0.0);
+        let c = m.lighting(&floor, &light, &outside, &eyev, &normalv, 1.0);
+        assert_eq!(c, Color::new(0.1, 0.1, 0.1));
+    }
+
     #[test]
     fn reflectivity_for_the_default_material() {
         let m = Material::default();
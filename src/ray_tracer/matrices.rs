@@ -1,9 +1,9 @@
 // Allow using `.get(0)` on vectors to make the matrix calculations more obvious
 #![allow(clippy::get_first)]
 
-use std::ops::Mul;
+use std::ops::{Index, IndexMut, Mul};
 
-use crate::ray_tracer::{tuples::Tuple, utils::is_float_equal};
+use crate::ray_tracer::{error::RayTracerError, tuples::Tuple, utils::is_float_equal};
 
 #[derive(Debug)]
 pub enum MatrixError {
@@ -14,10 +14,16 @@ pub enum MatrixError {
 }
 
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "MatrixData", into = "MatrixData")
+)]
 pub struct Matrix {
     matrix: [[f64; 4]; 4],
     size: usize,
     inverse: [[f64; 4]; 4],
+    inverse_transpose: [[f64; 4]; 4],
     is_inverted: bool,
 }
 
@@ -40,6 +46,7 @@ impl Matrix {
             matrix: [[0.0; 4]; 4],
             size: 4,
             inverse: [[0.0; 4]; 4],
+            inverse_transpose: [[0.0; 4]; 4],
             is_inverted: false,
         };
         let mut row_idx = 0;
@@ -60,6 +67,7 @@ impl Matrix {
                 matrix: [[0.0; 4]; 4],
                 size,
                 inverse: [[0.0; 4]; 4],
+                inverse_transpose: [[0.0; 4]; 4],
                 is_inverted: false,
             })
         } else {
@@ -77,6 +85,7 @@ impl Matrix {
             ],
             size: 4,
             inverse: [[0.0; 4]; 4],
+            inverse_transpose: [[0.0; 4]; 4],
             is_inverted: false,
         }
     }
@@ -85,8 +94,10 @@ impl Matrix {
         self.matrix[x][y]
     }
 
+    /// Changes one element. Any calculated inverse is out of date afterwards, so it has to be
+    /// calculated again.
     pub fn set_element(&mut self, x: usize, y: usize, val: f64) {
-        self.matrix[x][y] = val;
+        self[(x, y)] = val;
     }
 
     pub fn size(&self) -> usize {
@@ -109,6 +120,36 @@ impl Matrix {
                 matrix: self.inverse,
                 size: self.size,
                 inverse: [[0.0; 4]; 4],
+                inverse_transpose: [[0.0; 4]; 4],
+                is_inverted: false,
+            })
+        }
+    }
+
+    /// Multiplies `tuple` by the cached inverse without copying it into a new matrix.
+    /// Panics if the inverse hasn't been calculated.
+    pub(crate) fn mul_inverse(&self, tuple: &Tuple) -> Tuple {
+        assert!(self.is_inverted, "The inverse hasn't been calculated!");
+        mul_array(&self.inverse, tuple)
+    }
+
+    /// Multiplies `tuple` by the cached inverse transpose without copying it into a new matrix.
+    /// Panics if the inverse hasn't been calculated.
+    pub(crate) fn mul_inverse_transpose(&self, tuple: &Tuple) -> Tuple {
+        assert!(self.is_inverted, "The inverse hasn't been calculated!");
+        mul_array(&self.inverse_transpose, tuple)
+    }
+
+    /// Returns the transpose of the cached inverse, used to move normals into world space.
+    pub fn get_inverse_transpose(&self) -> Result<Matrix, MatrixError> {
+        if !self.is_inverted {
+            Err(MatrixError::NotInverted)
+        } else {
+            Ok(Matrix {
+                matrix: self.inverse_transpose,
+                size: self.size,
+                inverse: [[0.0; 4]; 4],
+                inverse_transpose: [[0.0; 4]; 4],
                 is_inverted: false,
             })
         }
@@ -172,15 +213,20 @@ impl Matrix {
     }
 
     fn minor(&self, row: usize, column: usize) -> f64 {
+        // The submatrix of a 2x2 matrix is the single element opposite the removed one.
+        if self.size == 2 {
+            return self.matrix[1 - row][1 - column];
+        }
+
         let submatrix = self.submatrix(row, column);
         submatrix.determinant()
     }
 
     fn cofactor(&self, row: usize, column: usize) -> f64 {
-        assert!(2 < self.size);
+        assert!(1 < self.size);
 
         let minor = self.minor(row, column);
-        if (row + column) % 2 == 0 {
+        if (row + column).is_multiple_of(2) {
             minor
         } else {
             -minor
@@ -202,16 +248,17 @@ impl Matrix {
             for column in 0..self.size {
                 let c = self.cofactor(row, column);
                 m2.matrix[column][row] = c / self.determinant();
+                self.inverse_transpose[row][column] = m2.matrix[column][row];
             }
         }
         self.inverse = m2.matrix;
         self.is_inverted = true;
         Ok(*self)
     }
-}
 
-impl PartialEq for Matrix {
-    fn eq(&self, other: &Self) -> bool {
+    /// Checks whether every element is within [`EPSILON`](crate::ray_tracer::utils::EPSILON)
+    /// of `other`'s. This is what `==` uses. Only the elements inside `self`'s size are compared.
+    pub fn approx_eq(&self, other: &Matrix) -> bool {
         let size = self.size();
 
         for i in 0..size {
@@ -229,6 +276,94 @@ impl PartialEq for Matrix {
 
         true
     }
+
+    /// Interpolates element by element between `self` at `t = 0` and `other` at `t = 1`.
+    /// Exact for translations and scalings; rotations are only approximated.
+    /// The inverse isn't calculated.
+    pub fn lerp(&self, other: &Matrix, t: f64) -> Matrix {
+        let mut m = Self::new_empty(self.size).unwrap();
+        for row in 0..self.size {
+            for column in 0..self.size {
+                m.matrix[row][column] = self.matrix[row][column]
+                    + (other.matrix[row][column] - self.matrix[row][column]) * t;
+            }
+        }
+        m
+    }
+
+    /// Checks that the matrix can be used as a transformation, i.e. that every element is a
+    /// number and that it can be inverted.
+    pub(crate) fn validate_transform(&self) -> Result<(), RayTracerError> {
+        if self.matrix.iter().flatten().any(|v| v.is_nan()) {
+            return Err(RayTracerError::NaNInput);
+        }
+        if !self.invertible() {
+            return Err(RayTracerError::SingularTransform);
+        }
+        Ok(())
+    }
+}
+
+/// Serialized form of a [`Matrix`]. The cached inverse is left out and recalculated on load.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct MatrixData {
+    matrix: [[f64; 4]; 4],
+    size: usize,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<MatrixData> for Matrix {
+    type Error = RayTracerError;
+
+    fn try_from(data: MatrixData) -> Result<Self, Self::Error> {
+        if !(2..=4).contains(&data.size) {
+            return Err(RayTracerError::InvalidDimensions);
+        }
+        let mut matrix = Matrix {
+            matrix: data.matrix,
+            size: data.size,
+            inverse: [[0.0; 4]; 4],
+            inverse_transpose: [[0.0; 4]; 4],
+            is_inverted: false,
+        };
+        // Non-invertible matrices simply stay without a cached inverse.
+        let _ = matrix.calculate_inverse();
+        Ok(matrix)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Matrix> for MatrixData {
+    fn from(matrix: Matrix) -> Self {
+        MatrixData {
+            matrix: matrix.matrix,
+            size: matrix.size,
+        }
+    }
+}
+
+impl PartialEq for Matrix {
+    fn eq(&self, other: &Self) -> bool {
+        self.approx_eq(other)
+    }
+}
+
+/// Reads the element at `(row, column)`, like [`Matrix::get_element`].
+impl Index<(usize, usize)> for Matrix {
+    type Output = f64;
+    fn index(&self, (row, column): (usize, usize)) -> &Self::Output {
+        &self.matrix[row][column]
+    }
+}
+
+/// Gives mutable access to the element at `(row, column)`. Any calculated inverse is out of date
+/// afterwards, so it has to be calculated again.
+impl IndexMut<(usize, usize)> for Matrix {
+    fn index_mut(&mut self, (row, column): (usize, usize)) -> &mut Self::Output {
+        self.is_inverted = false;
+        &mut self.matrix[row][column]
+    }
 }
 
 impl Mul for Matrix {
@@ -240,6 +375,7 @@ impl Mul for Matrix {
             matrix: [[0.0; 4], [0.0; 4], [0.0; 4], [0.0; 4]],
             size: 4,
             inverse: [[0.0; 4]; 4],
+            inverse_transpose: [[0.0; 4]; 4],
             is_inverted: false,
         };
         for row in 0..size {
@@ -258,23 +394,58 @@ impl Mul for Matrix {
 impl Mul<Tuple> for Matrix {
     type Output = Tuple;
     fn mul(self, rhs: Tuple) -> Self::Output {
-        let size = self.size();
-        assert_eq!(4, size);
-        let mut tup = [0.0; 4];
-        for (row, item) in tup.iter_mut().enumerate().take(size) {
-            *item = self.matrix[row][0] * rhs.x
-                + self.matrix[row][1] * rhs.y
-                + self.matrix[row][2] * rhs.z
-                + self.matrix[row][3] * rhs.w;
-        }
+        assert_eq!(4, self.size());
+        mul_array(&self.matrix, &rhs)
+    }
+}
 
-        Tuple::new(tup[0], tup[1], tup[2], tup[3])
+/// Multiplies a 4x4 matrix, given as its elements, by a tuple.
+fn mul_array(matrix: &[[f64; 4]; 4], rhs: &Tuple) -> Tuple {
+    let mut tup = [0.0; 4];
+    for (row, item) in tup.iter_mut().enumerate() {
+        *item = matrix[row][0] * rhs.x
+            + matrix[row][1] * rhs.y
+            + matrix[row][2] * rhs.z
+            + matrix[row][3] * rhs.w;
     }
+
+    Tuple::new(tup[0], tup[1], tup[2], tup[3])
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::ray_tracer::transformations::Transform;
+
+    #[test]
+    fn indexing_a_matrix() {
+        let mut m = Matrix::new_identity();
+        assert_eq!(m[(0, 0)], 1.0);
+        assert_eq!(m[(1, 2)], 0.0);
+        m[(1, 2)] = 3.0;
+        assert_eq!(m[(1, 2)], 3.0);
+        assert_eq!(m.get_element(1, 2), 3.0);
+        m.set_element(2, 1, -2.0);
+        assert_eq!(m[(2, 1)], -2.0);
+    }
+    #[test]
+    fn changing_an_element_discards_the_inverse() {
+        let mut m = Transform::scaling(2.0, 2.0, 2.0);
+        m.calculate_inverse().unwrap();
+        m[(0, 0)] = 4.0;
+        assert!(!m.is_inverted());
+        assert!(m.get_inverted().is_err());
+        m.calculate_inverse().unwrap();
+        assert_eq!(m.get_inverted().unwrap()[(0, 0)], 0.25);
+    }
+    #[test]
+    fn interpolating_between_two_translations() {
+        let a = Transform::translate(0.0, 2.0, -4.0);
+        let b = Transform::translate(4.0, 2.0, 0.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.25), Transform::translate(1.0, 2.0, -3.0));
+    }
 
     #[test]
     fn constructing_and_inspecting_a_4_x_4_matrix() {
@@ -429,6 +600,34 @@ mod tests {
         assert_eq!(a * b, ab);
     }
 
+    #[test]
+    fn a_matrix_multiplied_by_a_point_includes_the_translation() {
+        let a = Matrix::new(vec![
+            vec![1.0, 2.0, 3.0, 4.0],
+            vec![2.0, 4.0, 4.0, 2.0],
+            vec![8.0, 6.0, 4.0, 1.0],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let p = Tuple::new_point(1.0, 2.0, 3.0);
+
+        assert_eq!(a * p, Tuple::new_point(18.0, 24.0, 33.0));
+    }
+
+    #[test]
+    fn a_matrix_multiplied_by_a_vector_ignores_the_translation() {
+        let a = Matrix::new(vec![
+            vec![1.0, 2.0, 3.0, 4.0],
+            vec![2.0, 4.0, 4.0, 2.0],
+            vec![8.0, 6.0, 4.0, 1.0],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let v = Tuple::new_vector(1.0, 2.0, 3.0);
+
+        assert_eq!(a * v, Tuple::new_vector(14.0, 22.0, 32.0));
+    }
+
     #[test]
     fn a_matrix_multiplied_by_a_tuple() {
         let a = Matrix::new(vec![
@@ -633,6 +832,7 @@ mod tests {
             matrix: a.calculate_inverse().unwrap().inverse,
             size: a.size(),
             inverse: Matrix::new_empty(a.size()).unwrap().matrix,
+            inverse_transpose: [[0.0; 4]; 4],
             is_inverted: false,
         };
 
@@ -652,6 +852,66 @@ mod tests {
         assert_eq!(b, b_comp);
     }
 
+    #[test]
+    fn calculating_the_inverse_of_a_2x2_matrix() {
+        let mut a = Matrix::new(vec![vec![4.0, 7.0], vec![2.0, 6.0]]).unwrap();
+        let b = Matrix {
+            matrix: a.calculate_inverse().unwrap().inverse,
+            size: a.size(),
+            inverse: Matrix::new_empty(a.size()).unwrap().matrix,
+            inverse_transpose: [[0.0; 4]; 4],
+            is_inverted: false,
+        };
+
+        let b_comp = Matrix::new(vec![vec![0.6, -0.7], vec![-0.2, 0.4]]).unwrap();
+        assert_eq!(b, b_comp);
+    }
+
+    #[test]
+    fn calculating_the_inverse_of_a_3x3_matrix() {
+        let mut a = Matrix::new(vec![
+            vec![2.0, 0.0, 0.0],
+            vec![0.0, 4.0, 0.0],
+            vec![1.0, 0.0, 1.0],
+        ])
+        .unwrap();
+        let b = Matrix {
+            matrix: a.calculate_inverse().unwrap().inverse,
+            size: a.size(),
+            inverse: Matrix::new_empty(a.size()).unwrap().matrix,
+            inverse_transpose: [[0.0; 4]; 4],
+            is_inverted: false,
+        };
+
+        let b_comp = Matrix::new(vec![
+            vec![0.5, 0.0, 0.0],
+            vec![0.0, 0.25, 0.0],
+            vec![-0.5, 0.0, 1.0],
+        ])
+        .unwrap();
+        assert_eq!(b, b_comp);
+    }
+
+    #[test]
+    fn calculating_the_inverse_also_caches_its_transpose() {
+        let mut a = Matrix::new(vec![
+            vec![-5.0, 2.0, 6.0, -8.0],
+            vec![1.0, -5.0, 1.0, 8.0],
+            vec![7.0, 7.0, -6.0, -7.0],
+            vec![1.0, -3.0, 7.0, 4.0],
+        ])
+        .unwrap();
+        assert!(matches!(
+            a.get_inverse_transpose(),
+            Err(MatrixError::NotInverted)
+        ));
+
+        a.calculate_inverse().unwrap();
+        let inverse = a.get_inverted().unwrap();
+        let inverse_transpose = a.get_inverse_transpose().unwrap();
+        assert_eq!(inverse_transpose, inverse.transpose().unwrap());
+    }
+
     #[test]
     fn calculating_the_inverse_of_another_matrix() {
         let mut a = Matrix::new(vec![
@@ -673,6 +933,7 @@ mod tests {
             matrix: a.calculate_inverse().unwrap().inverse,
             size: a.size(),
             inverse: Matrix::new_empty(a.size()).unwrap().matrix,
+            inverse_transpose: [[0.0; 4]; 4],
             is_inverted: false,
         };
 
@@ -700,6 +961,7 @@ mod tests {
             matrix: a.calculate_inverse().unwrap().inverse,
             size: a.size(),
             inverse: Matrix::new_empty(a.size()).unwrap().matrix,
+            inverse_transpose: [[0.0; 4]; 4],
             is_inverted: false,
         };
 
@@ -729,4 +991,21 @@ mod tests {
             a
         );
     }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserializing_a_matrix_checks_its_size() {
+        let m = Transform::translate(1.0, 2.0, 3.0);
+        let json = serde_json::to_string(&m).unwrap();
+        let loaded: Matrix = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, m);
+        assert_eq!(
+            loaded.get_inverted().unwrap(),
+            Transform::translate(-1.0, -2.0, -3.0)
+        );
+
+        for size in [0, 1, 5, 1000] {
+            let bad = json.replace("\"size\":4", &format!("\"size\":{size}"));
+            assert!(serde_json::from_str::<Matrix>(&bad).is_err());
+        }
+    }
 }
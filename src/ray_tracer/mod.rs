@@ -1,13 +1,20 @@
+pub mod bounds;
 pub mod camera;
 pub mod canvas;
 pub mod colors;
+pub mod error;
 pub mod intersections;
 pub mod lights;
 pub mod materials;
 pub mod matrices;
+pub mod noise;
 pub mod patterns;
 pub mod rays;
+pub mod sampler;
+#[cfg(feature = "scene")]
+pub mod scene;
 pub mod shapes;
+pub mod stats;
 pub mod transformations;
 pub mod tuples;
 pub mod utils;
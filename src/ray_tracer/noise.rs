@@ -0,0 +1,211 @@
+//! Deterministic gradient (Perlin) noise.
+//! The permutation table is generated from a seed, so the same point always gives the same
+//! value, across runs and across threads.
+
+use crate::ray_tracer::tuples::Point;
+
+/// Seed used by [`noise`].
+pub const DEFAULT_SEED: u32 = 0x2545_f491;
+
+const DEFAULT: Perlin = Perlin::new(DEFAULT_SEED);
+
+/// A Perlin noise generator with its own permutation table.
+/// Generators with different seeds give unrelated noise fields.
+/// Only the seed is serialized; the permutation table is rebuilt from it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "u32", into = "u32")
+)]
+pub struct Perlin {
+    seed: u32,
+    permutation: [u8; 256],
+}
+
+#[cfg(feature = "serde")]
+impl From<u32> for Perlin {
+    fn from(seed: u32) -> Self {
+        Perlin::new(seed)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<Perlin> for u32 {
+    fn from(perlin: Perlin) -> Self {
+        perlin.seed
+    }
+}
+
+/// Shuffles the numbers 0..=255 with a xorshift generator.
+const fn permutation(seed: u32) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = i as u8;
+        i += 1;
+    }
+
+    // Xorshift gets stuck on a zero state
+    let mut state = if seed == 0 { DEFAULT_SEED } else { seed };
+    let mut i = 255;
+    while i > 0 {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        let j = (state % (i as u32 + 1)) as usize;
+        let tmp = table[i];
+        table[i] = table[j];
+        table[j] = tmp;
+        i -= 1;
+    }
+    table
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+/// Dot product between the distance vector and one of twelve gradient directions.
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    let u = if h & 1 == 0 { u } else { -u };
+    let v = if h & 2 == 0 { v } else { -v };
+    u + v
+}
+
+impl Perlin {
+    pub const fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            permutation: permutation(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    fn hash(&self, i: usize) -> usize {
+        self.permutation[i & 255] as usize
+    }
+
+    /// Returns the noise value at the point, in the range [-1, 1].
+    /// The value is 0 at every integer lattice point and varies smoothly in between.
+    pub fn noise(&self, point: Point) -> f64 {
+        let (xf, yf, zf) = (point.x.floor(), point.y.floor(), point.z.floor());
+        let (x, y, z) = (point.x - xf, point.y - yf, point.z - zf);
+        // Wrap the lattice coordinates onto the permutation table
+        let xi = (xf as i64).rem_euclid(256) as usize;
+        let yi = (yf as i64).rem_euclid(256) as usize;
+        let zi = (zf as i64).rem_euclid(256) as usize;
+
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        let a = self.hash(xi) + yi;
+        let aa = self.hash(a) + zi;
+        let ab = self.hash(a + 1) + zi;
+        let b = self.hash(xi + 1) + yi;
+        let ba = self.hash(b) + zi;
+        let bb = self.hash(b + 1) + zi;
+
+        let value = lerp(
+            w,
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(self.hash(aa), x, y, z),
+                    grad(self.hash(ba), x - 1.0, y, z),
+                ),
+                lerp(
+                    u,
+                    grad(self.hash(ab), x, y - 1.0, z),
+                    grad(self.hash(bb), x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(self.hash(aa + 1), x, y, z - 1.0),
+                    grad(self.hash(ba + 1), x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(self.hash(ab + 1), x, y - 1.0, z - 1.0),
+                    grad(self.hash(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        );
+
+        value.clamp(-1.0, 1.0)
+    }
+}
+
+/// Returns the value of the default noise field at the point, in the range [-1, 1].
+pub fn noise(point: Point) -> f64 {
+    DEFAULT.noise(point)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray_tracer::tuples::Tuple;
+
+    #[test]
+    fn the_permutation_table_contains_every_index_once() {
+        let mut seen = [false; 256];
+        for p in DEFAULT.permutation {
+            assert!(!seen[p as usize]);
+            seen[p as usize] = true;
+        }
+    }
+
+    #[test]
+    fn noise_is_zero_on_lattice_points() {
+        for (x, y, z) in [(0.0, 0.0, 0.0), (1.0, 2.0, 3.0), (-4.0, 7.0, -1.0)] {
+            assert_eq!(noise(Tuple::new_point(x, y, z)), 0.0);
+        }
+    }
+
+    #[test]
+    fn noise_is_deterministic_and_bounded() {
+        let mut nonzero = false;
+        for i in 0..100 {
+            let p = Tuple::new_point(i as f64 * 0.37, i as f64 * 0.11, i as f64 * -0.23);
+            let n = noise(p);
+            assert_eq!(n, noise(p));
+            assert!((-1.0..=1.0).contains(&n));
+            nonzero |= n != 0.0;
+        }
+        assert!(nonzero);
+    }
+
+    #[test]
+    fn different_seeds_give_different_noise() {
+        let a = Perlin::new(1);
+        let b = Perlin::new(2);
+        let differs = (0..20).any(|i| {
+            let p = Tuple::new_point(i as f64 * 0.37, 0.5, 0.25);
+            a.noise(p) != b.noise(p)
+        });
+        assert!(differs);
+        assert_eq!(
+            Perlin::new(DEFAULT_SEED).noise(Tuple::new_point(0.3, 0.4, 0.5)),
+            noise(Tuple::new_point(0.3, 0.4, 0.5))
+        );
+    }
+}
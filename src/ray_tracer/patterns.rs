@@ -6,37 +6,89 @@ use self::test_pattern::TestPattern;
 #[cfg(test)]
 use std::io::ErrorKind;
 
-use crate::ray_tracer::{colors::Color, matrices::Matrix, shapes::*, tuples::Point};
+use crate::ray_tracer::{
+    canvas::Canvas, colors::Color, matrices::Matrix, noise, shapes::*, tuples::Point,
+};
 
-use self::{checker::Checker, gradient::Gradient, rings::Ring, solid::Solid, stripes::Stripes};
+use self::{
+    blend::Blend, checker::Checker, cube_map::CubeMap, fbm::Fbm, gradient::Gradient, grid::Grid,
+    perturb::Perturb, rings::Ring, solid::Solid, stripes::Stripes, uv_image::UvImage,
+};
 
+pub mod blend;
 pub mod checker;
+pub mod cube_map;
+pub mod fbm;
 pub mod gradient;
+pub mod grid;
+pub mod perturb;
 pub mod rings;
 pub mod solid;
 pub mod stripes;
+pub mod uv_image;
+
+pub use self::{cube_map::CubeFace, uv_image::UvMapping};
 
 #[cfg(test)]
 pub(crate) mod test_pattern;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 enum PatternType {
     Stripes(Stripes),
     Gradient(Gradient),
     Ring(Ring),
     Checker(Checker),
     Solid(Solid),
+    Fbm(Fbm),
+    Perturb(Perturb),
+    Blend(Blend),
+    UvImage(UvImage),
+    CubeMap(CubeMap),
+    Grid(Grid),
 
     #[cfg(test)]
     TestPattern(TestPattern),
 }
 
+/// One of the "colors" of a pattern like stripes or checkers, which can itself be a pattern.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PatternColor {
+    Solid(Color),
+    /// A pattern nested inside another. The nested pattern's transform is applied on top of
+    /// the outer pattern's.
+    Nested(Box<Pattern>),
+}
+
+impl PatternColor {
+    fn color_at(&self, point: Point) -> Color {
+        match self {
+            PatternColor::Solid(color) => *color,
+            PatternColor::Nested(pattern) => pattern.inner_pattern_at(point),
+        }
+    }
+}
+
+impl From<Color> for PatternColor {
+    fn from(color: Color) -> Self {
+        PatternColor::Solid(color)
+    }
+}
+
+impl From<Pattern> for PatternColor {
+    fn from(pattern: Pattern) -> Self {
+        PatternColor::Nested(Box::new(pattern))
+    }
+}
+
 #[clonable]
 pub(crate) trait Patterns: Debug + Clone + Sync {
     fn color_at(&self, point: Point) -> Color;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pattern {
     pattern: PatternType,
     transform: Matrix,
@@ -48,15 +100,15 @@ impl Pattern {
             transform: Matrix::new_identity().calculate_inverse().unwrap(),
         }
     }
-    pub fn stripe(color_a: Color, color_b: Color) -> Self {
+    pub fn stripe(color_a: impl Into<PatternColor>, color_b: impl Into<PatternColor>) -> Self {
         Pattern {
-            pattern: PatternType::Stripes(Stripes::new(color_a, color_b)),
+            pattern: PatternType::Stripes(Stripes::new(color_a.into(), color_b.into())),
             transform: Matrix::new_identity().calculate_inverse().unwrap(),
         }
     }
-    pub fn gradient(color_a: Color, color_b: Color) -> Self {
+    pub fn gradient(color_a: impl Into<PatternColor>, color_b: impl Into<PatternColor>) -> Self {
         Pattern {
-            pattern: PatternType::Gradient(Gradient::new(color_a, color_b)),
+            pattern: PatternType::Gradient(Gradient::new(color_a.into(), color_b.into())),
             transform: Matrix::new_identity().calculate_inverse().unwrap(),
         }
     }
@@ -80,9 +132,9 @@ impl Pattern {
         }
     }
 
-    pub fn checker(color_a: Color, color_b: Color) -> Self {
+    pub fn checker(color_a: impl Into<PatternColor>, color_b: impl Into<PatternColor>) -> Self {
         Pattern {
-            pattern: PatternType::Checker(Checker::new(color_a, color_b)),
+            pattern: PatternType::Checker(Checker::new(color_a.into(), color_b.into())),
             transform: Matrix::new_identity().calculate_inverse().unwrap(),
         }
     }
@@ -106,25 +158,113 @@ impl Pattern {
         }
     }
 
+    /// Returns the average of the colors of two patterns.
+    pub fn blend(a: Pattern, b: Pattern) -> Self {
+        Pattern {
+            pattern: PatternType::Blend(Blend::new(a, b)),
+            transform: Matrix::new_identity().calculate_inverse().unwrap(),
+        }
+    }
+
+    /// Perturbs the inner pattern with `octaves` octaves of noise, for natural looking textures
+    /// like marble or wood. Each octave has double the frequency of the previous one, and its
+    /// amplitude is multiplied by `persistence`.
+    pub fn fbm(inner: Pattern, octaves: usize, persistence: f64) -> Self {
+        Pattern {
+            pattern: PatternType::Fbm(Fbm::new(inner, octaves, persistence)),
+            transform: Matrix::new_identity().calculate_inverse().unwrap(),
+        }
+    }
+
+    /// Jitters the points given to the inner pattern by up to `scale` along each axis, using Perlin
+    /// noise. Turns clean stripes or rings into wavy, wood grain like patterns.
+    pub fn perturb(inner: Pattern, scale: f64) -> Self {
+        Self::perturb_with_seed(inner, scale, noise::DEFAULT_SEED)
+    }
+
+    /// Like [`Pattern::perturb`], but with its own noise field. Patterns with the same seed are
+    /// perturbed identically.
+    pub fn perturb_with_seed(inner: Pattern, scale: f64, seed: u32) -> Self {
+        Pattern {
+            pattern: PatternType::Perturb(Perturb::new(inner, scale, seed)),
+            transform: Matrix::new_identity().calculate_inverse().unwrap(),
+        }
+    }
+
+    /// Returns the noise scale and seed if this is a perturbed pattern.
+    pub fn get_perturbation(&self) -> Option<(f64, u32)> {
+        match &self.pattern {
+            PatternType::Perturb(p) => Some((p.get_scale(), p.get_seed())),
+            _ => None,
+        }
+    }
+
+    /// Wraps an image around a sphere, e.g. an equirectangular map of the earth.
+    pub fn uv_image(canvas: Canvas) -> Self {
+        Self::uv_image_with_mapping(canvas, UvMapping::Spherical)
+    }
+
+    /// Samples an image at the `(u, v)` coordinates given by `mapping`.
+    pub fn uv_image_with_mapping(canvas: Canvas, mapping: UvMapping) -> Self {
+        Pattern {
+            pattern: PatternType::UvImage(UvImage::new(canvas, mapping)),
+            transform: Matrix::new_identity().calculate_inverse().unwrap(),
+        }
+    }
+
+    /// A different pattern for each face of a cube, ordered like [`CubeFace`]: left, front,
+    /// right, back, up and down. Each face pattern is sampled on the unit square of its xz plane.
+    pub fn cube_map(faces: [Pattern; 6]) -> Self {
+        Pattern {
+            pattern: PatternType::CubeMap(CubeMap::new(faces)),
+            transform: Matrix::new_identity().calculate_inverse().unwrap(),
+        }
+    }
+
+    /// Lines of `line_color` at every multiple of `spacing` along x and z, on a background of
+    /// `fill_color`. `thickness` is the width of the lines in pattern space.
+    /// Makes a handy floor for judging scale and perspective while setting up a scene.
+    pub fn grid(line_color: Color, fill_color: Color, spacing: f64, thickness: f64) -> Self {
+        Pattern {
+            pattern: PatternType::Grid(Grid::new(line_color, fill_color, spacing, thickness)),
+            transform: Matrix::new_identity().calculate_inverse().unwrap(),
+        }
+    }
+
     fn pattern_at(&self, point: Point) -> Color {
-        match self.pattern {
+        match &self.pattern {
             PatternType::Stripes(s) => s.color_at(point),
             PatternType::Gradient(g) => g.color_at(point),
             PatternType::Ring(r) => r.color_at(point),
             PatternType::Checker(c) => c.color_at(point),
             PatternType::Solid(s) => s.color_at(point),
+            PatternType::Fbm(f) => f.color_at(point),
+            PatternType::Perturb(p) => p.color_at(point),
+            PatternType::Blend(b) => b.color_at(point),
+            PatternType::UvImage(u) => u.color_at(point),
+            PatternType::CubeMap(c) => c.color_at(point),
+            PatternType::Grid(g) => g.color_at(point),
 
             #[cfg(test)]
             PatternType::TestPattern(tp) => tp.color_at(point),
         }
     }
+    /// Color of a pattern nested inside another pattern, or of a pattern at a point that is
+    /// already in object space.
+    /// `point` is in the outer pattern's space, so only this pattern's transform is applied.
+    pub(crate) fn inner_pattern_at(&self, point: Point) -> Color {
+        let pattern_point = self.transform.mul_inverse(&point);
+        self.pattern_at(pattern_point)
+    }
+    /// Color of the pattern at `world_point` on `object`. The point is taken into the object's
+    /// space, including any groups the object is in, and then into the pattern's space.
     pub(crate) fn pattern_at_object(
-        pattern: Pattern,
+        pattern: &Pattern,
         object: &Object,
         world_point: Point,
     ) -> Color {
-        let object_point = object.get_transform().get_inverted().unwrap() * world_point;
-        let pattern_point = pattern.get_transform().get_inverted().unwrap() * object_point;
+        let object_point = object.world_to_object(&world_point);
+        let pattern_point = pattern.transform.mul_inverse(&object_point);
 
         pattern.pattern_at(pattern_point)
     }
@@ -143,8 +283,8 @@ impl Pattern {
 #[cfg(test)]
 impl Pattern {
     fn get_stripe(&self) -> Result<Stripes, ErrorKind> {
-        match self.pattern {
-            PatternType::Stripes(s) => Ok(s),
+        match &self.pattern {
+            PatternType::Stripes(s) => Ok(s.clone()),
             _ => Err(ErrorKind::NotFound),
         }
     }
@@ -171,7 +311,7 @@ mod tests {
                 .unwrap(),
         );
         let pattern = Pattern::test_pattern_default();
-        let c = Pattern::pattern_at_object(pattern, &object, Point::new_point(2.0, 3.0, 4.0));
+        let c = Pattern::pattern_at_object(&pattern, &object, Point::new_point(2.0, 3.0, 4.0));
         assert_eq!(c, Color::new(1.0, 1.5, 2.0));
     }
     #[test]
@@ -183,7 +323,7 @@ mod tests {
                 .calculate_inverse()
                 .unwrap(),
         );
-        let c = Pattern::pattern_at_object(pattern, &object, Point::new_point(2.0, 3.0, 4.0));
+        let c = Pattern::pattern_at_object(&pattern, &object, Point::new_point(2.0, 3.0, 4.0));
         assert_eq!(c, Color::new(1.0, 1.5, 2.0));
     }
     #[test]
@@ -200,7 +340,7 @@ mod tests {
                 .calculate_inverse()
                 .unwrap(),
         );
-        let c = Pattern::pattern_at_object(pattern, &object, Point::new_point(2.5, 3.0, 3.5));
+        let c = Pattern::pattern_at_object(&pattern, &object, Point::new_point(2.5, 3.0, 3.5));
         assert_eq!(c, Color::new(0.75, 0.5, 0.25));
     }
 
@@ -215,4 +355,78 @@ mod tests {
         pattern.set_transform(Transform::translate(1.0, 2.0, 3.0));
         assert_eq!(pattern.transform, Transform::translate(1.0, 2.0, 3.0));
     }
+
+    #[test]
+    fn a_pattern_on_an_object_inside_a_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut s = new_sphere();
+        s.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        g.add_child(s);
+        let object = g.child(0).unwrap();
+
+        let pattern = Pattern::test_pattern_default();
+        let c = Pattern::pattern_at_object(&pattern, object, Point::new_point(10.0, 2.0, -1.0));
+        assert_eq!(c, Color::new(0.0, 1.0, -0.5));
+
+        let stripes = Pattern::stripe(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
+        let c = Pattern::pattern_at_object(&stripes, object, Point::new_point(11.5, 0.0, 0.0));
+        assert_eq!(c, Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn checker_ring_and_gradient_respect_the_pattern_transformation() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let object = new_sphere();
+        let point = Point::new_point(1.5, 0.0, 0.0);
+
+        let mut checker = Pattern::checker(white, black);
+        assert_eq!(Pattern::pattern_at_object(&checker, &object, point), black);
+        checker.set_transform(Transform::scaling(2.0, 2.0, 2.0));
+        assert_eq!(Pattern::pattern_at_object(&checker, &object, point), white);
+
+        let mut ring = Pattern::ring(white, black);
+        assert_eq!(Pattern::pattern_at_object(&ring, &object, point), black);
+        ring.set_transform(Transform::scaling(2.0, 2.0, 2.0));
+        assert_eq!(Pattern::pattern_at_object(&ring, &object, point), white);
+
+        let mut gradient = Pattern::gradient(white, black);
+        assert_eq!(
+            Pattern::pattern_at_object(&gradient, &object, point),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        gradient.set_transform(Transform::scaling(2.0, 2.0, 2.0));
+        assert_eq!(
+            Pattern::pattern_at_object(&gradient, &object, point),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
+
+    #[test]
+    fn a_stripe_inside_each_square_of_a_checker() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let red = Color::new(1.0, 0.0, 0.0);
+        let mut stripes = Pattern::stripe(white, black);
+        stripes.set_transform(Transform::scaling(0.5, 0.5, 0.5));
+        let pattern = Pattern::checker(stripes, red);
+
+        // The first square has stripes half a unit wide, the next one is solid
+        assert_eq!(pattern.pattern_at(Point::new_point(0.25, 0.0, 0.0)), white);
+        assert_eq!(pattern.pattern_at(Point::new_point(0.75, 0.0, 0.0)), black);
+        assert_eq!(pattern.pattern_at(Point::new_point(1.25, 0.0, 0.0)), red);
+        assert_eq!(pattern.pattern_at(Point::new_point(1.75, 0.0, 0.0)), red);
+    }
+    #[test]
+    fn nested_patterns_use_the_outer_pattern_space() {
+        let object = new_sphere();
+        let mut inner = Pattern::test_pattern_default();
+        inner.set_transform(Transform::translate(1.0, 0.0, 0.0));
+        let mut outer = Pattern::stripe(inner, Color::new(0.0, 0.0, 0.0));
+        outer.set_transform(Transform::scaling(4.0, 4.0, 4.0));
+
+        let c = Pattern::pattern_at_object(&outer, &object, Point::new_point(2.0, 4.0, 0.0));
+        assert_eq!(c, Color::new(-0.5, 1.0, 0.0));
+    }
 }
@@ -0,0 +1,59 @@
+use crate::ray_tracer::{colors::Color, tuples::Point};
+
+use super::{Pattern, Patterns};
+
+/// The average of two patterns.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) struct Blend {
+    pattern_a: Box<Pattern>,
+    pattern_b: Box<Pattern>,
+}
+
+impl Blend {
+    pub(super) fn new(pattern_a: Pattern, pattern_b: Pattern) -> Self {
+        Self {
+            pattern_a: Box::new(pattern_a),
+            pattern_b: Box::new(pattern_b),
+        }
+    }
+}
+
+impl Patterns for Blend {
+    fn color_at(&self, point: Point) -> Color {
+        (self.pattern_a.inner_pattern_at(point) + self.pattern_b.inner_pattern_at(point)) * 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::ray_tracer::{transformations::Transform, tuples::Tuple};
+
+    const WHITE: Color = Color {
+        red: 1.0,
+        green: 1.0,
+        blue: 1.0,
+    };
+    const BLACK: Color = Color {
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+    };
+
+    #[test]
+    fn blending_two_perpendicular_stripe_patterns() {
+        let along_x = Pattern::stripe(WHITE, BLACK);
+        let mut along_z = Pattern::stripe(WHITE, BLACK);
+        along_z.set_transform(Transform::rotation_y(PI / 2.0));
+        let pattern = Pattern::blend(along_x, along_z);
+
+        let grey = Color::new(0.5, 0.5, 0.5);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.5, 0.0, -0.5)), WHITE);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(1.5, 0.0, -0.5)), grey);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.5, 0.0, 0.5)), grey);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(1.5, 0.0, 0.5)), BLACK);
+    }
+}
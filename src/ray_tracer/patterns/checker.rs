@@ -1,15 +1,16 @@
 use crate::ray_tracer::{colors::Color, tuples};
 
-use super::Patterns;
+use super::{PatternColor, Patterns};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct Checker {
-    color_a: Color,
-    color_b: Color,
+    color_a: PatternColor,
+    color_b: PatternColor,
 }
 
 impl Checker {
-    pub(super) fn new(color_a: Color, color_b: Color) -> Self {
+    pub(super) fn new(color_a: PatternColor, color_b: PatternColor) -> Self {
         Self { color_a, color_b }
     }
 }
@@ -17,8 +18,8 @@ impl Checker {
 impl Default for Checker {
     fn default() -> Self {
         Self {
-            color_a: Color::new(1.0, 1.0, 1.0),
-            color_b: Color::new(0.0, 0.0, 0.0),
+            color_a: PatternColor::Solid(Color::new(1.0, 1.0, 1.0)),
+            color_b: PatternColor::Solid(Color::new(0.0, 0.0, 0.0)),
         }
     }
 }
@@ -26,9 +27,9 @@ impl Default for Checker {
 impl Patterns for Checker {
     fn color_at(&self, point: tuples::Point) -> Color {
         if (point.x.floor() + point.y.floor() + point.z.floor()) as isize % 2 == 0 {
-            self.color_a
+            self.color_a.color_at(point)
         } else {
-            self.color_b
+            self.color_b.color_at(point)
         }
     }
 }
@@ -71,4 +72,11 @@ mod tests {
         assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 0.99)), WHITE);
         assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 0.0, 1.01)), BLACK);
     }
+    #[test]
+    fn checkers_alternate_for_negative_coordinates() {
+        let pattern = Pattern::checker(WHITE, BLACK);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(-0.5, 0.0, 0.0)), BLACK);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(-1.5, 0.0, 0.0)), WHITE);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(-0.5, -0.5, 0.0)), WHITE);
+    }
 }
@@ -0,0 +1,162 @@
+use crate::ray_tracer::{
+    colors::Color,
+    tuples::{Point, Tuple},
+};
+
+use super::{Pattern, Patterns};
+
+/// The faces of a cube, in the order [`Pattern::cube_map`] expects their patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    Left = 0,
+    Front = 1,
+    Right = 2,
+    Back = 3,
+    Up = 4,
+    Down = 5,
+}
+
+/// Picks the face of an axis aligned cube a point belongs to, by its largest coordinate.
+pub fn cube_face(point: Point) -> CubeFace {
+    let coord = point.x.abs().max(point.y.abs()).max(point.z.abs());
+    if coord == point.x {
+        CubeFace::Right
+    } else if coord == -point.x {
+        CubeFace::Left
+    } else if coord == point.y {
+        CubeFace::Up
+    } else if coord == -point.y {
+        CubeFace::Down
+    } else if coord == point.z {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    }
+}
+
+/// Maps a point on the cube from -1 to 1 to its face and the `(u, v)` coordinates on that face.
+/// Every face is unfolded as seen from outside the cube, with `v` pointing up, or towards -z
+/// for the top face and +z for the bottom face.
+pub fn uv_cube(point: Point) -> (CubeFace, f64, f64) {
+    let face = cube_face(point);
+    let wrap = |value: f64| value.rem_euclid(2.0) / 2.0;
+    let (x, y, z) = (point.x, point.y, point.z);
+    let (u, v) = match face {
+        CubeFace::Front => (wrap(x + 1.0), wrap(y + 1.0)),
+        CubeFace::Back => (wrap(1.0 - x), wrap(y + 1.0)),
+        CubeFace::Left => (wrap(z + 1.0), wrap(y + 1.0)),
+        CubeFace::Right => (wrap(1.0 - z), wrap(y + 1.0)),
+        CubeFace::Up => (wrap(x + 1.0), wrap(1.0 - z)),
+        CubeFace::Down => (wrap(x + 1.0), wrap(z + 1.0)),
+    };
+    (face, u, v)
+}
+
+/// A different pattern on each face of a cube.
+/// The face patterns are sampled at `(u, 0, v)`, i.e. on the unit square of the xz plane.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) struct CubeMap {
+    faces: Box<[Pattern; 6]>,
+}
+
+impl CubeMap {
+    pub(super) fn new(faces: [Pattern; 6]) -> Self {
+        Self {
+            faces: Box::new(faces),
+        }
+    }
+}
+
+impl Patterns for CubeMap {
+    fn color_at(&self, point: Point) -> Color {
+        let (face, u, v) = uv_cube(point);
+        self.faces[face as usize].inner_pattern_at(Tuple::new_point(u, 0.0, v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray_tracer::{transformations::Transform, utils::is_float_equal};
+
+    #[test]
+    fn identifying_the_face_of_a_cube_from_a_point() {
+        let cases = [
+            ((-1.0, 0.5, -0.25), CubeFace::Left),
+            ((1.1, -0.75, 0.8), CubeFace::Right),
+            ((0.1, 0.6, 0.9), CubeFace::Front),
+            ((-0.7, 0.0, -2.0), CubeFace::Back),
+            ((0.5, 1.0, 0.9), CubeFace::Up),
+            ((-0.2, -1.3, 1.1), CubeFace::Down),
+        ];
+        for ((x, y, z), face) in cases {
+            assert_eq!(cube_face(Tuple::new_point(x, y, z)), face);
+        }
+    }
+
+    #[test]
+    fn uv_mapping_the_faces_of_a_cube() {
+        let cases = [
+            ((-0.5, 0.5, 1.0), CubeFace::Front, (0.25, 0.75)),
+            ((0.5, -0.5, 1.0), CubeFace::Front, (0.75, 0.25)),
+            ((0.5, 0.5, -1.0), CubeFace::Back, (0.25, 0.75)),
+            ((-0.5, -0.5, -1.0), CubeFace::Back, (0.75, 0.25)),
+            ((-1.0, 0.5, -0.5), CubeFace::Left, (0.25, 0.75)),
+            ((-1.0, -0.5, 0.5), CubeFace::Left, (0.75, 0.25)),
+            ((1.0, 0.5, 0.5), CubeFace::Right, (0.25, 0.75)),
+            ((1.0, -0.5, -0.5), CubeFace::Right, (0.75, 0.25)),
+            ((-0.5, 1.0, -0.5), CubeFace::Up, (0.25, 0.75)),
+            ((0.5, 1.0, 0.5), CubeFace::Up, (0.75, 0.25)),
+            ((-0.5, -1.0, 0.5), CubeFace::Down, (0.25, 0.75)),
+            ((0.5, -1.0, -0.5), CubeFace::Down, (0.75, 0.25)),
+        ];
+        for ((x, y, z), face, (u, v)) in cases {
+            let (mapped_face, mapped_u, mapped_v) = uv_cube(Tuple::new_point(x, y, z));
+            assert_eq!(mapped_face, face);
+            assert!(is_float_equal(&mapped_u, u));
+            assert!(is_float_equal(&mapped_v, v));
+        }
+    }
+
+    #[test]
+    fn a_cube_map_samples_a_different_pattern_per_face() {
+        let colors = [
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(1.0, 0.5, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        ];
+        let pattern = Pattern::cube_map(colors.map(Pattern::solid));
+        let points = [
+            (-1.0, 0.5, -0.25),
+            (0.1, 0.6, 1.0),
+            (1.0, -0.75, 0.8),
+            (-0.7, 0.0, -1.0),
+            (0.5, 1.0, 0.9),
+            (-0.2, -1.0, 0.3),
+        ];
+        for ((x, y, z), color) in points.into_iter().zip(colors) {
+            assert_eq!(pattern.pattern_at(Tuple::new_point(x, y, z)), color);
+        }
+    }
+
+    #[test]
+    fn face_patterns_see_the_uv_coordinates_on_the_xz_plane() {
+        let mut stripes = Pattern::stripe(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
+        stripes.set_transform(Transform::scaling(0.5, 1.0, 1.0));
+        let faces = std::array::from_fn(|_| stripes.clone());
+        let pattern = Pattern::cube_map(faces);
+        // u = 0.25 is in the first stripe, u = 0.75 in the second
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(-0.5, 0.5, 1.0)),
+            Color::new(1.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            pattern.pattern_at(Tuple::new_point(0.5, -0.5, 1.0)),
+            Color::new(0.0, 0.0, 0.0)
+        );
+    }
+}
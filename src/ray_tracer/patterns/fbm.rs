@@ -0,0 +1,103 @@
+use crate::ray_tracer::{
+    colors::Color,
+    noise::noise,
+    tuples::{Point, Tuple},
+};
+
+use super::{Pattern, Patterns};
+
+/// Offsets the noise lookups for each axis, so the axes get independent perturbations.
+pub(super) const Y_OFFSET: f64 = 31.416;
+pub(super) const Z_OFFSET: f64 = 57.291;
+
+/// Perturbs the points given to an inner pattern with fractal Brownian motion, i.e. a sum of noise
+/// octaves where every octave has twice the frequency and `persistence` times the amplitude of
+/// the previous one.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) struct Fbm {
+    inner: Box<Pattern>,
+    octaves: usize,
+    persistence: f64,
+}
+
+impl Fbm {
+    pub(super) fn new(inner: Pattern, octaves: usize, persistence: f64) -> Self {
+        Self {
+            inner: Box::new(inner),
+            octaves,
+            persistence,
+        }
+    }
+
+    fn octaves_at(&self, x: f64, y: f64, z: f64) -> f64 {
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut sum = 0.0;
+        for _ in 0..self.octaves {
+            sum += noise(Tuple::new_point(
+                x * frequency,
+                y * frequency,
+                z * frequency,
+            )) * amplitude;
+            frequency *= 2.0;
+            amplitude *= self.persistence;
+        }
+        sum
+    }
+
+    fn perturb(&self, point: Point) -> Point {
+        let (x, y, z) = (point.x, point.y, point.z);
+        Tuple::new_point(
+            x + self.octaves_at(x, y, z),
+            y + self.octaves_at(x + Y_OFFSET, y + Y_OFFSET, z + Y_OFFSET),
+            z + self.octaves_at(x + Z_OFFSET, y + Z_OFFSET, z + Z_OFFSET),
+        )
+    }
+}
+
+impl Patterns for Fbm {
+    fn color_at(&self, point: Point) -> Color {
+        self.inner.inner_pattern_at(self.perturb(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variation(fbm: &Fbm) -> f64 {
+        (0..1000)
+            .map(|i| {
+                let a = fbm.perturb(Tuple::new_point(i as f64 * 0.01, 0.3, 0.7));
+                let b = fbm.perturb(Tuple::new_point((i + 1) as f64 * 0.01, 0.3, 0.7));
+                (b - a).magnitude()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn zero_octaves_reproduces_the_inner_pattern() {
+        let inner = Pattern::stripe(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
+        let fbm = Pattern::fbm(inner.clone(), 0, 0.5);
+        for i in 0..20 {
+            let p = Tuple::new_point(i as f64 * 0.3, i as f64 * 0.1, i as f64 * -0.2);
+            assert_eq!(fbm.pattern_at(p), inner.pattern_at(p));
+        }
+    }
+
+    #[test]
+    fn more_octaves_add_higher_frequency_detail() {
+        let inner = Pattern::gradient_default();
+        let one = Fbm::new(inner.clone(), 1, 0.5);
+        let four = Fbm::new(inner, 4, 0.5);
+        assert!(variation(&four) > variation(&one));
+    }
+
+    #[test]
+    fn fbm_is_deterministic() {
+        let pattern = Pattern::fbm(Pattern::gradient_default(), 3, 0.5);
+        let p = Tuple::new_point(0.25, 1.5, -2.75);
+        assert_eq!(pattern.pattern_at(p), pattern.pattern_at(p));
+    }
+}
@@ -1,15 +1,16 @@
 use crate::ray_tracer::{colors::Color, tuples};
 
-use super::Patterns;
+use super::{PatternColor, Patterns};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct Gradient {
-    color_a: Color,
-    color_b: Color,
+    color_a: PatternColor,
+    color_b: PatternColor,
 }
 
 impl Gradient {
-    pub(super) fn new(color_a: Color, color_b: Color) -> Self {
+    pub(super) fn new(color_a: PatternColor, color_b: PatternColor) -> Self {
         Self { color_a, color_b }
     }
 }
@@ -17,18 +18,18 @@ impl Gradient {
 impl Default for Gradient {
     fn default() -> Self {
         Self {
-            color_a: Color::new(1.0, 1.0, 1.0),
-            color_b: Color::new(0.0, 0.0, 0.0),
+            color_a: PatternColor::Solid(Color::new(1.0, 1.0, 1.0)),
+            color_b: PatternColor::Solid(Color::new(0.0, 0.0, 0.0)),
         }
     }
 }
 
 impl Patterns for Gradient {
     fn color_at(&self, point: tuples::Point) -> Color {
-        let distance = self.color_b - self.color_a;
         let fraction = point.x - f64::floor(point.x);
-
-        self.color_a + distance * fraction
+        self.color_a
+            .color_at(point)
+            .lerp(&self.color_b.color_at(point), fraction)
     }
 }
 
@@ -0,0 +1,88 @@
+use crate::ray_tracer::{colors::Color, tuples};
+
+use super::Patterns;
+
+/// Lines at every multiple of `spacing` along x and z, on a plain background.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) struct Grid {
+    line_color: Color,
+    fill_color: Color,
+    spacing: f64,
+    thickness: f64,
+}
+
+impl Grid {
+    pub(super) fn new(line_color: Color, fill_color: Color, spacing: f64, thickness: f64) -> Self {
+        Self {
+            line_color,
+            fill_color,
+            spacing,
+            thickness,
+        }
+    }
+
+    /// Whether `value` is within half a line's thickness of a multiple of the spacing.
+    fn on_line(&self, value: f64) -> bool {
+        let offset = value.rem_euclid(self.spacing);
+        offset.min(self.spacing - offset) <= self.thickness / 2.0
+    }
+}
+
+impl Patterns for Grid {
+    fn color_at(&self, point: tuples::Point) -> Color {
+        if self.spacing > 0.0 && (self.on_line(point.x) || self.on_line(point.z)) {
+            self.line_color
+        } else {
+            self.fill_color
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ray_tracer::{patterns::Pattern, tuples::Tuple};
+
+    const WHITE: Color = Color {
+        red: 1.0,
+        green: 1.0,
+        blue: 1.0,
+    };
+    const BLACK: Color = Color {
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+    };
+
+    #[test]
+    fn a_grid_draws_lines_at_multiples_of_its_spacing() {
+        let pattern = Pattern::grid(BLACK, WHITE, 2.0, 0.2);
+        let on_line = [
+            Tuple::new_point(0.0, 0.0, 0.7),
+            Tuple::new_point(2.05, 0.0, 0.7),
+            Tuple::new_point(-3.95, 5.0, 0.7),
+            Tuple::new_point(0.7, 0.0, 1.91),
+            Tuple::new_point(0.7, 0.0, -6.0),
+        ];
+        for point in on_line {
+            assert_eq!(pattern.pattern_at(point), BLACK);
+        }
+        let off_line = [
+            Tuple::new_point(1.0, 0.0, 1.0),
+            Tuple::new_point(0.15, 0.0, 0.7),
+            Tuple::new_point(-1.0, 3.0, 5.0),
+            Tuple::new_point(2.5, 0.0, -3.5),
+        ];
+        for point in off_line {
+            assert_eq!(pattern.pattern_at(point), WHITE);
+        }
+    }
+
+    #[test]
+    fn the_grid_ignores_y() {
+        let pattern = Pattern::grid(BLACK, WHITE, 1.0, 0.1);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.5, 0.0, 0.5)), WHITE);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.5, 1.0, 0.5)), WHITE);
+    }
+}
@@ -0,0 +1,101 @@
+use crate::ray_tracer::{
+    colors::Color,
+    noise::Perlin,
+    tuples::{Point, Tuple},
+};
+
+use super::{
+    fbm::{Y_OFFSET, Z_OFFSET},
+    Pattern, Patterns,
+};
+
+/// Jitters the points given to an inner pattern with a single octave of Perlin noise.
+/// Every axis is moved by up to `scale` in either direction.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) struct Perturb {
+    inner: Box<Pattern>,
+    scale: f64,
+    perlin: Box<Perlin>,
+}
+
+impl Perturb {
+    pub(super) fn new(inner: Pattern, scale: f64, seed: u32) -> Self {
+        Self {
+            inner: Box::new(inner),
+            scale,
+            perlin: Box::new(Perlin::new(seed)),
+        }
+    }
+
+    pub(super) fn get_scale(&self) -> f64 {
+        self.scale
+    }
+
+    pub(super) fn get_seed(&self) -> u32 {
+        self.perlin.seed()
+    }
+
+    fn perturb(&self, point: Point) -> Point {
+        let (x, y, z) = (point.x, point.y, point.z);
+        let offset = |o: f64| self.perlin.noise(Tuple::new_point(x + o, y + o, z + o));
+        Tuple::new_point(
+            x + offset(0.0) * self.scale,
+            y + offset(Y_OFFSET) * self.scale,
+            z + offset(Z_OFFSET) * self.scale,
+        )
+    }
+}
+
+impl Patterns for Perturb {
+    fn color_at(&self, point: Point) -> Color {
+        self.inner.inner_pattern_at(self.perturb(point))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stripes() -> Pattern {
+        Pattern::stripe(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0))
+    }
+
+    #[test]
+    fn scale_zero_leaves_the_inner_pattern_unchanged() {
+        let inner = stripes();
+        let perturbed = Pattern::perturb(inner.clone(), 0.0);
+        for i in 0..50 {
+            let p = Tuple::new_point(i as f64 * 0.13, i as f64 * -0.07, i as f64 * 0.29);
+            assert_eq!(perturbed.pattern_at(p), inner.pattern_at(p));
+        }
+    }
+
+    #[test]
+    fn perturbing_moves_points_by_at_most_the_scale() {
+        let perturb = Perturb::new(stripes(), 0.25, 7);
+        let mut moved = false;
+        for i in 0..100 {
+            let p = Tuple::new_point(i as f64 * 0.37, i as f64 * 0.11, i as f64 * -0.23);
+            let q = perturb.perturb(p);
+            for d in [q.x - p.x, q.y - p.y, q.z - p.z] {
+                assert!(d.abs() <= 0.25);
+            }
+            moved |= q != p;
+        }
+        assert!(moved);
+    }
+
+    #[test]
+    fn the_seed_changes_the_perturbation() {
+        let a = Perturb::new(stripes(), 1.0, 1);
+        let b = Perturb::new(stripes(), 1.0, 2);
+        assert_eq!(a.get_seed(), 1);
+        assert_eq!(a.get_scale(), 1.0);
+        let differs = (0..20).any(|i| {
+            let p = Tuple::new_point(i as f64 * 0.37, 0.5, 0.25);
+            a.perturb(p) != b.perturb(p)
+        });
+        assert!(differs);
+    }
+}
@@ -3,6 +3,7 @@ use crate::ray_tracer::{colors::Color, tuples};
 use super::Patterns;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct Ring {
     color_a: Color,
     color_b: Color,
@@ -27,7 +28,7 @@ impl Patterns for Ring {
     fn color_at(&self, point: tuples::Point) -> Color {
         let inside = point.x.powi(2) + point.z.powi(2);
         let magnitude = inside.sqrt();
-        if magnitude.floor() as usize % 2 == 0 {
+        if (magnitude.floor() as usize).is_multiple_of(2) {
             self.color_a
         } else {
             self.color_b
@@ -3,6 +3,7 @@ use crate::ray_tracer::{colors::Color, tuples};
 use super::Patterns;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct Solid {
     color: Color,
 }
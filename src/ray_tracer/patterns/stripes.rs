@@ -1,31 +1,32 @@
 use crate::ray_tracer::{colors::Color, tuples::Point};
 
-use super::Patterns;
+use super::{PatternColor, Patterns};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct Stripes {
-    color_a: Color,
-    color_b: Color,
+    color_a: PatternColor,
+    color_b: PatternColor,
 }
 
 impl Stripes {
-    pub(super) fn new(color_a: Color, color_b: Color) -> Self {
+    pub(super) fn new(color_a: PatternColor, color_b: PatternColor) -> Self {
         Stripes { color_a, color_b }
     }
 }
 
 #[cfg(test)]
 impl Stripes {
-    pub(super) fn get_colors(&self) -> (Color, Color) {
-        (self.color_a, self.color_b)
+    pub(super) fn get_colors(&self) -> (PatternColor, PatternColor) {
+        (self.color_a.clone(), self.color_b.clone())
     }
 }
 
 impl Default for Stripes {
     fn default() -> Self {
         Stripes {
-            color_a: Color::new(1.0, 1.0, 1.0),
-            color_b: Color::new(0.0, 0.0, 0.0),
+            color_a: PatternColor::Solid(Color::new(1.0, 1.0, 1.0)),
+            color_b: PatternColor::Solid(Color::new(0.0, 0.0, 0.0)),
         }
     }
 }
@@ -33,16 +34,16 @@ impl Default for Stripes {
 impl Patterns for Stripes {
     fn color_at(&self, point: Point) -> Color {
         if point.x.floor() as isize % 2 == 0 {
-            self.color_a
+            self.color_a.color_at(point)
         } else {
-            self.color_b
+            self.color_b.color_at(point)
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ray_tracer::patterns::Pattern;
+    use crate::ray_tracer::{patterns::Pattern, shapes::new_sphere, transformations::Transform};
 
     use super::*;
 
@@ -61,8 +62,8 @@ mod tests {
     fn creating_a_stripe_pattern() {
         let pattern = Pattern::stripe(WHITE, BLACK);
         let (color_a, color_b) = pattern.get_stripe().unwrap().get_colors();
-        assert_eq!(color_a, WHITE);
-        assert_eq!(color_b, BLACK);
+        assert_eq!(color_a, PatternColor::Solid(WHITE));
+        assert_eq!(color_b, PatternColor::Solid(BLACK));
     }
     #[test]
     fn a_stripe_pattern_is_constant_in_y() {
@@ -88,4 +89,29 @@ mod tests {
         assert_eq!(pattern.pattern_at(Point::new_point(-1.0, 0.0, 0.0)), BLACK);
         assert_eq!(pattern.pattern_at(Point::new_point(-1.1, 0.0, 0.0)), WHITE);
     }
+    #[test]
+    fn stripes_with_an_object_transformation() {
+        let mut object = new_sphere();
+        object.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let pattern = Pattern::stripe(WHITE, BLACK);
+        let c = Pattern::pattern_at_object(&pattern, &object, Point::new_point(1.5, 0.0, 0.0));
+        assert_eq!(c, WHITE);
+    }
+    #[test]
+    fn stripes_with_a_pattern_transformation() {
+        let object = new_sphere();
+        let mut pattern = Pattern::stripe(WHITE, BLACK);
+        pattern.set_transform(Transform::scaling(2.0, 2.0, 2.0));
+        let c = Pattern::pattern_at_object(&pattern, &object, Point::new_point(1.5, 0.0, 0.0));
+        assert_eq!(c, WHITE);
+    }
+    #[test]
+    fn stripes_with_both_an_object_and_a_pattern_transformation() {
+        let mut object = new_sphere();
+        object.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut pattern = Pattern::stripe(WHITE, BLACK);
+        pattern.set_transform(Transform::translate(0.5, 0.0, 0.0));
+        let c = Pattern::pattern_at_object(&pattern, &object, Point::new_point(2.5, 0.0, 0.0));
+        assert_eq!(c, WHITE);
+    }
 }
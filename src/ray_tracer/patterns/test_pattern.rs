@@ -3,6 +3,7 @@ use crate::ray_tracer::{colors::Color, tuples::Point};
 use super::Patterns;
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub(super) struct TestPattern {}
 impl TestPattern {}
 
@@ -0,0 +1,189 @@
+use std::f64::consts::PI;
+
+use crate::ray_tracer::{
+    canvas::Canvas,
+    colors::Color,
+    tuples::{Point, Tuple},
+};
+
+use super::Patterns;
+
+/// How a point is turned into `(u, v)` texture coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UvMapping {
+    /// See [`uv_sphere`].
+    Spherical,
+    /// See [`uv_plane`].
+    Planar,
+    /// See [`uv_cylinder`].
+    Cylindrical,
+}
+
+/// Maps a point on a unit sphere to `(u, v)` coordinates in [0, 1].
+/// `u` follows the longitude, starting at -z and going around counter clockwise seen from above,
+/// while `v` follows the latitude, from 0 at the south pole to 1 at the north pole.
+pub fn uv_sphere(point: Point) -> (f64, f64) {
+    let theta = point.x.atan2(point.z);
+    let radius = Tuple::new_vector(point.x, point.y, point.z).magnitude();
+    let phi = (point.y / radius).acos();
+
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = 1.0 - phi / PI;
+    (u, v)
+}
+
+/// Maps a point on the xz plane to `(u, v)` coordinates, repeating every unit.
+pub fn uv_plane(point: Point) -> (f64, f64) {
+    (point.x.rem_euclid(1.0), point.z.rem_euclid(1.0))
+}
+
+/// Maps a point on a unit cylinder around the y axis to `(u, v)` coordinates.
+/// `u` goes around the axis like in [`uv_sphere`], and `v` repeats every unit of height.
+pub fn uv_cylinder(point: Point) -> (f64, f64) {
+    let theta = point.x.atan2(point.z);
+    let raw_u = theta / (2.0 * PI);
+    let u = 1.0 - (raw_u + 0.5);
+    let v = point.y.rem_euclid(1.0);
+    (u, v)
+}
+
+/// Samples an image, stretched over the texture coordinates of a mapping.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(super) struct UvImage {
+    canvas: Box<Canvas>,
+    mapping: UvMapping,
+}
+
+impl UvImage {
+    pub(super) fn new(canvas: Canvas, mapping: UvMapping) -> Self {
+        Self {
+            canvas: Box::new(canvas),
+            mapping,
+        }
+    }
+
+    /// `v` goes up while the canvas rows go down, so `v` is flipped.
+    fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        let x = (u * (self.canvas.width() - 1) as f64).round() as usize;
+        let y = ((1.0 - v) * (self.canvas.height() - 1) as f64).round() as usize;
+        *self.canvas.pixel_at(x, y)
+    }
+}
+
+impl Patterns for UvImage {
+    fn color_at(&self, point: Point) -> Color {
+        let (u, v) = match self.mapping {
+            UvMapping::Spherical => uv_sphere(point),
+            UvMapping::Planar => uv_plane(point),
+            UvMapping::Cylindrical => uv_cylinder(point),
+        };
+        self.uv_color_at(u, v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use super::*;
+    use crate::ray_tracer::{patterns::Pattern, utils::is_float_equal};
+
+    const WHITE: Color = Color {
+        red: 1.0,
+        green: 1.0,
+        blue: 1.0,
+    };
+    const BLACK: Color = Color {
+        red: 0.0,
+        green: 0.0,
+        blue: 0.0,
+    };
+
+    fn checker_texture() -> Canvas {
+        let mut canvas = Canvas::new(2, 2);
+        canvas.write_pixel(1, 0, WHITE);
+        canvas.write_pixel(0, 1, WHITE);
+        canvas
+    }
+
+    #[test]
+    fn using_a_spherical_mapping_on_a_3d_point() {
+        let cases = [
+            ((0.0, 0.0, -1.0), (0.0, 0.5)),
+            ((1.0, 0.0, 0.0), (0.25, 0.5)),
+            ((0.0, 0.0, 1.0), (0.5, 0.5)),
+            ((-1.0, 0.0, 0.0), (0.75, 0.5)),
+            ((0.0, 1.0, 0.0), (0.5, 1.0)),
+            ((0.0, -1.0, 0.0), (0.5, 0.0)),
+            ((FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0), (0.25, 0.75)),
+        ];
+        for ((x, y, z), (u, v)) in cases {
+            let (mapped_u, mapped_v) = uv_sphere(Tuple::new_point(x, y, z));
+            assert!(is_float_equal(&mapped_u, u));
+            assert!(is_float_equal(&mapped_v, v));
+        }
+    }
+
+    #[test]
+    fn using_a_planar_mapping_on_a_3d_point() {
+        let cases = [
+            ((0.25, 0.0, 0.5), (0.25, 0.5)),
+            ((0.25, 0.0, -0.25), (0.25, 0.75)),
+            ((0.25, 0.5, -0.25), (0.25, 0.75)),
+            ((1.25, 0.0, 0.5), (0.25, 0.5)),
+            ((0.25, 0.0, -1.75), (0.25, 0.25)),
+            ((1.0, 0.0, -1.0), (0.0, 0.0)),
+            ((0.0, 0.0, 0.0), (0.0, 0.0)),
+        ];
+        for ((x, y, z), (u, v)) in cases {
+            let (mapped_u, mapped_v) = uv_plane(Tuple::new_point(x, y, z));
+            assert!(is_float_equal(&mapped_u, u));
+            assert!(is_float_equal(&mapped_v, v));
+        }
+    }
+
+    #[test]
+    fn using_a_cylindrical_mapping_on_a_3d_point() {
+        let cases = [
+            ((0.0, 0.0, -1.0), (0.0, 0.0)),
+            ((0.0, 0.5, -1.0), (0.0, 0.5)),
+            ((0.0, 1.0, -1.0), (0.0, 0.0)),
+            ((FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), (0.125, 0.5)),
+            ((1.0, 0.5, 0.0), (0.25, 0.5)),
+            ((FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), (0.375, 0.5)),
+            ((0.0, -0.25, 1.0), (0.5, 0.75)),
+            ((-FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), (0.625, 0.5)),
+            ((-1.0, 1.25, 0.0), (0.75, 0.25)),
+            ((-FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), (0.875, 0.5)),
+        ];
+        for ((x, y, z), (u, v)) in cases {
+            let (mapped_u, mapped_v) = uv_cylinder(Tuple::new_point(x, y, z));
+            assert!(is_float_equal(&mapped_u, u));
+            assert!(is_float_equal(&mapped_v, v));
+        }
+    }
+
+    #[test]
+    fn sampling_the_corners_and_center_of_a_checker_texture() {
+        let image = UvImage::new(checker_texture(), UvMapping::Planar);
+        // v = 0 is the bottom row of the canvas
+        assert_eq!(image.uv_color_at(0.0, 0.0), WHITE);
+        assert_eq!(image.uv_color_at(1.0, 0.0), BLACK);
+        assert_eq!(image.uv_color_at(0.0, 1.0), BLACK);
+        assert_eq!(image.uv_color_at(1.0, 1.0), WHITE);
+        assert_eq!(image.uv_color_at(0.5, 0.5), BLACK);
+    }
+
+    #[test]
+    fn a_uv_image_pattern_wraps_a_texture_around_a_sphere() {
+        let pattern = Pattern::uv_image(checker_texture());
+        // The south pole is on the bottom row, the north pole on the top row
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, -1.0, 0.0)), BLACK);
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, 1.0, 0.0)), WHITE);
+        // Seen from the front (-z), u = 0 picks the left column
+        assert_eq!(pattern.pattern_at(Tuple::new_point(0.0, -0.9, -0.1)), WHITE);
+    }
+}
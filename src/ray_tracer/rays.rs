@@ -1,29 +1,40 @@
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::{Intersection, Intersections},
     matrices::Matrix,
     shapes::*,
+    stats,
     tuples::{Point, Vector},
     world::World,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq)]
-pub(crate) struct Ray {
+pub struct Ray {
     pub origin: Point,
     pub direction: Vector,
 }
 
 impl Ray {
-    pub(crate) fn new(origin: Point, direction: Vector) -> Self {
+    pub fn new(origin: Point, direction: Vector) -> Self {
         Ray { origin, direction }
     }
-    pub(crate) fn get_direction(&self) -> Vector {
+    pub fn get_origin(&self) -> Point {
+        self.origin
+    }
+    pub fn get_direction(&self) -> Vector {
         self.direction
     }
-    pub(crate) fn position(&self, time: f64) -> Point {
+    /// Checks whether the ray hits the axis aligned box at all, using the slab method.
+    /// Doesn't allocate, so it's cheap enough to cull groups with.
+    pub fn intersects_bounds(&self, bounds: &Bounds) -> bool {
+        bounds.intersects(self)
+    }
+    /// The point `time` units of the direction along the ray.
+    pub fn position(&self, time: f64) -> Point {
         self.origin + self.direction * time
     }
     fn global_to_local(&self, object: &Object) -> Ray {
-        self.transform(object.get_transform().get_inverted().unwrap())
+        self.inverse_transform(object.get_transform_ref())
     }
 
     pub(crate) fn intersect(&self, object: &Object) -> Vec<Intersection> {
@@ -31,18 +42,33 @@ impl Ray {
         object.local_intersect(local_ray)
     }
 
-    pub(crate) fn intersect_world(&self, world: &World) -> Intersections {
+    pub fn intersect_world(&self, world: &World) -> Intersections {
         let mut intersections = Intersections { list: Vec::new() };
+        // Drop tests made outside of a world, so only this world's are added to its stats
+        stats::take_intersection_tests();
         for object in &world.objects {
             intersections.put_elements(&self.intersect(object));
         }
+        world
+            .get_stats()
+            .add_intersection_tests(stats::take_intersection_tests());
         intersections
     }
 
-    pub(crate) fn transform(&self, transformation: Matrix) -> Self {
+    /// Returns the ray with its origin and direction transformed by `transformation`.
+    /// The direction isn't normalized, so `t` values stay comparable with the original ray.
+    pub fn transform(&self, transformation: &Matrix) -> Self {
+        Ray {
+            origin: *transformation * self.origin,
+            direction: *transformation * self.direction,
+        }
+    }
+    /// Like [`Ray::transform`], but by the inverse of `transformation`, which must have been
+    /// calculated. This is how a ray is taken into an object's space.
+    pub(crate) fn inverse_transform(&self, transformation: &Matrix) -> Self {
         Ray {
-            origin: transformation * self.origin,
-            direction: transformation * self.direction,
+            origin: transformation.mul_inverse(&self.origin),
+            direction: transformation.mul_inverse(&self.direction),
         }
     }
 }
@@ -52,6 +78,32 @@ mod tests {
     use super::*;
     use crate::ray_tracer::{transformations::Transform, tuples::Tuple, utils::is_float_equal};
 
+    #[test]
+    fn a_ray_grazing_the_corner_of_a_bounding_box() {
+        let b = Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        );
+        let r = Ray::new(
+            Tuple::new_point(2.0, 0.0, 0.0),
+            Tuple::new_vector(-1.0, 1.0, 1.0),
+        );
+        assert!(r.intersects_bounds(&b));
+    }
+
+    #[test]
+    fn a_ray_missing_a_bounding_box() {
+        let b = Bounds::new(
+            Tuple::new_point(-1.0, -1.0, -1.0),
+            Tuple::new_point(1.0, 1.0, 1.0),
+        );
+        let r = Ray::new(
+            Tuple::new_point(2.0, 0.0, 0.0),
+            Tuple::new_vector(-1.0, 1.5, 1.0),
+        );
+        assert!(!r.intersects_bounds(&b));
+    }
+
     #[test]
     fn creating_and_querying_a_ray() {
         let origin = Tuple::new_point(1.0, 2.0, 3.0);
@@ -154,7 +206,7 @@ mod tests {
             Tuple::new_vector(0.0, 1.0, 0.0),
         );
         let m = Transform::translate(3.0, 4.0, 5.0);
-        let r2 = r.transform(m);
+        let r2 = r.transform(&m);
         assert_eq!(r2.origin, Tuple::new_point(4.0, 6.0, 8.0));
         assert_eq!(r2.direction, Tuple::new_vector(0.0, 1.0, 0.0));
     }
@@ -165,11 +217,23 @@ mod tests {
             Tuple::new_vector(0.0, 1.0, 0.0),
         );
         let m = Transform::scaling(2.0, 3.0, 4.0);
-        let r2 = r.transform(m);
+        let r2 = r.transform(&m);
         assert_eq!(r2.origin, Tuple::new_point(2.0, 6.0, 12.0));
         assert_eq!(r2.direction, Tuple::new_vector(0.0, 3.0, 0.0));
     }
     #[test]
+    fn the_inverse_transform_undoes_the_transform() {
+        let r = Ray::new(
+            Tuple::new_point(1.0, 2.0, 3.0),
+            Tuple::new_vector(0.0, 1.0, 0.0),
+        );
+        let mut m = Transform::translate(3.0, 4.0, 5.0) * Transform::scaling(2.0, 3.0, 4.0);
+        m.calculate_inverse().unwrap();
+        assert_eq!(r.transform(&m).inverse_transform(&m), r);
+        assert_eq!(r.get_origin(), Tuple::new_point(1.0, 2.0, 3.0));
+        assert_eq!(r.get_direction(), Tuple::new_vector(0.0, 1.0, 0.0));
+    }
+    #[test]
     fn intersecting_a_scaled_sphere_with_a_ray() {
         let r = Ray::new(
             Tuple::new_point(0.0, 0.0, -5.0),
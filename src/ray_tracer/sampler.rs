@@ -0,0 +1,60 @@
+//! Deterministic pseudo-random numbers for sampled features like motion blur and soft shadows.
+//! Everything is derived from a seed, so a render can be reproduced exactly by reusing it.
+
+/// The golden ratio as a 64 bit fraction, the increment of [`SplitMix64`].
+pub(crate) const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// A source of numbers in [0, 1) for the parts of a render that sample randomly.
+pub trait Sampler {
+    fn next_f64(&mut self) -> f64;
+}
+
+/// A small, fast generator that gives the same sequence for the same seed on every platform.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+}
+
+impl Sampler for SplitMix64 {
+    fn next_f64(&mut self) -> f64 {
+        self.state = self.state.wrapping_add(GOLDEN_GAMMA);
+        to_unit(mix(self.state))
+    }
+}
+
+/// The SplitMix64 finalizer, which scrambles every bit of `h` into every bit of the result.
+pub(crate) fn mix(mut h: u64) -> u64 {
+    h = (h ^ (h >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h = (h ^ (h >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+/// Maps the top 53 bits of `h` to [0, 1).
+pub(crate) fn to_unit(h: u64) -> f64 {
+    (h >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_same_seed_gives_the_same_sequence() {
+        let mut a = SplitMix64::new(42);
+        let mut b = SplitMix64::new(42);
+        let mut c = SplitMix64::new(43);
+        let first: Vec<f64> = (0..16).map(|_| a.next_f64()).collect();
+        let second: Vec<f64> = (0..16).map(|_| b.next_f64()).collect();
+        let other: Vec<f64> = (0..16).map(|_| c.next_f64()).collect();
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert!(first.iter().all(|x| (0.0..1.0).contains(x)));
+    }
+}
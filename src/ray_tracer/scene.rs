@@ -0,0 +1,648 @@
+//! Loading scenes from the YAML format used by "The Ray Tracer Challenge".
+//! Only available with the `scene` feature.
+//!
+//! A scene is a list of items. Each item either adds something to the scene, or defines a value
+//! that later items can refer to by name:
+//!
+//! ```yaml
+//! - add: camera
+//!   width: 100
+//!   height: 50
+//!   field-of-view: 1.0472
+//!   from: [0, 1.5, -5]
+//!   to: [0, 1, 0]
+//!   up: [0, 1, 0]
+//!
+//! - add: light
+//!   at: [-10, 10, -10]
+//!   intensity: [1, 1, 1]
+//!
+//! - define: shiny
+//!   value:
+//!     specular: 0.9
+//!     reflective: 0.3
+//!
+//! - define: red-shiny
+//!   extend: shiny
+//!   value:
+//!     color: [1, 0, 0]
+//!
+//! - add: sphere
+//!   material: red-shiny
+//!   transform:
+//!     - [scale, 0.5, 0.5, 0.5]
+//!     - [translate, 0, 0.5, 0]
+//! ```
+//!
+//! Transforms are applied in the order they are listed.
+
+use std::{collections::HashMap, fmt};
+
+use serde_yaml::{Mapping, Value};
+
+use crate::ray_tracer::{
+    camera::Camera, colors::Color, error::RayTracerError, lights::Light, materials::Material,
+    matrices::Matrix, shapes::*, transformations::Transform, tuples::Tuple, world::World,
+};
+
+/// Errors from loading a scene description.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneError {
+    /// The input isn't YAML, or isn't a list of items. Contains the parser's message.
+    Yaml(String),
+    /// An `add` item names something that isn't a shape, light, camera or definition.
+    UnknownItem(String),
+    /// A name was used that hasn't been defined by an earlier `define`.
+    UnknownDefinition(String),
+    /// An item lacks a field it needs.
+    MissingField(String),
+    /// A field has the wrong kind of value.
+    InvalidValue(String),
+    /// The scene doesn't add a camera.
+    MissingCamera,
+    /// A definition refers back to itself, directly or through other definitions.
+    RecursiveDefinition(String),
+    /// The values are well formed, but can't be used, e.g. a transform that can't be inverted.
+    InvalidScene(RayTracerError),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Yaml(msg) => write!(f, "invalid scene: {msg}"),
+            SceneError::UnknownItem(name) => write!(f, "unknown item '{name}'"),
+            SceneError::UnknownDefinition(name) => write!(f, "'{name}' is not defined"),
+            SceneError::MissingField(field) => write!(f, "missing field '{field}'"),
+            SceneError::InvalidValue(field) => write!(f, "invalid value for '{field}'"),
+            SceneError::MissingCamera => write!(f, "the scene has no camera"),
+            SceneError::RecursiveDefinition(name) => {
+                write!(f, "'{name}' is defined in terms of itself")
+            }
+            SceneError::InvalidScene(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+impl From<RayTracerError> for SceneError {
+    fn from(err: RayTracerError) -> Self {
+        SceneError::InvalidScene(err)
+    }
+}
+
+/// Builds a world and a camera from a YAML scene description.
+/// Supported items are `camera`, point `light`s, and `sphere`, `plane`, `cube`, `cylinder`, `cone`
+/// and `group` shapes. Cylinders and cones with a `min` or `max` are capped unless they have
+/// `closed: false`.
+pub fn load_scene(yaml: &str) -> Result<(World, Camera), SceneError> {
+    let items: Vec<Mapping> =
+        serde_yaml::from_str(yaml).map_err(|err| SceneError::Yaml(err.to_string()))?;
+
+    let mut loader = SceneLoader {
+        defines: HashMap::new(),
+    };
+    let mut world = World::new();
+    let mut camera = None;
+
+    for item in &items {
+        if let Some(name) = item.get("define") {
+            loader.define(as_str(name, "define")?, item)?;
+            continue;
+        }
+        match item.get("add") {
+            Some(Value::String(kind)) if kind == "camera" => camera = Some(loader.camera(item)?),
+            Some(Value::String(kind)) if kind == "light" => world.lights.push(loader.light(item)?),
+            Some(_) => world.add_object(loader.object(item)?),
+            None => return Err(SceneError::MissingField(String::from("add"))),
+        }
+    }
+
+    Ok((world, camera.ok_or(SceneError::MissingCamera)?))
+}
+
+struct SceneLoader {
+    defines: HashMap<String, Value>,
+}
+
+impl SceneLoader {
+    /// Stores a definition, merged on top of the one it extends, if any.
+    fn define(&mut self, name: &str, item: &Mapping) -> Result<(), SceneError> {
+        let value = field(item, "value")?.clone();
+        let value = match item.get("extend") {
+            Some(parent) => {
+                let parent = as_str(parent, "extend")?;
+                let mut merged = self
+                    .lookup(parent)?
+                    .as_mapping()
+                    .cloned()
+                    .ok_or_else(|| SceneError::InvalidValue(String::from("extend")))?;
+                let overrides = value
+                    .as_mapping()
+                    .ok_or_else(|| SceneError::InvalidValue(String::from("value")))?;
+                for (key, value) in overrides {
+                    merged.insert(key.clone(), value.clone());
+                }
+                Value::Mapping(merged)
+            }
+            None => value,
+        };
+        self.defines.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn lookup(&self, name: &str) -> Result<&Value, SceneError> {
+        self.defines
+            .get(name)
+            .ok_or_else(|| SceneError::UnknownDefinition(name.to_string()))
+    }
+
+    /// Marks `name` as being expanded, or returns an error if it already is further up, since
+    /// expanding it again would never end.
+    fn enter(name: &str, expanding: &mut Vec<String>) -> Result<(), SceneError> {
+        if expanding.iter().any(|n| n == name) {
+            return Err(SceneError::RecursiveDefinition(name.to_string()));
+        }
+        expanding.push(name.to_string());
+        Ok(())
+    }
+
+    fn camera(&self, item: &Mapping) -> Result<Camera, SceneError> {
+        let width = as_usize(field(item, "width")?, "width")?;
+        let height = as_usize(field(item, "height")?, "height")?;
+        let field_of_view = as_f64(field(item, "field-of-view")?, "field-of-view")?;
+        let from = as_triple(field(item, "from")?, "from")?;
+        let to = as_triple(field(item, "to")?, "to")?;
+        let up = as_triple(field(item, "up")?, "up")?;
+
+        let mut camera = Camera::try_new(width, height, field_of_view)?;
+        camera.try_set_transform(Transform::view_transform(
+            &Tuple::new_point(from.0, from.1, from.2),
+            &Tuple::new_point(to.0, to.1, to.2),
+            &Tuple::new_vector(up.0, up.1, up.2),
+        ))?;
+        Ok(camera)
+    }
+
+    fn light(&self, item: &Mapping) -> Result<Light, SceneError> {
+        let at = as_triple(field(item, "at")?, "at")?;
+        let intensity = as_triple(field(item, "intensity")?, "intensity")?;
+        Ok(Light::point_light(
+            &Tuple::new_point(at.0, at.1, at.2),
+            &Color::new(intensity.0, intensity.1, intensity.2),
+        ))
+    }
+
+    fn object(&self, item: &Mapping) -> Result<Object, SceneError> {
+        self.object_expanding(item, &mut Vec::new())
+    }
+
+    /// Builds an object, where `expanding` holds the definitions it is being expanded from.
+    fn object_expanding(
+        &self,
+        item: &Mapping,
+        expanding: &mut Vec<String>,
+    ) -> Result<Object, SceneError> {
+        let kind = as_str(field(item, "add")?, "add")?;
+        let mut object = match kind {
+            "sphere" => new_sphere(),
+            "plane" => new_plane(),
+            "cube" => new_cube(),
+            "cylinder" => match self.bounds(item)? {
+                Some((max, min)) if !self.closed(item)? => new_cylinder_open(min, max),
+                bounds => new_cylinder(bounds),
+            },
+            "cone" => match self.bounds(item)? {
+                Some((max, min)) if !self.closed(item)? => new_cone_open(min, max),
+                bounds => new_cone(bounds),
+            },
+            "group" => {
+                let mut group = Group::new();
+                if let Some(children) = item.get("children") {
+                    let children = children
+                        .as_sequence()
+                        .ok_or_else(|| SceneError::InvalidValue(String::from("children")))?;
+                    for child in children {
+                        let child = child
+                            .as_mapping()
+                            .ok_or_else(|| SceneError::InvalidValue(String::from("children")))?;
+                        group.add_child(self.object_expanding(child, expanding)?);
+                    }
+                }
+                new_group(group)
+            }
+            // A shape defined earlier, with this item's fields on top
+            name => {
+                Self::enter(name, expanding)?;
+                let mut merged = self
+                    .defines
+                    .get(name)
+                    .and_then(Value::as_mapping)
+                    .cloned()
+                    .ok_or_else(|| SceneError::UnknownItem(name.to_string()))?;
+                for (key, value) in item {
+                    if key != "add" {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+                let object = self.object_expanding(&merged, expanding);
+                expanding.pop();
+                return object;
+            }
+        };
+
+        if let Some(material) = item.get("material") {
+            object.set_material(&self.material(material)?);
+        }
+        if let Some(transform) = item.get("transform") {
+            object.try_set_transform(&self.transform(transform, expanding)?)?;
+        }
+        Ok(object)
+    }
+
+    /// The `max` and `min` of a cylinder or cone, if it is truncated.
+    fn bounds(&self, item: &Mapping) -> Result<Option<(f64, f64)>, SceneError> {
+        let min = item.get("min").map(|v| as_f64(v, "min")).transpose()?;
+        let max = item.get("max").map(|v| as_f64(v, "max")).transpose()?;
+        Ok(match (min, max) {
+            (None, None) => None,
+            (min, max) => Some((
+                max.unwrap_or(f64::INFINITY),
+                min.unwrap_or(f64::NEG_INFINITY),
+            )),
+        })
+    }
+
+    /// Whether a truncated cylinder or cone gets caps. Defaults to true.
+    fn closed(&self, item: &Mapping) -> Result<bool, SceneError> {
+        match item.get("closed") {
+            None => Ok(true),
+            Some(value) => value
+                .as_bool()
+                .ok_or_else(|| SceneError::InvalidValue(String::from("closed"))),
+        }
+    }
+
+    /// A material, either given by the name of a definition or as a mapping of its fields.
+    /// Fields that aren't given keep their default values.
+    fn material(&self, value: &Value) -> Result<Material, SceneError> {
+        let fields = match value {
+            Value::String(name) => self.lookup(name)?,
+            value => value,
+        }
+        .as_mapping()
+        .ok_or_else(|| SceneError::InvalidValue(String::from("material")))?;
+
+        let mut material = Material::new();
+        for (key, value) in fields {
+            let key = as_str(key, "material")?;
+            match key {
+                "color" => {
+                    let (r, g, b) = as_triple(value, key)?;
+                    material.color = Color::new(r, g, b);
+                }
+                "ambient" => material.ambient = as_f64(value, key)?,
+                "diffuse" => material.diffuse = as_f64(value, key)?,
+                "specular" => material.specular = as_f64(value, key)?,
+                "shininess" => material.shininess = as_f64(value, key)?,
+                "reflective" => material.reflective = as_f64(value, key)?,
+                "transparency" => material.transparency = as_f64(value, key)?,
+                "refractive-index" => material.refractive_index = as_f64(value, key)?,
+                "emissive" => {
+                    let (r, g, b) = as_triple(value, key)?;
+                    material.emissive = Color::new(r, g, b);
+                }
+                _ => return Err(SceneError::UnknownItem(key.to_string())),
+            }
+        }
+        Ok(material)
+    }
+
+    /// A list of transformations, each either `[name, arguments...]` or the name of a definition
+    /// holding another list. The first transformation in the list is applied first.
+    /// `expanding` holds the definitions the list is being expanded from.
+    fn transform(&self, value: &Value, expanding: &mut Vec<String>) -> Result<Matrix, SceneError> {
+        let steps = value
+            .as_sequence()
+            .ok_or_else(|| SceneError::InvalidValue(String::from("transform")))?;
+
+        let mut transform = Matrix::new_identity();
+        for step in steps {
+            let step = match step {
+                Value::String(name) => {
+                    Self::enter(name, expanding)?;
+                    let step = self.transform(self.lookup(name)?, expanding)?;
+                    expanding.pop();
+                    step
+                }
+                Value::Sequence(step) => transformation(step)?,
+                _ => return Err(SceneError::InvalidValue(String::from("transform"))),
+            };
+            transform = step * transform;
+        }
+        Ok(transform)
+    }
+}
+
+/// A single transformation like `[translate, 1, 2, 3]`.
+fn transformation(step: &[Value]) -> Result<Matrix, SceneError> {
+    let (name, args) = step
+        .split_first()
+        .ok_or_else(|| SceneError::InvalidValue(String::from("transform")))?;
+    let name = as_str(name, "transform")?;
+    let args = args
+        .iter()
+        .map(|arg| as_f64(arg, name))
+        .collect::<Result<Vec<f64>, SceneError>>()?;
+
+    let expected = match name {
+        "translate" | "scale" => 3,
+        "rotate-x" | "rotate-y" | "rotate-z" => 1,
+        "shear" => 6,
+        _ => return Err(SceneError::UnknownItem(name.to_string())),
+    };
+    if args.len() != expected {
+        return Err(SceneError::InvalidValue(name.to_string()));
+    }
+
+    Ok(match name {
+        "translate" => Transform::translate(args[0], args[1], args[2]),
+        "scale" => Transform::scaling(args[0], args[1], args[2]),
+        "rotate-x" => Transform::rotation_x(args[0]),
+        "rotate-y" => Transform::rotation_y(args[0]),
+        "rotate-z" => Transform::rotation_z(args[0]),
+        _ => Transform::shearing(args[0], args[1], args[2], args[3], args[4], args[5]),
+    })
+}
+
+fn field<'a>(item: &'a Mapping, name: &str) -> Result<&'a Value, SceneError> {
+    item.get(name)
+        .ok_or_else(|| SceneError::MissingField(name.to_string()))
+}
+
+fn as_str<'a>(value: &'a Value, name: &str) -> Result<&'a str, SceneError> {
+    value
+        .as_str()
+        .ok_or_else(|| SceneError::InvalidValue(name.to_string()))
+}
+
+fn as_f64(value: &Value, name: &str) -> Result<f64, SceneError> {
+    value
+        .as_f64()
+        .ok_or_else(|| SceneError::InvalidValue(name.to_string()))
+}
+
+fn as_usize(value: &Value, name: &str) -> Result<usize, SceneError> {
+    value
+        .as_u64()
+        .map(|v| v as usize)
+        .ok_or_else(|| SceneError::InvalidValue(name.to_string()))
+}
+
+fn as_triple(value: &Value, name: &str) -> Result<(f64, f64, f64), SceneError> {
+    match value.as_sequence().map(Vec::as_slice) {
+        Some([x, y, z]) => Ok((as_f64(x, name)?, as_f64(y, name)?, as_f64(z, name)?)),
+        _ => Err(SceneError::InvalidValue(name.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::ray_tracer::bounds::Bounds;
+
+    const SCENE: &str = "
+- add: camera
+  width: 100
+  height: 50
+  field-of-view: 1.0471975511965976
+  from: [0, 1.5, -5]
+  to: [0, 1, 0]
+  up: [0, 1, 0]
+
+- add: light
+  at: [-10, 10, -10]
+  intensity: [1, 1, 1]
+
+- define: shiny
+  value:
+    specular: 0.9
+    reflective: 0.3
+
+- define: red-shiny
+  extend: shiny
+  value:
+    color: [1, 0, 0]
+
+- define: half-size
+  value:
+    - [scale, 0.5, 0.5, 0.5]
+
+# The first sphere uses definitions, the second spells everything out
+- add: sphere
+  material: red-shiny
+  transform:
+    - half-size
+    - [translate, 0, 0.5, 0]
+
+- add: sphere
+  material:
+    color: [0.1, 1, 0.5]
+    diffuse: 0.7
+  transform:
+    - [translate, 1.5, 0.5, -0.5]
+";
+
+    #[test]
+    fn loading_a_small_scene() {
+        let (world, camera) = load_scene(SCENE).unwrap();
+
+        let mut expected_camera = Camera::new(100, 50, PI / 3.0);
+        expected_camera.set_transform(Transform::view_transform(
+            &Tuple::new_point(0.0, 1.5, -5.0),
+            &Tuple::new_point(0.0, 1.0, 0.0),
+            &Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+        assert_eq!(camera, expected_camera);
+
+        assert_eq!(
+            world.lights,
+            vec![Light::point_light(
+                &Tuple::new_point(-10.0, 10.0, -10.0),
+                &Color::new(1.0, 1.0, 1.0),
+            )]
+        );
+
+        let mut first = new_sphere();
+        let mut material = Material::new();
+        material.color = Color::new(1.0, 0.0, 0.0);
+        material.specular = 0.9;
+        material.reflective = 0.3;
+        first.set_material(&material);
+        first.set_transform(
+            &(Transform::translate(0.0, 0.5, 0.0) * Transform::scaling(0.5, 0.5, 0.5)),
+        );
+
+        let mut second = new_sphere();
+        let mut material = Material::new();
+        material.color = Color::new(0.1, 1.0, 0.5);
+        material.diffuse = 0.7;
+        second.set_material(&material);
+        second.set_transform(&Transform::translate(1.5, 0.5, -0.5));
+
+        assert_eq!(world.objects, vec![first, second]);
+    }
+
+    #[test]
+    fn a_scene_renders_like_the_same_scene_built_in_code() {
+        let (world, camera) = load_scene(SCENE).unwrap();
+        let mut expected_world = World::new();
+        expected_world.lights = world.lights.clone();
+        expected_world.objects = world.objects.clone();
+
+        let image = camera.render(&world, 1);
+        let expected = camera.render(&expected_world, 1);
+        assert_eq!(image.pixel_at(50, 25), expected.pixel_at(50, 25));
+        assert_ne!(image.pixel_at(50, 25), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn groups_and_defined_shapes() {
+        let scene = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 0.5
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- define: pillar
+  value:
+    add: cylinder
+    min: 0
+    max: 2
+- add: group
+  transform:
+    - [translate, 0, 1, 0]
+  children:
+    - add: pillar
+    - add: cube
+";
+        let (world, _) = load_scene(scene).unwrap();
+        let Object::Group(group) = &world.objects[0] else {
+            panic!("expected a group");
+        };
+        assert_eq!(group.get_children().len(), 2);
+        assert!(matches!(group.get_children()[0], Object::Cylinder(_)));
+        assert_eq!(
+            group.get_children()[0].bounds(),
+            Bounds::new(
+                Tuple::new_point(-1.0, 0.0, -1.0),
+                Tuple::new_point(1.0, 2.0, 1.0)
+            )
+        );
+        assert_eq!(
+            world.objects[0].get_transform(),
+            Transform::translate(0.0, 1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn an_open_cylinder() {
+        let scene = "
+- add: camera
+  width: 10
+  height: 10
+  field-of-view: 0.5
+  from: [0, 0, -5]
+  to: [0, 0, 0]
+  up: [0, 1, 0]
+- add: cylinder
+  min: 0
+  max: 2
+  closed: false
+- add: cone
+  min: -1
+  max: 0
+";
+        let (world, _) = load_scene(scene).unwrap();
+        assert_eq!(world.objects[0], new_cylinder_open(0.0, 2.0));
+        assert_eq!(world.objects[1], new_cone_capped(-1.0, 0.0));
+        let invalid = scene.replace("closed: false", "closed: 3");
+        assert_eq!(
+            load_scene(&invalid).err(),
+            Some(SceneError::InvalidValue(String::from("closed")))
+        );
+    }
+
+    #[test]
+    fn recursive_definitions_are_an_error() {
+        let recursive = |name: &str| Err(SceneError::RecursiveDefinition(name.to_string()));
+        assert_eq!(
+            load_scene("- define: t\n  value: [t]\n- add: sphere\n  transform: [t]\n"),
+            recursive("t")
+        );
+        let chain = "
+- define: a
+  value: [b]
+- define: b
+  value: [[scale, 2, 2, 2], a]
+- add: sphere
+  transform: [a]
+";
+        assert_eq!(load_scene(chain), recursive("a"));
+        assert_eq!(
+            load_scene("- define: s\n  value:\n    add: s\n- add: s\n"),
+            recursive("s")
+        );
+        let nested = "
+- define: g
+  value:
+    add: group
+    children:
+      - add: g
+- add: g
+";
+        assert_eq!(load_scene(nested), recursive("g"));
+
+        // Using a definition twice side by side is fine
+        let twice = format!(
+            "{SCENE}- define: up\n  value: [[translate, 0, 1, 0]]\n- add: sphere\n  transform: [up, up]\n"
+        );
+        let (w, _) = load_scene(&twice).unwrap();
+        assert_eq!(
+            w.objects.last().unwrap().get_transform(),
+            Transform::translate(0.0, 2.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn loading_invalid_scenes() {
+        assert_eq!(
+            load_scene("- add: light\n  at: [0, 0, 0]\n  intensity: [1, 1, 1]\n"),
+            Err(SceneError::MissingCamera)
+        );
+        assert_eq!(
+            load_scene("- add: teapot\n"),
+            Err(SceneError::UnknownItem(String::from("teapot")))
+        );
+        assert_eq!(
+            load_scene("- add: sphere\n  material: missing\n"),
+            Err(SceneError::UnknownDefinition(String::from("missing")))
+        );
+        assert_eq!(
+            load_scene("- add: sphere\n  transform:\n    - [scale, 0, 1, 1]\n"),
+            Err(SceneError::InvalidScene(RayTracerError::SingularTransform))
+        );
+        assert_eq!(
+            load_scene("- add: light\n  at: [0, 0]\n  intensity: [1, 1, 1]\n"),
+            Err(SceneError::InvalidValue(String::from("at")))
+        );
+        assert!(matches!(
+            load_scene("add: sphere"),
+            Err(SceneError::Yaml(_))
+        ));
+    }
+}
@@ -1,23 +1,35 @@
 #![allow(clippy::approx_constant)]
 use crate::ray_tracer::{
+    bounds::Bounds,
+    error::RayTracerError,
     materials::Material,
     matrices::Matrix,
+    stats,
+    transformations::Transform,
     tuples::{Point, Vector},
 };
-use std::fmt::Debug;
+use std::{
+    f64::consts::PI,
+    fmt::Debug,
+    sync::atomic::{AtomicUsize, Ordering},
+};
 
 mod cylinder;
 pub use cylinder::Cylinder;
 mod cone;
 pub use cone::Cone;
 mod group;
-use group::Group;
+pub use group::Group;
 mod sphere;
 pub use sphere::Sphere;
 mod cube;
 pub use cube::Cube;
 mod plane;
 pub use plane::Plane;
+mod disk;
+pub use disk::Disk;
+mod smooth_triangle;
+pub use smooth_triangle::SmoothTriangle;
 
 #[cfg(test)]
 mod test_shape;
@@ -27,23 +39,61 @@ use test_shape::TestShape;
 use super::{intersections::Intersection, rays::Ray};
 
 pub(super) trait Shapes: Debug + Default + Sync {
+    #[allow(dead_code)]
     fn set_position(&mut self, pos: &Point);
     fn get_position(&self) -> Point;
     fn set_transform(&mut self, transform: &Matrix);
     fn get_transform(&self) -> Matrix;
+    /// Borrows the transformation, so the cached inverse can be used without copying it.
+    fn get_transform_ref(&self) -> &Matrix;
     fn set_material(&mut self, material: &Material);
     fn get_material(&self) -> Material;
+    /// Borrows the material, so shading doesn't have to copy it and its patterns.
+    fn material(&self) -> &Material;
     fn set_parent(&mut self, parent: &BaseShape);
-    fn get_parent(&self) -> BaseShape;
+    fn get_parent(&self) -> Option<&BaseShape>;
+    fn clear_parent(&mut self);
+    fn set_metadata(&mut self, metadata: Option<String>);
+    fn get_metadata(&self) -> Option<&str>;
+    fn set_epsilon(&mut self, epsilon: Option<f64>);
+    fn get_epsilon(&self) -> Option<f64>;
+    fn get_id(&self) -> usize;
+    /// Gives the shape, and everything in it if it is a group, new ids.
+    fn renew_id(&mut self);
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>);
+    fn get_motion(&self) -> Option<(Matrix, Matrix)>;
+    fn set_casts_shadow(&mut self, casts_shadow: bool);
+    fn get_casts_shadow(&self) -> bool;
+    /// The bounding box of the shape in its own object space.
+    fn bounds(&self) -> Bounds;
     fn local_normal_at(&self, point: Point) -> Vector;
     fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection>;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BaseShape {
     position: Option<Point>,
     transform: Option<Matrix>,
     material: Option<Material>,
+    /// Free-form data attached by the user, e.g. a name or an external id.
+    /// The renderer never looks at it.
+    metadata: Option<String>,
+    /// Distance to move hit points off the surface to avoid self-intersection.
+    /// When `None`, `utils::EPSILON` is used.
+    epsilon: Option<f64>,
+    /// Unique for every constructed shape, and kept by clones until they are added to a
+    /// [`World`](crate::ray_tracer::world::World) or a [`Group`], which gives them an id of
+    /// their own. Not part of equality, so identical shapes still compare equal.
+    /// Deserialized shapes get new ids.
+    #[cfg_attr(feature = "serde", serde(skip, default = "BaseShape::next_id"))]
+    id: usize,
+    /// Transforms at the start and end of a moving shape's motion.
+    motion: Option<(Matrix, Matrix)>,
+    /// Whether the shape blocks light on its way to other surfaces.
+    casts_shadow: bool,
 }
 impl BaseShape {
     pub fn new() -> Self {
@@ -51,8 +101,40 @@ impl BaseShape {
             position: None,
             transform: None,
             material: None,
+            metadata: None,
+            epsilon: None,
+            id: BaseShape::next_id(),
+            motion: None,
+            casts_shadow: true,
         }
     }
+
+    fn next_id() -> usize {
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    }
+
+    fn renew_id(&mut self) {
+        self.id = BaseShape::next_id();
+    }
+
+    /// Sets the transformation.
+    /// Panics if the transformation can't be inverted.
+    fn set_transform(&mut self, transform: &Matrix) {
+        let mut trans = *transform;
+        trans.calculate_inverse().unwrap();
+        self.transform = Some(trans);
+    }
+}
+impl PartialEq for BaseShape {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+            && self.transform == other.transform
+            && self.material == other.material
+            && self.metadata == other.metadata
+            && self.epsilon == other.epsilon
+            && self.motion == other.motion
+            && self.casts_shadow == other.casts_shadow
+    }
 }
 impl Default for BaseShape {
     fn default() -> Self {
@@ -61,6 +143,7 @@ impl Default for BaseShape {
 }
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Object {
     Group(Group),
     Sphere(Sphere),
@@ -68,28 +151,25 @@ pub enum Object {
     Cube(Cube),
     Cylinder(Cylinder),
     Cone(Cone),
+    Disk(Disk),
+    SmoothTriangle(SmoothTriangle),
 
     #[cfg(test)]
     TestShape(TestShape),
 }
 impl Object {
-    fn world_point_to_local(&self, point: &Point) -> Point {
-        let inverted = match self {
-            Object::Group(g) => g.get_transform().get_inverted().unwrap(),
-            Object::Sphere(s) => s.get_transform().get_inverted().unwrap(),
-            Object::Plane(p) => p.get_transform().get_inverted().unwrap(),
-            Object::Cube(c) => c.get_transform().get_inverted().unwrap(),
-            Object::Cylinder(c) => c.get_transform().get_inverted().unwrap(),
-            Object::Cone(c) => c.get_transform().get_inverted().unwrap(),
-
-            #[cfg(test)]
-            Object::TestShape(s) => s.get_transform().get_inverted().unwrap(),
+    /// Converts a point from world space to the object's own space, through the transforms of
+    /// every group it is nested in.
+    pub(crate) fn world_to_object(&self, point: &Point) -> Point {
+        let point = match self.get_parent() {
+            Some(parent) => parent.transform.as_ref().unwrap().mul_inverse(point),
+            None => *point,
         };
 
-        inverted * *point
+        self.get_transform_ref().mul_inverse(&point)
     }
     pub(crate) fn normal_at(&self, world_point: Point) -> Vector {
-        let local_point = self.world_point_to_local(&world_point);
+        let local_point = self.world_to_object(&world_point);
         let local_normal = match self {
             Object::Group(g) => g.local_normal_at(local_point),
             Object::Sphere(s) => s.local_normal_at(local_point),
@@ -97,28 +177,38 @@ impl Object {
             Object::Cube(c) => c.local_normal_at(local_point),
             Object::Cylinder(c) => c.local_normal_at(local_point),
             Object::Cone(c) => c.local_normal_at(local_point),
+            Object::Disk(d) => d.local_normal_at(local_point),
+            Object::SmoothTriangle(t) => t.local_normal_at(local_point),
 
             #[cfg(test)]
             Object::TestShape(s) => s.local_normal_at(local_point),
         };
+        let local_normal = self.bump_normal(&local_normal, &local_point);
 
         self.local_vector_to_world(&local_normal)
     }
+    /// Tilts a normal in object space by the material's `normal_pattern`, if it has one.
+    fn bump_normal(&self, local_normal: &Vector, local_point: &Point) -> Vector {
+        match &self.material().normal_pattern {
+            Some(pattern) => {
+                let offset = pattern.inner_pattern_at(*local_point);
+                (*local_normal + Vector::new_vector(offset.red, offset.green, offset.blue))
+                    .normalize()
+            }
+            None => *local_normal,
+        }
+    }
     fn local_vector_to_world(&self, local_vector: &Vector) -> Vector {
-        let inverted = match self {
-            Object::Group(g) => g.get_transform().get_inverted().unwrap(),
-            Object::Sphere(s) => s.get_transform().get_inverted().unwrap(),
-            Object::Plane(p) => p.get_transform().get_inverted().unwrap(),
-            Object::Cube(c) => c.get_transform().get_inverted().unwrap(),
-            Object::Cylinder(c) => c.get_transform().get_inverted().unwrap(),
-            Object::Cone(c) => c.get_transform().get_inverted().unwrap(),
-
-            #[cfg(test)]
-            Object::TestShape(s) => s.get_transform().get_inverted().unwrap(),
-        };
-
-        let mut world_vector = inverted.transpose().unwrap() * *local_vector;
+        let mut world_vector = self.get_transform_ref().mul_inverse_transpose(local_vector);
         world_vector.w = 0.0;
+        if let Some(parent) = self.get_parent() {
+            world_vector = parent
+                .transform
+                .as_ref()
+                .unwrap()
+                .mul_inverse_transpose(&world_vector);
+            world_vector.w = 0.0;
+        }
         world_vector.normalize()
     }
 
@@ -130,11 +220,20 @@ impl Object {
             Object::Cube(c) => c.set_transform(transform),
             Object::Cylinder(c) => c.set_transform(transform),
             Object::Cone(c) => c.set_transform(transform),
+            Object::Disk(d) => d.set_transform(transform),
+            Object::SmoothTriangle(t) => t.set_transform(transform),
 
             #[cfg(test)]
             Object::TestShape(s) => s.set_transform(transform),
         }
     }
+    /// Sets the transformation, or returns an error if the matrix contains NaN or can't be
+    /// inverted.
+    pub fn try_set_transform(&mut self, transform: &Matrix) -> Result<(), RayTracerError> {
+        transform.validate_transform()?;
+        self.set_transform(transform);
+        Ok(())
+    }
     pub fn get_transform(&self) -> Matrix {
         match self {
             Object::Group(g) => g.get_transform(),
@@ -143,11 +242,33 @@ impl Object {
             Object::Cube(c) => c.get_transform(),
             Object::Cylinder(c) => c.get_transform(),
             Object::Cone(c) => c.get_transform(),
+            Object::Disk(d) => d.get_transform(),
+            Object::SmoothTriangle(t) => t.get_transform(),
 
             #[cfg(test)]
             Object::TestShape(s) => s.get_transform(),
         }
     }
+    pub(crate) fn get_transform_ref(&self) -> &Matrix {
+        match self {
+            Object::Group(g) => g.get_transform_ref(),
+            Object::Sphere(s) => s.get_transform_ref(),
+            Object::Plane(p) => p.get_transform_ref(),
+            Object::Cube(c) => c.get_transform_ref(),
+            Object::Cylinder(c) => c.get_transform_ref(),
+            Object::Cone(c) => c.get_transform_ref(),
+            Object::Disk(d) => d.get_transform_ref(),
+            Object::SmoothTriangle(t) => t.get_transform_ref(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_transform_ref(),
+        }
+    }
+    /// Splits the object's transformation into (translation, scale, rotation), with the
+    /// rotation as Euler angles in radians. See [`Transform::decompose`] for the limitations.
+    pub fn decompose_transform(&self) -> (Vector, Vector, Vector) {
+        Transform::decompose(&self.get_transform())
+    }
     pub fn set_material(&mut self, material: &Material) {
         match self {
             Object::Group(g) => g.set_material(material),
@@ -156,22 +277,312 @@ impl Object {
             Object::Cube(c) => c.set_material(material),
             Object::Cylinder(c) => c.set_material(material),
             Object::Cone(c) => c.set_material(material),
+            Object::Disk(d) => d.set_material(material),
+            Object::SmoothTriangle(t) => t.set_material(material),
 
             #[cfg(test)]
             Object::TestShape(s) => s.set_material(material),
         }
     }
+    /// Sets the material, and for groups also the material of every child.
+    pub(crate) fn override_material(&mut self, material: &Material) {
+        match self {
+            Object::Group(g) => g.override_material(material),
+            _ => self.set_material(material),
+        }
+    }
     pub fn get_material(&self) -> Material {
+        self.material().clone()
+    }
+    /// Borrows the material, which avoids copying its patterns.
+    pub fn material(&self) -> &Material {
+        match self {
+            Object::Group(g) => g.material(),
+            Object::Sphere(s) => s.material(),
+            Object::Plane(p) => p.material(),
+            Object::Cube(c) => c.material(),
+            Object::Cylinder(c) => c.material(),
+            Object::Cone(c) => c.material(),
+            Object::Disk(d) => d.material(),
+            Object::SmoothTriangle(t) => t.material(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.material(),
+        }
+    }
+    pub fn set_metadata(&mut self, metadata: Option<String>) {
+        match self {
+            Object::Group(g) => g.set_metadata(metadata),
+            Object::Sphere(s) => s.set_metadata(metadata),
+            Object::Plane(p) => p.set_metadata(metadata),
+            Object::Cube(c) => c.set_metadata(metadata),
+            Object::Cylinder(c) => c.set_metadata(metadata),
+            Object::Cone(c) => c.set_metadata(metadata),
+            Object::Disk(d) => d.set_metadata(metadata),
+            Object::SmoothTriangle(t) => t.set_metadata(metadata),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.set_metadata(metadata),
+        }
+    }
+    pub fn get_metadata(&self) -> Option<&str> {
+        match self {
+            Object::Group(g) => g.get_metadata(),
+            Object::Sphere(s) => s.get_metadata(),
+            Object::Plane(p) => p.get_metadata(),
+            Object::Cube(c) => c.get_metadata(),
+            Object::Cylinder(c) => c.get_metadata(),
+            Object::Cone(c) => c.get_metadata(),
+            Object::Disk(d) => d.get_metadata(),
+            Object::SmoothTriangle(t) => t.get_metadata(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_metadata(),
+        }
+    }
+    /// Overrides the distance hit points are moved off this shape's surface when shading it.
+    /// Thin or closely spaced geometry may need a smaller value than the default
+    /// `utils::EPSILON`, and large shapes may need a bigger one. `None` uses the default.
+    pub fn set_epsilon(&mut self, epsilon: Option<f64>) {
         match self {
-            Object::Group(g) => g.get_material(),
-            Object::Sphere(s) => s.get_material(),
-            Object::Plane(p) => p.get_material(),
-            Object::Cube(c) => c.get_material(),
-            Object::Cylinder(c) => c.get_material(),
-            Object::Cone(c) => c.get_material(),
+            Object::Group(g) => g.set_epsilon(epsilon),
+            Object::Sphere(s) => s.set_epsilon(epsilon),
+            Object::Plane(p) => p.set_epsilon(epsilon),
+            Object::Cube(c) => c.set_epsilon(epsilon),
+            Object::Cylinder(c) => c.set_epsilon(epsilon),
+            Object::Cone(c) => c.set_epsilon(epsilon),
+            Object::Disk(d) => d.set_epsilon(epsilon),
+            Object::SmoothTriangle(t) => t.set_epsilon(epsilon),
 
             #[cfg(test)]
-            Object::TestShape(s) => s.get_material(),
+            Object::TestShape(s) => s.set_epsilon(epsilon),
+        }
+    }
+    pub fn get_epsilon(&self) -> Option<f64> {
+        match self {
+            Object::Group(g) => g.get_epsilon(),
+            Object::Sphere(s) => s.get_epsilon(),
+            Object::Plane(p) => p.get_epsilon(),
+            Object::Cube(c) => c.get_epsilon(),
+            Object::Cylinder(c) => c.get_epsilon(),
+            Object::Cone(c) => c.get_epsilon(),
+            Object::Disk(d) => d.get_epsilon(),
+            Object::SmoothTriangle(t) => t.get_epsilon(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_epsilon(),
+        }
+    }
+    /// Decides whether the shape blocks light from reaching other surfaces, e.g. to keep a glass
+    /// object or a helper shape from darkening what's behind it. Setting it on a group sets it
+    /// on everything in the group. Shapes cast shadows by default.
+    pub fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        match self {
+            Object::Group(g) => g.set_casts_shadow(casts_shadow),
+            Object::Sphere(s) => s.set_casts_shadow(casts_shadow),
+            Object::Plane(p) => p.set_casts_shadow(casts_shadow),
+            Object::Cube(c) => c.set_casts_shadow(casts_shadow),
+            Object::Cylinder(c) => c.set_casts_shadow(casts_shadow),
+            Object::Cone(c) => c.set_casts_shadow(casts_shadow),
+            Object::Disk(d) => d.set_casts_shadow(casts_shadow),
+            Object::SmoothTriangle(t) => t.set_casts_shadow(casts_shadow),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.set_casts_shadow(casts_shadow),
+        }
+    }
+    pub fn casts_shadow(&self) -> bool {
+        match self {
+            Object::Group(g) => g.get_casts_shadow(),
+            Object::Sphere(s) => s.get_casts_shadow(),
+            Object::Plane(p) => p.get_casts_shadow(),
+            Object::Cube(c) => c.get_casts_shadow(),
+            Object::Cylinder(c) => c.get_casts_shadow(),
+            Object::Cone(c) => c.get_casts_shadow(),
+            Object::Disk(d) => d.get_casts_shadow(),
+            Object::SmoothTriangle(t) => t.get_casts_shadow(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_casts_shadow(),
+        }
+    }
+    /// Makes the shape move from the `start` transform at time 0 to the `end` transform at time 1,
+    /// for motion blurred renders. The shape is placed at `start` in still renders.
+    /// Positions in between are blended with [`Transform::interpolate`].
+    pub fn set_motion(&mut self, start: &Matrix, end: &Matrix) {
+        self.set_transform(start);
+        let motion = Some((*start, *end));
+        match self {
+            Object::Group(g) => g.set_motion(motion),
+            Object::Sphere(s) => s.set_motion(motion),
+            Object::Plane(p) => p.set_motion(motion),
+            Object::Cube(c) => c.set_motion(motion),
+            Object::Cylinder(c) => c.set_motion(motion),
+            Object::Cone(c) => c.set_motion(motion),
+            Object::Disk(d) => d.set_motion(motion),
+            Object::SmoothTriangle(t) => t.set_motion(motion),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.set_motion(motion),
+        }
+    }
+    pub fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        match self {
+            Object::Group(g) => g.get_motion(),
+            Object::Sphere(s) => s.get_motion(),
+            Object::Plane(p) => p.get_motion(),
+            Object::Cube(c) => c.get_motion(),
+            Object::Cylinder(c) => c.get_motion(),
+            Object::Cone(c) => c.get_motion(),
+            Object::Disk(d) => d.get_motion(),
+            Object::SmoothTriangle(t) => t.get_motion(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_motion(),
+        }
+    }
+    /// Moves the shape, and everything in it, to where it is at `time`.
+    pub(crate) fn move_to_time(&mut self, time: f64) {
+        if let Object::Group(g) = self {
+            g.move_to_time(time);
+        }
+        if let Some((start, end)) = self.get_motion() {
+            let blended = Transform::interpolate(&start, &end, time);
+            // Only a scale that changes sign on the way, e.g. into a mirroring, passes through
+            // zero; jump over it instead of failing
+            if blended.validate_transform().is_ok() {
+                self.set_transform(&blended);
+            } else if time < 0.5 {
+                self.set_transform(&start);
+            } else {
+                self.set_transform(&end);
+            }
+        }
+    }
+    /// Identifies the shape, e.g. to order intersections deterministically.
+    pub fn get_id(&self) -> usize {
+        match self {
+            Object::Group(g) => g.get_id(),
+            Object::Sphere(s) => s.get_id(),
+            Object::Plane(p) => p.get_id(),
+            Object::Cube(c) => c.get_id(),
+            Object::Cylinder(c) => c.get_id(),
+            Object::Cone(c) => c.get_id(),
+            Object::Disk(d) => d.get_id(),
+            Object::SmoothTriangle(t) => t.get_id(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_id(),
+        }
+    }
+    /// Whether `other` is this object, or is somewhere inside it if this is a group.
+    /// Objects are matched by id, so a clone of a shape counts as the shape itself until either
+    /// is added to a world or a group.
+    pub fn includes(&self, other: &Object) -> bool {
+        if self.get_id() == other.get_id() {
+            return true;
+        }
+        match self {
+            Object::Group(g) => g.get_children().iter().any(|child| child.includes(other)),
+            _ => false,
+        }
+    }
+    /// Collects every non-group shape in the tree as a stand-alone shape, with the
+    /// transformations of the groups above it folded into its own and no parent left. The
+    /// shapes can be put into a [`World`](crate::ray_tracer::world::World) in place of the tree
+    /// and intersected as a flat list, without descending through groups.
+    pub fn flatten(&self) -> Vec<Object> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(&mut leaves);
+        leaves
+    }
+    fn collect_leaves(&self, leaves: &mut Vec<Object>) {
+        match self {
+            Object::Group(g) => {
+                for child in g.get_children() {
+                    child.collect_leaves(leaves);
+                }
+            }
+            _ => {
+                let mut leaf = self.clone();
+                if let Some(parent) = self.get_parent() {
+                    let to_world = *parent.transform.as_ref().unwrap();
+                    leaf.set_transform(&(to_world * self.get_transform()));
+                    if let Some((start, end)) = self.get_motion() {
+                        leaf.set_motion(&(to_world * start), &(to_world * end));
+                    }
+                    leaf.clear_parent();
+                }
+                leaves.push(leaf);
+            }
+        }
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        match self {
+            Object::Group(g) => g.get_parent(),
+            Object::Sphere(s) => s.get_parent(),
+            Object::Plane(p) => p.get_parent(),
+            Object::Cube(c) => c.get_parent(),
+            Object::Cylinder(c) => c.get_parent(),
+            Object::Cone(c) => c.get_parent(),
+            Object::Disk(d) => d.get_parent(),
+            Object::SmoothTriangle(t) => t.get_parent(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.get_parent(),
+        }
+    }
+    /// The bounding box of the object in its own object space.
+    pub fn bounds(&self) -> Bounds {
+        match self {
+            Object::Group(g) => g.bounds(),
+            Object::Sphere(s) => s.bounds(),
+            Object::Plane(p) => p.bounds(),
+            Object::Cube(c) => c.bounds(),
+            Object::Cylinder(c) => c.bounds(),
+            Object::Cone(c) => c.bounds(),
+            Object::Disk(d) => d.bounds(),
+            Object::SmoothTriangle(t) => t.bounds(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.bounds(),
+        }
+    }
+    /// The bounding box of the object after applying its own transformation, i.e. in the space
+    /// of the group containing it.
+    pub fn parent_space_bounds(&self) -> Bounds {
+        self.bounds().transform(&self.get_transform())
+    }
+    /// Gives the object, and everything in it if it is a group, new ids, so that it is told
+    /// apart from the shape it was cloned from.
+    pub(crate) fn renew_id(&mut self) {
+        match self {
+            Object::Group(g) => g.renew_id(),
+            Object::Sphere(s) => s.renew_id(),
+            Object::Plane(p) => p.renew_id(),
+            Object::Cube(c) => c.renew_id(),
+            Object::Cylinder(c) => c.renew_id(),
+            Object::Cone(c) => c.renew_id(),
+            Object::Disk(d) => d.renew_id(),
+            Object::SmoothTriangle(t) => t.renew_id(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.renew_id(),
+        }
+    }
+    fn clear_parent(&mut self) {
+        match self {
+            Object::Group(g) => g.clear_parent(),
+            Object::Sphere(s) => s.clear_parent(),
+            Object::Plane(p) => p.clear_parent(),
+            Object::Cube(c) => c.clear_parent(),
+            Object::Cylinder(c) => c.clear_parent(),
+            Object::Cone(c) => c.clear_parent(),
+            Object::Disk(d) => d.clear_parent(),
+            Object::SmoothTriangle(t) => t.clear_parent(),
+
+            #[cfg(test)]
+            Object::TestShape(s) => s.clear_parent(),
         }
     }
     fn set_parent(&mut self, parent: &BaseShape) {
@@ -182,12 +593,15 @@ impl Object {
             Object::Cube(c) => c.set_parent(parent),
             Object::Cylinder(c) => c.set_parent(parent),
             Object::Cone(c) => c.set_parent(parent),
+            Object::Disk(d) => d.set_parent(parent),
+            Object::SmoothTriangle(t) => t.set_parent(parent),
 
             #[cfg(test)]
             Object::TestShape(s) => s.set_parent(parent),
         }
     }
     pub(crate) fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
+        stats::count_intersection_test();
         match self {
             Object::Group(g) => g.local_intersect(local_ray),
             Object::Sphere(s) => s.local_intersect(local_ray),
@@ -195,6 +609,8 @@ impl Object {
             Object::Cube(c) => c.local_intersect(local_ray),
             Object::Cylinder(c) => c.local_intersect(local_ray),
             Object::Cone(c) => c.local_intersect(local_ray),
+            Object::Disk(d) => d.local_intersect(local_ray),
+            Object::SmoothTriangle(t) => t.local_intersect(local_ray),
 
             #[cfg(test)]
             Object::TestShape(s) => s.local_intersect(local_ray),
@@ -230,6 +646,38 @@ pub fn new_cylinder(max_min: Option<(f64, f64)>) -> Object {
 
     Object::Cylinder(cyl)
 }
+/// A cylinder truncated to `minimum < y < maximum`, without caps, like a piece of pipe.
+pub fn new_cylinder_open(minimum: f64, maximum: f64) -> Object {
+    let mut cyl = Cylinder::default();
+    cyl.minimum = minimum;
+    cyl.maximum = maximum;
+    cyl.closed = false;
+
+    Object::Cylinder(cyl)
+}
+/// A cylinder truncated to `minimum < y < maximum`, closed off with caps at both ends.
+/// Same as `new_cylinder(Some((maximum, minimum)))`.
+pub fn new_cylinder_capped(minimum: f64, maximum: f64) -> Object {
+    let mut cyl = Cylinder::default();
+    cyl.minimum = minimum;
+    cyl.maximum = maximum;
+    cyl.closed = true;
+
+    Object::Cylinder(cyl)
+}
+/// Like [`new_cylinder`], but with sides `radius` away from the y-axis instead of 1. Unlike
+/// scaling a unit cylinder, this keeps the caps round and the cap height unchanged.
+pub fn new_cylinder_with_radius(radius: f64, max_min: Option<(f64, f64)>) -> Object {
+    let mut cyl = Cylinder::default();
+    cyl.radius = radius;
+    if let Some(max_min) = max_min {
+        cyl.maximum = max_min.0;
+        cyl.minimum = max_min.1;
+        cyl.closed = true;
+    }
+
+    Object::Cylinder(cyl)
+}
 pub fn new_cone(max_min: Option<(f64, f64)>) -> Object {
     let mut cone = Cone::default();
     if let Some(max_min) = max_min {
@@ -240,9 +688,86 @@ pub fn new_cone(max_min: Option<(f64, f64)>) -> Object {
 
     Object::Cone(cone)
 }
+/// A double cone truncated to `minimum < y < maximum`, without caps.
+pub fn new_cone_open(minimum: f64, maximum: f64) -> Object {
+    let mut cone = Cone::default();
+    cone.minimum = minimum;
+    cone.maximum = maximum;
+    cone.closed = false;
+
+    Object::Cone(cone)
+}
+/// A double cone truncated to `minimum < y < maximum`, closed off with caps at both ends.
+/// Same as `new_cone(Some((maximum, minimum)))`.
+pub fn new_cone_capped(minimum: f64, maximum: f64) -> Object {
+    let mut cone = Cone::default();
+    cone.minimum = minimum;
+    cone.maximum = maximum;
+    cone.closed = true;
+
+    Object::Cone(cone)
+}
+/// Creates a flat ring in the xz-plane between the `inner` and `outer` radius.
+/// An inner radius of 0 gives a solid disk.
+pub fn new_disk(inner: f64, outer: f64) -> Object {
+    let mut disk = Disk::default();
+    disk.inner = inner;
+    disk.outer = outer;
+
+    Object::Disk(disk)
+}
 pub fn new_group(group: Group) -> Object {
     Object::Group(group)
 }
+pub fn new_smooth_triangle(
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+) -> Object {
+    Object::SmoothTriangle(SmoothTriangle::new(p1, p2, p3, n1, n2, n3))
+}
+/// Builds a unit sphere out of smooth triangles, for comparing meshes against [`Sphere`].
+/// The sphere is cut into `lat_segments` bands from pole to pole, at least 2, and
+/// `long_segments` slices around the y-axis, at least 3. Every vertex gets the true sphere
+/// normal.
+pub fn uv_sphere_mesh(lat_segments: usize, long_segments: usize) -> Object {
+    let lat_segments = lat_segments.max(2);
+    let long_segments = long_segments.max(3);
+    let vertex = |lat: usize, long: usize| {
+        let polar = PI * lat as f64 / lat_segments as f64;
+        let azimuth = 2.0 * PI * long as f64 / long_segments as f64;
+        Vector::new_vector(
+            polar.sin() * azimuth.cos(),
+            polar.cos(),
+            polar.sin() * azimuth.sin(),
+        )
+    };
+    let triangle = |a: Vector, b: Vector, c: Vector| {
+        let to_point = |v: Vector| Point::new_point(v.x, v.y, v.z);
+        new_smooth_triangle(to_point(a), to_point(b), to_point(c), a, b, c)
+    };
+
+    let mut group = Group::new();
+    for lat in 0..lat_segments {
+        for long in 0..long_segments {
+            let a = vertex(lat, long);
+            let b = vertex(lat + 1, long);
+            let c = vertex(lat + 1, long + 1);
+            let d = vertex(lat, long + 1);
+            // The bands touching the poles are fans of single triangles
+            if lat != lat_segments - 1 {
+                group.add_child(triangle(a, b, c));
+            }
+            if lat != 0 {
+                group.add_child(triangle(a, c, d));
+            }
+        }
+    }
+    new_group(group)
+}
 
 #[cfg(test)]
 fn new_test_shape() -> Object {
@@ -254,7 +779,134 @@ mod tests {
     use std::f64::consts::PI;
 
     use super::*;
-    use crate::ray_tracer::{transformations::Transform, tuples::Tuple};
+    use crate::ray_tracer::{
+        camera::Camera, colors::Color, patterns::Pattern, tuples::Tuple, world::World,
+    };
+
+    #[test]
+    fn flattening_a_two_level_group_composes_the_transforms() {
+        let mut sphere = new_sphere();
+        sphere.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        let mut inner = Group::new();
+        inner.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        inner.add_child(sphere.clone());
+        let mut cube = new_cube();
+        cube.set_transform(&Transform::translate(0.0, 1.0, 0.0));
+        let mut outer = Group::new();
+        outer.set_transform(&Transform::rotation_y(PI / 2.0));
+        outer.add_child(new_group(inner));
+        outer.add_child(cube.clone());
+
+        let group = new_group(outer);
+        let leaves = group.flatten();
+        assert_eq!(leaves.len(), 2);
+        assert!(leaves.iter().all(|leaf| leaf.get_parent().is_none()));
+        assert_eq!(
+            leaves[0].get_transform(),
+            Transform::rotation_y(PI / 2.0)
+                * Transform::scaling(2.0, 2.0, 2.0)
+                * Transform::translate(5.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            leaves[1].get_transform(),
+            Transform::rotation_y(PI / 2.0) * Transform::translate(0.0, 1.0, 0.0)
+        );
+
+        let Object::Group(g) = &group else {
+            unreachable!()
+        };
+        let point = Tuple::new_point(-2.0, 3.0, -10.0);
+        assert_eq!(
+            leaves[1].world_to_object(&point),
+            g.get_children()[1].world_to_object(&point)
+        );
+        assert_eq!(
+            leaves[1].normal_at(Tuple::new_point(0.0, 1.0, 0.5)),
+            g.get_children()[1].normal_at(Tuple::new_point(0.0, 1.0, 0.5))
+        );
+    }
+    #[test]
+    fn a_flattened_world_renders_like_the_grouped_one() {
+        let mut sphere = new_sphere();
+        sphere.set_transform(&Transform::translate(1.5, 0.0, 0.0));
+        let mut inner = Group::new();
+        inner.set_transform(&Transform::scaling(0.5, 1.0, 0.5));
+        inner.add_child(sphere);
+        inner.add_child(new_cube());
+        let mut outer = Group::new();
+        outer.set_transform(&(Transform::translate(0.0, 0.5, 0.0) * Transform::rotation_y(0.4)));
+        outer.add_child(new_group(inner));
+        outer.add_child(new_cylinder_capped(-0.5, 0.5));
+
+        let mut grouped = World::new_default_world();
+        grouped.objects = vec![new_group(outer)];
+        let mut flat = World::new_default_world();
+        flat.objects = grouped.objects[0].flatten();
+        assert_eq!(flat.objects.len(), 3);
+
+        let mut c = Camera::new(21, 21, PI / 3.0);
+        c.set_transform(Transform::view_transform(
+            &Tuple::new_point(1.0, 2.0, -5.0),
+            &Tuple::new_point(0.0, 0.5, 0.0),
+            &Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+        let image = c.render(&grouped, 1);
+        assert!(c.render(&flat, 1).max_channel_diff(&image) < 1e-4);
+    }
+    #[test]
+    fn a_shape_includes_itself_and_a_group_includes_its_descendants() {
+        let s = new_sphere();
+        assert!(s.includes(&s));
+        assert!(!s.includes(&new_sphere()));
+
+        let mut inner = Group::new();
+        inner.add_child(new_cube());
+        let mut outer = Group::new();
+        outer.add_child(s.clone());
+        outer.add_child(new_group(inner));
+        let child = outer.child(0).unwrap().clone();
+        let cube = match outer.child(1) {
+            Some(Object::Group(inner)) => inner.child(0).unwrap().clone(),
+            _ => unreachable!(),
+        };
+        let outer = new_group(outer);
+
+        assert!(outer.includes(&outer));
+        assert!(outer.includes(&child));
+        assert!(outer.includes(&cube));
+        assert!(!outer.includes(&new_cube()));
+        assert!(!child.includes(&outer));
+        // Adding a shape to a group makes it a shape of its own
+        assert!(!outer.includes(&s));
+    }
+
+    #[test]
+    fn open_and_capped_cylinders_and_cones() {
+        let down = |x: f64| {
+            Ray::new(
+                Point::new_point(x, 3.0, 0.0),
+                Vector::new_vector(0.0, -1.0, 0.0),
+            )
+        };
+        let side = Ray::new(
+            Point::new_point(0.0, 1.5, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        let open = new_cylinder_open(1.0, 2.0);
+        let capped = new_cylinder_capped(1.0, 2.0);
+        assert_eq!(side.intersect(&open).len(), 2);
+        assert_eq!(side.intersect(&capped).len(), 2);
+        assert_eq!(down(0.0).intersect(&open).len(), 0);
+        assert_eq!(down(0.0).intersect(&capped).len(), 2);
+        assert_eq!(capped, new_cylinder(Some((2.0, 1.0))));
+
+        let open = new_cone_open(0.5, 1.0);
+        let capped = new_cone_capped(0.5, 1.0);
+        assert_eq!(down(0.0).intersect(&open).len(), 0);
+        assert_eq!(down(0.0).intersect(&capped).len(), 2);
+        assert_eq!(capped, new_cone(Some((1.0, 0.5))));
+    }
 
     #[test]
     fn the_default_transformation() {
@@ -268,6 +920,28 @@ mod tests {
         assert_eq!(s.get_transform(), Transform::translate(2.0, 3.0, 4.0));
     }
     #[test]
+    fn assigning_an_invalid_transformation_is_an_error() {
+        let mut s = new_test_shape();
+        assert_eq!(
+            s.try_set_transform(&Transform::scaling(0.0, 1.0, 1.0)),
+            Err(RayTracerError::SingularTransform)
+        );
+        assert_eq!(
+            s.try_set_transform(&Transform::translate(f64::NAN, 0.0, 0.0)),
+            Err(RayTracerError::NaNInput)
+        );
+        assert_eq!(s.get_transform(), Matrix::new_identity());
+    }
+    #[test]
+    fn decomposing_a_shapes_transformation() {
+        let mut s = new_test_shape();
+        s.set_transform(&(Transform::translate(2.0, 3.0, 4.0) * Transform::scaling(0.5, 1.0, 2.0)));
+        let (translation, scale, rotation) = s.decompose_transform();
+        assert_eq!(translation, Tuple::new_vector(2.0, 3.0, 4.0));
+        assert_eq!(scale, Tuple::new_vector(0.5, 1.0, 2.0));
+        assert_eq!(rotation, Tuple::new_vector(0.0, 0.0, 0.0));
+    }
+    #[test]
     fn the_default_material() {
         let s = new_test_shape();
         let m = s.get_material();
@@ -280,14 +954,33 @@ mod tests {
         m.ambient = 1.0;
         s.set_material(&m);
         assert_eq!(s.get_material(), m);
+        assert_eq!(s.material(), &m);
     }
     #[test]
     fn intersecting_a_scaled_shape_with_a_ray() {
-        todo!("Convert Ray to accept new objects")
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut s = new_test_shape();
+        s.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let _xs = r.intersect(&s);
+        let saved_ray = TestShape::get_saved_ray().unwrap();
+        assert_eq!(saved_ray.origin, Tuple::new_point(0.0, 0.0, -2.5));
+        assert_eq!(saved_ray.direction, Tuple::new_vector(0.0, 0.0, 0.5));
     }
     #[test]
     fn intersecting_a_translated_shape_with_a_ray() {
-        todo!("Convert Ray to accept new objects")
+        let r = Ray::new(
+            Tuple::new_point(0.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let mut s = new_test_shape();
+        s.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        let _xs = r.intersect(&s);
+        let saved_ray = TestShape::get_saved_ray().unwrap();
+        assert_eq!(saved_ray.origin, Tuple::new_point(-5.0, 0.0, -5.0));
+        assert_eq!(saved_ray.direction, Tuple::new_vector(0.0, 0.0, 1.0));
     }
     #[test]
     fn computing_the_normal_on_a_translated_shape() {
@@ -297,6 +990,71 @@ mod tests {
         assert_eq!(n, Tuple::new_vector(0.0, 0.70711, -0.70711));
     }
     #[test]
+    fn the_transformation_is_borrowed_instead_of_copied() {
+        let mut s = new_sphere();
+        s.set_transform(&(Transform::translate(1.0, 2.0, 3.0) * Transform::scaling(2.0, 1.0, 0.5)));
+
+        // The borrowed matrix comes with its inverse already worked out, so nothing needs to be
+        // copied or inverted again to take points into object space
+        assert_eq!(*s.get_transform_ref(), s.get_transform());
+        assert!(s.get_transform_ref().get_inverted().is_ok());
+        assert!(s.get_transform_ref().get_inverse_transpose().is_ok());
+
+        let point = Tuple::new_point(3.0, -1.0, 2.0);
+        let inverse = s.get_transform().get_inverted().unwrap();
+        let inverse_transpose = s.get_transform().get_inverse_transpose().unwrap();
+        assert_eq!(s.get_transform_ref().mul_inverse(&point), inverse * point);
+        assert_eq!(
+            s.get_transform_ref().mul_inverse_transpose(&point),
+            inverse_transpose * point
+        );
+        assert_eq!(s.world_to_object(&point), inverse * point);
+    }
+    #[test]
+    fn a_finer_uv_sphere_mesh_has_normals_closer_to_a_sphere() {
+        let r = Ray::new(
+            Tuple::new_point(0.3, 0.2, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let first_hit = |object: &Object| {
+            let hit = r
+                .intersect(object)
+                .into_iter()
+                .min_by(|a, b| a.get_time().total_cmp(&b.get_time()))
+                .unwrap();
+            hit.get_object().normal_at(r.position(hit.get_time()))
+        };
+        let sphere_normal = first_hit(&new_sphere());
+        let normal_error = |lat_segments, long_segments| {
+            let mesh_normal = first_hit(&uv_sphere_mesh(lat_segments, long_segments));
+            (mesh_normal - sphere_normal).magnitude()
+        };
+
+        let coarse = normal_error(4, 8);
+        let medium = normal_error(8, 16);
+        let fine = normal_error(32, 64);
+        assert!(medium < coarse);
+        assert!(fine < medium);
+        assert!(fine < 0.01);
+    }
+    #[test]
+    fn a_normal_pattern_tilts_the_normal() {
+        let mut flat = new_plane();
+        flat.set_transform(&Transform::rotation_x(PI / 2.0));
+        let mut bumped = flat.clone();
+        let mut material = bumped.get_material();
+        material.normal_pattern = Some(Pattern::solid(Color::new(1.0, 0.0, 0.0)));
+        bumped.set_material(&material);
+
+        // The plane is rotated to face +z, and the bump tilts it towards x in both spaces
+        let point = Tuple::new_point(3.0, 2.0, 0.0);
+        assert_eq!(flat.normal_at(point), Tuple::new_vector(0.0, 0.0, 1.0));
+        assert_eq!(
+            bumped.normal_at(point),
+            Tuple::new_vector(1.0, 0.0, 1.0).normalize()
+        );
+    }
+    #[test]
     fn computing_the_normal_on_a_transformed_shape() {
         let mut s = new_test_shape();
         let m = Transform::scaling(1.0, 0.5, 1.0) * Transform::rotation_z(PI / 5.0);
@@ -310,6 +1068,34 @@ mod tests {
     }
     #[test]
     fn a_helper_for_producing_a_sphere_with_a_glassy_material() {
-        todo!("Implement spheres")
+        let s = glass_sphere();
+        assert_eq!(s.get_transform(), Matrix::new_identity());
+        assert_eq!(s.get_material().transparency, 1.0);
+        assert_eq!(s.get_material().refractive_index, 1.5);
+    }
+    #[test]
+    fn metadata_round_trips_and_does_not_affect_rendering() {
+        let mut s = new_sphere();
+        assert_eq!(s.get_metadata(), None);
+        s.set_metadata(Some(String::from("left marble")));
+        assert_eq!(s.get_metadata(), Some("left marble"));
+
+        let plain = World::new_default_world();
+        let mut tagged = World::new_default_world();
+        tagged.objects[0].set_metadata(Some(String::from("outer sphere")));
+
+        let mut c = Camera::new(11, 11, PI / 2.0);
+        c.set_transform(Transform::view_transform(
+            &Tuple::new_point(0.0, 0.0, -5.0),
+            &Tuple::new_point(0.0, 0.0, 0.0),
+            &Tuple::new_vector(0.0, 1.0, 0.0),
+        ));
+        let plain_image = c.render(&plain, 1);
+        let tagged_image = c.render(&tagged, 1);
+        for y in 0..11 {
+            for x in 0..11 {
+                assert_eq!(plain_image.pixel_at(x, y), tagged_image.pixel_at(x, y));
+            }
+        }
     }
 }
@@ -1,6 +1,7 @@
 #![allow(unused)]
 use super::*;
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::Intersection,
     materials::Material,
     matrices::Matrix,
@@ -10,10 +11,13 @@ use crate::ray_tracer::{
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cone {
     base: BaseShape,
     parent: Option<BaseShape>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub(super) minimum: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub(super) maximum: f64,
     pub(super) closed: bool,
 }
@@ -25,6 +29,11 @@ impl Cone {
                 position: Some(Point::new_point(0.0, 0.0, 0.0)),
                 transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
                 material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
             },
             parent: None,
             minimum: f64::NEG_INFINITY,
@@ -40,19 +49,20 @@ impl Cone {
         (x.powi(2) + z.powi(2)) <= y_plane.powi(2)
     }
 
-    fn intersect_caps(&self, ray: &Ray, xs: &mut Vec<Intersection>) {
+    /// Adds the times the ray hits the caps, if the shape has them, to `ts`.
+    fn intersect_caps(&self, ray: &Ray, ts: &mut Vec<f64>) {
         if !self.closed || is_float_equal(&ray.direction.y, 0.0) {
             return;
         }
 
         let t = (self.minimum - ray.origin.y) / ray.direction.y;
         if Cone::check_cap(self.minimum, ray, &t) {
-            xs.push(Intersection::new(t, Object::Cone(self.clone())));
+            ts.push(t);
         }
 
         let t = (self.maximum - ray.origin.y) / ray.direction.y;
         if Cone::check_cap(self.maximum, ray, &t) {
-            xs.push(Intersection::new(t, Object::Cone(self.clone())));
+            ts.push(t);
         }
     }
 }
@@ -71,33 +81,78 @@ impl Shapes for Cone {
         self.base.position.unwrap()
     }
     fn set_transform(&mut self, transform: &Matrix) {
-        let mut trans = *transform;
-        trans.calculate_inverse().unwrap();
-        self.base.transform = Some(trans);
+        self.base.set_transform(transform);
     }
     fn get_transform(&self) -> Matrix {
         self.base.transform.unwrap()
     }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
     fn set_material(&mut self, material: &Material) {
-        self.base.material = Some(*material);
+        self.base.material = Some(material.clone());
     }
     fn get_material(&self) -> Material {
-        self.base.material.unwrap()
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
     }
     fn set_parent(&mut self, parent: &BaseShape) {
-        self.parent = Some(*parent);
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
     }
-    fn get_parent(&self) -> BaseShape {
-        self.parent.unwrap()
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
+    }
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        let limit = self.minimum.abs().max(self.maximum.abs());
+        Bounds::new(
+            Point::new_point(-limit, self.minimum, -limit),
+            Point::new_point(limit, self.maximum, limit),
+        )
     }
     fn local_normal_at(&self, point: Point) -> Vector {
         // Compute the square of the distance from the y-axis
         let dist = point.x.powi(2) + point.z.powi(2);
 
-        if dist < 1.0 && point.y >= (self.maximum - EPSILON) {
+        // The caps of a cone have the same radius as the y value they're placed at
+        if dist < self.maximum.powi(2) && point.y >= (self.maximum - EPSILON) {
             // Check top cap
             Vector::new_vector(0.0, 1.0, 0.0)
-        } else if dist < 1.0 && point.y <= (self.minimum + EPSILON) {
+        } else if dist < self.minimum.powi(2) && point.y <= (self.minimum + EPSILON) {
             // Check bottom cap
             Vector::new_vector(0.0, -1.0, 0.0)
         } else {
@@ -119,7 +174,7 @@ impl Shapes for Cone {
 
         // Forward declare. Different calculation if a single or double intersection.
 
-        let mut xs = vec![];
+        let mut ts = vec![];
 
         if is_float_equal(&a, 0.0) {
             if is_float_equal(&b, 0.0) {
@@ -129,7 +184,7 @@ impl Shapes for Cone {
                 // Parallel to one of the halves.
                 // One intersection.
                 let t = -c / (2.0 * b);
-                xs.push(Intersection::new(t, Object::Cone(self.clone())));
+                ts.push(t);
             }
         } else {
             let disc = b.powi(2) - 4.0 * a * c;
@@ -143,18 +198,18 @@ impl Shapes for Cone {
 
             let y0 = local_ray.origin.y + t0 * local_ray.direction.y;
             if self.minimum < y0 && y0 < self.maximum {
-                xs.push(Intersection::new(t0, Object::Cone(self.clone())));
+                ts.push(t0);
             }
 
             let y1 = local_ray.origin.y + t1 * local_ray.direction.y;
             if self.minimum < y1 && y1 < self.maximum {
-                xs.push(Intersection::new(t1, Object::Cone(self.clone())));
+                ts.push(t1);
             }
         }
 
-        self.intersect_caps(&local_ray, &mut xs);
+        self.intersect_caps(&local_ray, &mut ts);
 
-        xs
+        Intersection::new_all(&ts, || Object::Cone(self.clone()))
     }
 }
 
@@ -387,6 +442,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn the_normal_vector_on_a_wide_cone_cap() {
+        let mut cone = Cone::new();
+        cone.minimum = -3.0;
+        cone.maximum = 2.0;
+        cone.closed = true;
+
+        let n = cone.local_normal_at(Point::new_point(1.5, 2.0, 0.0));
+        assert_eq!(n, Vector::new_vector(0.0, 1.0, 0.0));
+        let n = cone.local_normal_at(Point::new_point(0.0, -3.0, -2.5));
+        assert_eq!(n, Vector::new_vector(0.0, -1.0, 0.0));
+    }
+
     #[test]
     fn a_ray_misses_a_restricted_cone() {
         let mut cone = Cone::new();
@@ -1,6 +1,7 @@
 #![allow(unused)]
 use super::*;
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::Intersection,
     materials::Material,
     matrices::Matrix,
@@ -10,6 +11,7 @@ use crate::ray_tracer::{
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cube {
     base: BaseShape,
     parent: Option<BaseShape>,
@@ -22,6 +24,11 @@ impl Cube {
                 position: Some(Point::new_point(0.0, 0.0, 0.0)),
                 transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
                 material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
             },
             parent: None,
         }
@@ -42,85 +49,99 @@ impl Shapes for Cube {
         self.base.position.unwrap()
     }
     fn set_transform(&mut self, transform: &Matrix) {
-        let mut trans = *transform;
-        trans.calculate_inverse().unwrap();
-        self.base.transform = Some(trans);
+        self.base.set_transform(transform);
     }
     fn get_transform(&self) -> Matrix {
         self.base.transform.unwrap()
     }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
     fn set_material(&mut self, material: &Material) {
-        self.base.material = Some(*material);
+        self.base.material = Some(material.clone());
     }
     fn get_material(&self) -> Material {
-        self.base.material.unwrap()
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
     }
     fn set_parent(&mut self, parent: &BaseShape) {
-        self.parent = Some(*parent);
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
     }
-    fn get_parent(&self) -> BaseShape {
-        self.parent.unwrap()
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
     }
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
+    }
+    /// The normal points along the axis of the point's largest component.
+    /// On edges and corners several components are equally large, and the tie goes to x, then
+    /// y, then z. Components within `EPSILON` of the largest count as ties, so rounding in the
+    /// hit point can't flip the normal between two faces.
     fn local_normal_at(&self, point: Point) -> Vector {
-        let maxc = [point.x.abs(), point.y.abs(), point.z.abs()]
-            .iter()
-            .max_by(|a, b| a.total_cmp(b))
-            .unwrap()
-            .to_owned();
+        let (x, y, z) = (point.x.abs(), point.y.abs(), point.z.abs());
+        let maxc = x.max(y).max(z);
 
-        if is_float_equal(&maxc, point.x.abs()) {
-            Vector::new_vector(point.x, 0.0, 0.0)
-        } else if is_float_equal(&maxc, point.y.abs()) {
-            Vector::new_vector(0.0, point.y, 0.0)
-        } else if is_float_equal(&maxc, point.z.abs()) {
-            Vector::new_vector(0.0, 0.0, point.z)
+        if is_float_equal(&x, maxc) {
+            Vector::new_vector(point.x.signum(), 0.0, 0.0)
+        } else if is_float_equal(&y, maxc) {
+            Vector::new_vector(0.0, point.y.signum(), 0.0)
         } else {
-            panic!("Intersection did not match any axis")
+            Vector::new_vector(0.0, 0.0, point.z.signum())
         }
     }
     fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
-        let (xtmin, xtmax): (f64, f64) = check_axis(local_ray.origin.x, local_ray.direction.x);
-        let (ytmin, ytmax): (f64, f64) = check_axis(local_ray.origin.y, local_ray.direction.y);
-        let (ztmin, ztmax): (f64, f64) = check_axis(local_ray.origin.z, local_ray.direction.z);
-
-        let tmin = [xtmin, ytmin, ztmin]
-            .iter()
-            .max_by(|a, b| a.total_cmp(b))
-            .unwrap()
-            .to_owned();
-        let tmax = [xtmax, ytmax, ztmax]
-            .iter()
-            .min_by(|a, b| a.total_cmp(b))
-            .unwrap()
-            .to_owned();
+        let (tmin, tmax) = Bounds::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
+        .slab_times(&local_ray);
 
         if tmin > tmax {
             Vec::new()
         } else {
-            vec![
-                Intersection::new(tmin, Object::Cube(self.clone())),
-                Intersection::new(tmax, Object::Cube(self.clone())),
-            ]
+            Intersection::new_all(&[tmin, tmax], || Object::Cube(self.clone()))
         }
     }
 }
 
-/// Check which plane of a given axis is hit first and returns the time difference between the
-/// intersections.
-fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
-    let tmin_numerator = -1.0 - origin;
-    let tmax_numerator = 1.0 - origin;
-
-    let mut tmin = tmin_numerator / direction;
-    let mut tmax = tmax_numerator / direction;
-
-    if tmin > tmax {
-        (tmin, tmax) = (tmax, tmin);
-    }
-
-    (tmin, tmax)
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,4 +305,53 @@ mod tests {
             assert_eq!(normal, ex.1);
         }
     }
+
+    #[test]
+    fn the_normal_on_an_edge_of_a_cube_prefers_x_then_y() {
+        let c = Cube::new();
+        let examples = [
+            (
+                Point::new_point(1.0, 1.0, 0.0),
+                Vector::new_vector(1.0, 0.0, 0.0),
+            ),
+            (
+                Point::new_point(-1.0, 1.0, 0.3),
+                Vector::new_vector(-1.0, 0.0, 0.0),
+            ),
+            (
+                Point::new_point(0.2, -1.0, 1.0),
+                Vector::new_vector(0.0, -1.0, 0.0),
+            ),
+            (
+                Point::new_point(1.0, 0.0, -1.0),
+                Vector::new_vector(1.0, 0.0, 0.0),
+            ),
+            (
+                Point::new_point(-1.0, 1.0, -1.0),
+                Vector::new_vector(-1.0, 0.0, 0.0),
+            ),
+        ];
+        for (point, normal) in examples {
+            assert_eq!(c.local_normal_at(point), normal);
+        }
+    }
+
+    #[test]
+    fn rounding_at_an_edge_does_not_flip_the_normal() {
+        let c = Cube::new();
+        let lit_corner = [
+            Point::new_point(1.0, 1.0, 0.0),
+            Point::new_point(1.0 - 1e-9, 1.0, 0.0),
+            Point::new_point(1.0, 1.0 + 1e-9, 0.0),
+            Point::new_point(1.0 + 1e-12, 1.0 - 1e-12, 1e-12),
+        ];
+        for point in lit_corner {
+            assert_eq!(c.local_normal_at(point), Vector::new_vector(1.0, 0.0, 0.0));
+        }
+        // Just inside a face the normal is still a unit vector
+        assert_eq!(
+            c.local_normal_at(Point::new_point(0.3, 0.99999, -0.2)),
+            Vector::new_vector(0.0, 1.0, 0.0)
+        );
+    }
 }
@@ -1,6 +1,7 @@
 #![allow(unused)]
 use super::*;
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::Intersection,
     materials::Material,
     matrices::Matrix,
@@ -10,12 +11,17 @@ use crate::ray_tracer::{
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Cylinder {
     base: BaseShape,
     parent: Option<BaseShape>,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub(super) minimum: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub(super) maximum: f64,
     pub(super) closed: bool,
+    /// Distance from the y-axis to the sides. Caps are cut to the same radius.
+    pub(super) radius: f64,
 }
 
 impl Cylinder {
@@ -25,34 +31,41 @@ impl Cylinder {
                 position: Some(Point::new_point(0.0, 0.0, 0.0)),
                 transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
                 material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
             },
             parent: None,
             minimum: f64::NEG_INFINITY,
             maximum: f64::INFINITY,
             closed: false,
+            radius: 1.0,
         }
     }
 
-    fn check_cap(ray: &Ray, t: &f64) -> bool {
+    fn check_cap(&self, ray: &Ray, t: &f64) -> bool {
         let x = ray.origin.x + t * ray.direction.x;
         let z = ray.origin.z + t * ray.direction.z;
 
-        (x.powi(2) + z.powi(2)) <= 1.0
+        (x.powi(2) + z.powi(2)) <= self.radius.powi(2)
     }
 
-    fn intersect_caps(&self, ray: &Ray, xs: &mut Vec<Intersection>) {
+    /// Adds the times the ray hits the caps, if the shape has them, to `ts`.
+    fn intersect_caps(&self, ray: &Ray, ts: &mut Vec<f64>) {
         if !self.closed || is_float_equal(&ray.direction.y, 0.0) {
             return;
         }
 
         let t = (self.minimum - ray.origin.y) / ray.direction.y;
-        if Cylinder::check_cap(ray, &t) {
-            xs.push(Intersection::new(t, Object::Cylinder(self.clone())));
+        if self.check_cap(ray, &t) {
+            ts.push(t);
         }
 
         let t = (self.maximum - ray.origin.y) / ray.direction.y;
-        if Cylinder::check_cap(ray, &t) {
-            xs.push(Intersection::new(t, Object::Cylinder(self.clone())));
+        if self.check_cap(ray, &t) {
+            ts.push(t);
         }
     }
 }
@@ -71,32 +84,76 @@ impl Shapes for Cylinder {
         self.base.position.unwrap()
     }
     fn set_transform(&mut self, transform: &Matrix) {
-        let mut trans = *transform;
-        trans.calculate_inverse().unwrap();
-        self.base.transform = Some(trans);
+        self.base.set_transform(transform);
     }
     fn get_transform(&self) -> Matrix {
         self.base.transform.unwrap()
     }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
     fn set_material(&mut self, material: &Material) {
-        self.base.material = Some(*material);
+        self.base.material = Some(material.clone());
     }
     fn get_material(&self) -> Material {
-        self.base.material.unwrap()
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
     }
     fn set_parent(&mut self, parent: &BaseShape) {
-        self.parent = Some(*parent);
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
     }
-    fn get_parent(&self) -> BaseShape {
-        self.parent.unwrap()
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new_point(-self.radius, self.minimum, -self.radius),
+            Point::new_point(self.radius, self.maximum, self.radius),
+        )
     }
     fn local_normal_at(&self, point: Point) -> Vector {
         // Compute the square of the distance from the y-axis
         let dist = point.x.powi(2) + point.z.powi(2);
+        let radius_squared = self.radius.powi(2);
 
-        if dist < 1.0 && point.y >= (self.maximum - EPSILON) {
+        if dist < radius_squared && point.y >= (self.maximum - EPSILON) {
             Vector::new_vector(0.0, 1.0, 0.0)
-        } else if dist < 1.0 && point.y <= (self.minimum + EPSILON) {
+        } else if dist < radius_squared && point.y <= (self.minimum + EPSILON) {
             Vector::new_vector(0.0, -1.0, 0.0)
         } else {
             Vector::new_vector(point.x, 0.0, point.z)
@@ -105,14 +162,15 @@ impl Shapes for Cylinder {
     fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
         let a = local_ray.direction.x.powi(2) + local_ray.direction.z.powi(2);
         if is_float_equal(&a, 0.0) {
-            let mut xs = vec![];
-            self.intersect_caps(&local_ray, &mut xs);
-            return xs;
+            let mut ts = vec![];
+            self.intersect_caps(&local_ray, &mut ts);
+            ts.sort_unstable_by(f64::total_cmp);
+            return Intersection::new_all(&ts, || Object::Cylinder(self.clone()));
         }
 
         let b = 2.0 * local_ray.origin.x * local_ray.direction.x
             + 2.0 * local_ray.origin.z * local_ray.direction.z;
-        let c = local_ray.origin.x.powi(2) + local_ray.origin.z.powi(2) - 1.0;
+        let c = local_ray.origin.x.powi(2) + local_ray.origin.z.powi(2) - self.radius.powi(2);
         let disc = b.powi(2) - 4.0 * a * c;
         if disc < 0.0 {
             // Ray doesn't intersect the cylinder
@@ -122,21 +180,23 @@ impl Shapes for Cylinder {
         let t0 = (-b - disc.sqrt()) / (2.0 * a);
         let t1 = (-b + disc.sqrt()) / (2.0 * a);
 
-        let mut xs = vec![];
+        let mut ts = vec![];
 
         let y0 = local_ray.origin.y + t0 * local_ray.direction.y;
         if self.minimum < y0 && y0 < self.maximum {
-            xs.push(Intersection::new(t0, Object::Cylinder(self.clone())));
+            ts.push(t0);
         }
 
         let y1 = local_ray.origin.y + t1 * local_ray.direction.y;
         if self.minimum < y1 && y1 < self.maximum {
-            xs.push(Intersection::new(t1, Object::Cylinder(self.clone())));
+            ts.push(t1);
         }
 
-        self.intersect_caps(&local_ray, &mut xs);
+        self.intersect_caps(&local_ray, &mut ts);
 
-        xs
+        // The caps are checked after the sides, so they may need to be moved to the front
+        ts.sort_unstable_by(f64::total_cmp);
+        Intersection::new_all(&ts, || Object::Cylinder(self.clone()))
     }
 }
 
@@ -204,6 +264,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn a_ray_hits_a_cylinder_with_radius_2() {
+        let mut cyl = Cylinder::new();
+        cyl.radius = 2.0;
+        let examples = [
+            (
+                Point::new_point(2.0, 0.0, -5.0),
+                Vector::new_vector(0.0, 0.0, 1.0),
+                5.0,
+                5.0,
+            ),
+            (
+                Point::new_point(0.0, 0.0, -5.0),
+                Vector::new_vector(0.0, 0.0, 1.0),
+                3.0,
+                7.0,
+            ),
+            (
+                Point::new_point(-5.0, 0.0, 1.0),
+                Vector::new_vector(1.0, 0.0, 0.0),
+                5.0 - f64::sqrt(3.0),
+                5.0 + f64::sqrt(3.0),
+            ),
+        ];
+
+        for (origin, direction, t0, t1) in examples {
+            let xs = cyl.local_intersect(Ray::new(origin, direction));
+            assert_eq!(2, xs.len());
+            assert!(is_float_equal(&xs[0].get_time(), t0));
+            assert!(is_float_equal(&xs[1].get_time(), t1));
+        }
+        let outside = Ray::new(
+            Point::new_point(2.5, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        assert!(cyl.local_intersect(outside).is_empty());
+    }
+
+    #[test]
+    fn the_caps_of_a_cylinder_with_radius_2() {
+        let mut cyl = Cylinder::new();
+        cyl.radius = 2.0;
+        cyl.minimum = 0.0;
+        cyl.maximum = 2.0;
+        cyl.closed = true;
+
+        let r = Ray::new(
+            Point::new_point(1.5, 3.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        let xs = cyl.local_intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert!(is_float_equal(&xs[0].get_time(), 1.0));
+        assert!(is_float_equal(&xs[1].get_time(), 3.0));
+        assert_eq!(
+            cyl.local_normal_at(Point::new_point(1.5, 2.0, 0.0)),
+            Vector::new_vector(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            cyl.bounds(),
+            Bounds::new(
+                Point::new_point(-2.0, 0.0, -2.0),
+                Point::new_point(2.0, 2.0, 2.0)
+            )
+        );
+    }
+
     #[test]
     fn normal_vector_on_a_cylinder() {
         let examples = [
@@ -287,6 +414,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn intersections_with_the_sides_and_caps_are_sorted() {
+        let mut cyl = Cylinder::new();
+        cyl.minimum = 1.0;
+        cyl.maximum = 2.0;
+        cyl.closed = true;
+
+        // Enters through the top cap and leaves through the side
+        let r = Ray::new(
+            Point::new_point(0.0, 2.5, 0.0),
+            Vector::new_vector(0.0, -1.0, 1.0),
+        );
+        let xs = cyl.local_intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert!(is_float_equal(&xs[0].get_time(), 0.5));
+        assert!(is_float_equal(&xs[1].get_time(), 1.0));
+    }
+
     #[test]
     fn the_default_closed_value_for_a_cylinder() {
         let cyl = Cylinder::new();
@@ -0,0 +1,211 @@
+#![allow(unused)]
+use super::*;
+use crate::ray_tracer::{
+    bounds::Bounds,
+    intersections::Intersection,
+    materials::Material,
+    matrices::Matrix,
+    rays::Ray,
+    tuples::{Point, Tuple, Vector},
+    utils::EPSILON,
+};
+
+/// A flat ring in the xz-plane, between `inner` and `outer` distance from the y-axis.
+/// With an inner radius of 0 it's a solid disk.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Disk {
+    base: BaseShape,
+    parent: Option<BaseShape>,
+    pub(super) inner: f64,
+    pub(super) outer: f64,
+}
+
+impl Disk {
+    pub fn new() -> Self {
+        Self {
+            base: BaseShape {
+                position: Some(Point::new_point(0.0, 0.0, 0.0)),
+                transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
+                material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
+            },
+            parent: None,
+            inner: 0.0,
+            outer: 1.0,
+        }
+    }
+}
+
+impl Default for Disk {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shapes for Disk {
+    fn set_position(&mut self, pos: &Point) {
+        self.base.position = Some(*pos);
+    }
+    fn get_position(&self) -> Point {
+        self.base.position.unwrap()
+    }
+    fn set_transform(&mut self, transform: &Matrix) {
+        self.base.set_transform(transform);
+    }
+    fn get_transform(&self) -> Matrix {
+        self.base.transform.unwrap()
+    }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
+    fn set_material(&mut self, material: &Material) {
+        self.base.material = Some(material.clone());
+    }
+    fn get_material(&self) -> Material {
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
+    }
+    fn set_parent(&mut self, parent: &BaseShape) {
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
+    }
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new_point(-self.outer, 0.0, -self.outer),
+            Point::new_point(self.outer, 0.0, self.outer),
+        )
+    }
+    fn local_normal_at(&self, point: Point) -> Vector {
+        Vector::new_vector(0.0, 1.0, 0.0)
+    }
+    fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
+        if f64::abs(local_ray.direction.y) < EPSILON {
+            return Vec::new();
+        }
+
+        // Where the ray crosses the xz-plane, kept only if it's on the ring
+        let t = -local_ray.origin.y / local_ray.direction.y;
+        let x = local_ray.origin.x + t * local_ray.direction.x;
+        let z = local_ray.origin.z + t * local_ray.direction.z;
+        let dist = x.powi(2) + z.powi(2);
+        if dist < self.inner.powi(2) || dist > self.outer.powi(2) {
+            return Vec::new();
+        }
+
+        vec![Intersection::new(t, Object::Disk(self.clone()))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn washer() -> Disk {
+        let mut d = Disk::new();
+        d.inner = 0.5;
+        d.outer = 2.0;
+        d
+    }
+
+    #[test]
+    fn the_normal_of_a_disk_is_constant_everywhere() {
+        let d = washer();
+        let n1 = d.local_normal_at(Point::new_point(1.0, 0.0, 0.0));
+        let n2 = d.local_normal_at(Point::new_point(0.0, 0.0, -1.5));
+        assert_eq!(n1, Vector::new_vector(0.0, 1.0, 0.0));
+        assert_eq!(n2, Vector::new_vector(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn a_ray_hits_the_ring_of_a_disk() {
+        let d = washer();
+        let examples = [
+            Point::new_point(1.0, 1.0, 0.0),
+            Point::new_point(0.0, 1.0, -1.9),
+            Point::new_point(0.5, 1.0, 0.0),
+            Point::new_point(2.0, 1.0, 0.0),
+        ];
+        for origin in examples {
+            let r = Ray::new(origin, Vector::new_vector(0.0, -1.0, 0.0));
+            let xs = d.local_intersect(r);
+            assert_eq!(xs.len(), 1);
+            assert_eq!(xs[0].get_time(), 1.0);
+        }
+    }
+
+    #[test]
+    fn a_ray_misses_a_disk_through_its_hole_or_outside_it() {
+        let d = washer();
+        let examples = [
+            (
+                Point::new_point(0.0, 1.0, 0.0),
+                Vector::new_vector(0.0, -1.0, 0.0),
+            ),
+            (
+                Point::new_point(0.3, -1.0, 0.3),
+                Vector::new_vector(0.0, 1.0, 0.0),
+            ),
+            (
+                Point::new_point(2.5, 1.0, 0.0),
+                Vector::new_vector(0.0, -1.0, 0.0),
+            ),
+            (
+                Point::new_point(1.0, 0.0, -5.0),
+                Vector::new_vector(0.0, 0.0, 1.0),
+            ),
+        ];
+        for (origin, direction) in examples {
+            let xs = d.local_intersect(Ray::new(origin, direction));
+            assert!(xs.is_empty());
+        }
+    }
+
+    #[test]
+    fn the_bounds_of_a_disk() {
+        let b = washer().bounds();
+        assert_eq!(b.get_min(), Point::new_point(-2.0, 0.0, -2.0));
+        assert_eq!(b.get_max(), Point::new_point(2.0, 0.0, 2.0));
+    }
+}
@@ -0,0 +1,635 @@
+use super::*;
+use crate::ray_tracer::{
+    bounds::Bounds,
+    intersections::Intersection,
+    materials::Material,
+    matrices::Matrix,
+    rays::Ray,
+    tuples::{Point, Vector},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Group {
+    base: BaseShape,
+    parent: Option<BaseShape>,
+    children: Vec<Object>,
+    /// Bounding box of all children, in the group's object space.
+    bounds: Bounds,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self {
+            base: BaseShape {
+                position: Some(Point::new_point(0.0, 0.0, 0.0)),
+                transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
+                material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
+            },
+            parent: None,
+            children: Vec::new(),
+            bounds: Bounds::empty(),
+        }
+    }
+
+    /// Adds a child to the group, giving it new ids so that it is a shape of its own even if it
+    /// is a clone of another child.
+    /// The child is told about the group's full transformation so that it can convert between
+    /// world space and its own object space without walking up through its parents.
+    pub fn add_child(&mut self, mut child: Object) {
+        child.renew_id();
+        child.set_parent(&self.world_base());
+        self.bounds.merge(&child.parent_space_bounds());
+        self.children.push(child);
+    }
+
+    pub fn get_children(&self) -> &[Object] {
+        &self.children
+    }
+    /// The number of direct children, not counting the contents of subgroups.
+    pub fn len(&self) -> usize {
+        self.children.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+    pub fn child(&self, index: usize) -> Option<&Object> {
+        self.children.get(index)
+    }
+
+    /// Applies `transform` on top of the group's current transformation, moving the whole
+    /// group with everything in it.
+    pub fn apply_transform(&mut self, transform: &Matrix) {
+        let combined = *transform * self.get_transform();
+        self.set_transform(&combined);
+    }
+
+    /// Sets the material of the group and of everything in it.
+    pub(super) fn override_material(&mut self, material: &Material) {
+        self.base.material = Some(material.clone());
+        for child in &mut self.children {
+            child.override_material(material);
+        }
+    }
+
+    /// Moves every child to where it is at `time`, see [`Object::set_motion`].
+    pub(super) fn move_to_time(&mut self, time: f64) {
+        self.bounds = Bounds::empty();
+        for child in &mut self.children {
+            child.move_to_time(time);
+            self.bounds.merge(&child.parent_space_bounds());
+        }
+    }
+
+    /// Builds a bounding volume hierarchy out of the group.
+    /// Children are recursively sorted into sub-groups by splitting the bounding box in two along
+    /// its longest axis, until a group has fewer than `threshold` children.
+    /// Children that don't fit entirely in either half stay where they are.
+    pub fn divide(&mut self, threshold: usize) {
+        if threshold <= self.children.len() {
+            let (left, right) = self.partition_children();
+            if !left.is_empty() {
+                self.make_subgroup(left);
+            }
+            if !right.is_empty() {
+                self.make_subgroup(right);
+            }
+        }
+
+        for child in &mut self.children {
+            if let Object::Group(g) = child {
+                g.divide(threshold);
+            }
+        }
+    }
+
+    /// Removes the children that fit entirely in either half of the bounding box, and returns
+    /// them as (left, right).
+    fn partition_children(&mut self) -> (Vec<Object>, Vec<Object>) {
+        let (left_bounds, right_bounds) = self.bounds.split();
+        let mut left = Vec::new();
+        let mut right = Vec::new();
+        let mut remaining = Vec::new();
+
+        for child in self.children.drain(..) {
+            let child_bounds = child.parent_space_bounds();
+            if left_bounds.contains_bounds(&child_bounds) {
+                left.push(child);
+            } else if right_bounds.contains_bounds(&child_bounds) {
+                right.push(child);
+            } else {
+                remaining.push(child);
+            }
+        }
+        self.children = remaining;
+
+        (left, right)
+    }
+
+    fn make_subgroup(&mut self, children: Vec<Object>) {
+        let mut subgroup = Group::new();
+        for child in children {
+            subgroup.add_child(child);
+        }
+        self.add_child(Object::Group(subgroup));
+    }
+
+    /// Returns a copy of the base shape where the transform is the combined transformation of
+    /// the group and all of its parents.
+    fn world_base(&self) -> BaseShape {
+        let mut base = self.base.clone();
+        if let Some(parent) = &self.parent {
+            base.transform = Some(
+                (parent.transform.unwrap() * self.base.transform.unwrap())
+                    .calculate_inverse()
+                    .unwrap(),
+            );
+        }
+        base
+    }
+
+    /// Pushes the group's combined transformation down to all of its children.
+    fn update_children(&mut self) {
+        let base = self.world_base();
+        for child in &mut self.children {
+            child.set_parent(&base);
+        }
+    }
+}
+
+impl Default for Group {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shapes for Group {
+    fn set_position(&mut self, pos: &Point) {
+        self.base.position = Some(*pos);
+    }
+    fn get_position(&self) -> Point {
+        self.base.position.unwrap()
+    }
+    fn set_transform(&mut self, transform: &Matrix) {
+        self.base.set_transform(transform);
+        self.update_children();
+    }
+    fn get_transform(&self) -> Matrix {
+        self.base.transform.unwrap()
+    }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
+    fn set_material(&mut self, material: &Material) {
+        self.base.material = Some(material.clone());
+    }
+    fn get_material(&self) -> Material {
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
+    }
+    fn set_parent(&mut self, parent: &BaseShape) {
+        self.parent = Some(parent.clone());
+        self.update_children();
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+        self.update_children();
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+        for child in &mut self.children {
+            child.renew_id();
+        }
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
+        for child in &mut self.children {
+            child.set_casts_shadow(casts_shadow);
+        }
+    }
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+    #[allow(unused_variables)]
+    fn local_normal_at(&self, point: Point) -> Vector {
+        panic!("Groups don't have normals, the normal should be taken from the child that was hit")
+    }
+    fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
+        if !local_ray.intersects_bounds(&self.bounds) {
+            return Vec::new();
+        }
+
+        let mut xs: Vec<Intersection> = self
+            .children
+            .iter()
+            .flat_map(|child| local_ray.intersect(child))
+            .collect();
+        xs.sort_unstable_by(|a, b| a.get_time().total_cmp(&b.get_time()));
+        xs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::PI;
+
+    use super::*;
+    use crate::ray_tracer::{transformations::Transform, utils::is_float_equal};
+
+    #[test]
+    fn creating_a_new_group() {
+        let g = Group::new();
+        assert_eq!(g.get_transform(), Matrix::new_identity());
+        assert!(g.get_children().is_empty());
+    }
+
+    #[test]
+    fn adding_a_child_to_a_group() {
+        let mut g = Group::new();
+        let s = new_test_shape();
+        g.add_child(s.clone());
+        assert_eq!(g.get_children().len(), 1);
+        assert_eq!(g.get_children()[0].get_parent(), Some(&g.base));
+    }
+
+    #[test]
+    fn querying_the_children_of_a_group() {
+        let mut g = Group::new();
+        assert!(g.is_empty());
+        assert!(g.child(0).is_none());
+
+        let s = new_sphere();
+        g.add_child(s.clone());
+        g.add_child(new_cube());
+        assert_eq!(g.len(), 2);
+        assert!(!g.is_empty());
+        assert!(matches!(g.child(0), Some(Object::Sphere(_))));
+        assert_ne!(g.child(0).unwrap().get_id(), s.get_id());
+        assert!(g.child(2).is_none());
+    }
+
+    #[test]
+    fn moving_a_built_group_moves_its_children() {
+        let mut g = Group::new();
+        g.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut s = new_sphere();
+        s.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        g.add_child(s);
+        let before = g
+            .child(0)
+            .unwrap()
+            .world_to_object(&Point::new_point(10.0, 0.0, 0.0));
+        assert_eq!(before, Point::new_point(0.0, 0.0, 0.0));
+
+        g.apply_transform(&Transform::translate(0.0, 4.0, 0.0));
+        assert_eq!(
+            g.get_transform(),
+            Transform::translate(0.0, 4.0, 0.0) * Transform::scaling(2.0, 2.0, 2.0)
+        );
+        let after = g
+            .child(0)
+            .unwrap()
+            .world_to_object(&Point::new_point(10.0, 4.0, 0.0));
+        assert_eq!(after, Point::new_point(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn overriding_the_material_of_a_group_reaches_nested_children() {
+        let mut inner = Group::new();
+        inner.add_child(new_sphere());
+        let mut g = Group::new();
+        g.add_child(new_group(inner));
+        g.add_child(new_sphere());
+        let mut m = Material::new();
+        m.ambient = 1.0;
+
+        let mut g = new_group(g);
+        g.override_material(&m);
+        let g = match g {
+            Object::Group(g) => g,
+            _ => panic!("Expected a group"),
+        };
+        assert_eq!(g.get_material(), m);
+        assert_eq!(g.get_children()[1].get_material(), m);
+        match &g.get_children()[0] {
+            Object::Group(inner) => assert_eq!(inner.get_children()[0].get_material(), m),
+            _ => panic!("Expected a group"),
+        }
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_an_empty_group() {
+        let g = Group::new();
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = g.local_intersect(r);
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn intersecting_a_ray_with_a_nonempty_group() {
+        let mut g = Group::new();
+        let s1 = new_sphere();
+        let mut s2 = new_sphere();
+        s2.set_transform(&Transform::translate(0.0, 0.0, -3.0));
+        let mut s3 = new_sphere();
+        s3.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        g.add_child(s1);
+        g.add_child(s2);
+        g.add_child(s3);
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = g.local_intersect(r);
+        assert_eq!(xs.len(), 4);
+        assert_eq!(xs[0].get_object(), &g.get_children()[1]);
+        assert_eq!(xs[1].get_object(), &g.get_children()[1]);
+        assert_eq!(xs[2].get_object(), &g.get_children()[0]);
+        assert_eq!(xs[3].get_object(), &g.get_children()[0]);
+    }
+
+    #[test]
+    fn intersecting_a_transformed_group() {
+        let mut g = Group::new();
+        g.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut s = new_sphere();
+        s.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        g.add_child(s);
+        let g = new_group(g);
+
+        let r = Ray::new(
+            Point::new_point(10.0, 0.0, -10.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = r.intersect(&g);
+        assert_eq!(xs.len(), 2);
+    }
+
+    #[test]
+    fn converting_a_point_from_world_to_object_space() {
+        let mut g2 = Group::new();
+        g2.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        let mut s = new_sphere();
+        s.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        g2.add_child(s);
+
+        let mut g1 = Group::new();
+        g1.set_transform(&Transform::rotation_y(PI / 2.0));
+        g1.add_child(new_group(g2));
+
+        let s = match &g1.get_children()[0] {
+            Object::Group(g2) => g2.get_children()[0].clone(),
+            _ => panic!("Expected a group"),
+        };
+        let p = s.world_to_object(&Point::new_point(-2.0, 0.0, -10.0));
+        assert_eq!(p, Point::new_point(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn finding_the_normal_on_a_child_object() {
+        let mut g2 = Group::new();
+        g2.set_transform(&Transform::scaling(1.0, 2.0, 3.0));
+        let mut s = new_sphere();
+        s.set_transform(&Transform::translate(5.0, 0.0, 0.0));
+        g2.add_child(s);
+
+        let mut g1 = Group::new();
+        g1.set_transform(&Transform::rotation_y(PI / 2.0));
+        g1.add_child(new_group(g2));
+
+        let s = match &g1.get_children()[0] {
+            Object::Group(g2) => g2.get_children()[0].clone(),
+            _ => panic!("Expected a group"),
+        };
+        let n = s.normal_at(Point::new_point(1.7321, 1.1547, -5.5774));
+        assert_eq!(n, Vector::new_vector(0.2857, 0.42854, -0.85716));
+    }
+
+    #[test]
+    fn a_group_has_a_bounding_box_that_contains_its_children() {
+        let mut s = new_sphere();
+        s.set_transform(
+            &(Transform::translate(2.0, 5.0, -3.0) * Transform::scaling(2.0, 2.0, 2.0)),
+        );
+        let mut c = new_cylinder(Some((2.0, -2.0)));
+        c.set_transform(
+            &(Transform::translate(-4.0, -1.0, 4.0) * Transform::scaling(0.5, 1.0, 0.5)),
+        );
+        let mut g = Group::new();
+        g.add_child(s);
+        g.add_child(c);
+
+        let b = g.bounds();
+        assert_eq!(b.get_min(), Point::new_point(-4.5, -3.0, -5.0));
+        assert_eq!(b.get_max(), Point::new_point(4.0, 7.0, 4.5));
+    }
+
+    #[test]
+    fn intersecting_ray_and_group_doesnt_test_children_if_box_is_missed() {
+        let mut g = Group::new();
+        g.add_child(new_test_shape());
+        TestShape::reset_intersect_count();
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        let _xs = g.local_intersect(r);
+        assert_eq!(TestShape::get_intersect_count(), 0);
+    }
+
+    #[test]
+    fn partitioning_a_groups_children() {
+        let mut s1 = new_sphere();
+        s1.set_transform(&Transform::translate(-2.0, 0.0, 0.0));
+        let mut s2 = new_sphere();
+        s2.set_transform(&Transform::translate(2.0, 0.0, 0.0));
+        let s3 = new_sphere();
+        let mut g = Group::new();
+        g.add_child(s1.clone());
+        g.add_child(s2.clone());
+        g.add_child(s3.clone());
+
+        let (left, right) = g.partition_children();
+        assert_eq!(g.get_children().len(), 1);
+        assert_eq!(g.get_children()[0].get_transform(), s3.get_transform());
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].get_transform(), s1.get_transform());
+        assert_eq!(right.len(), 1);
+        assert_eq!(right[0].get_transform(), s2.get_transform());
+    }
+
+    #[test]
+    fn subdividing_a_group_partitions_its_children() {
+        let mut s1 = new_sphere();
+        s1.set_transform(&Transform::translate(-2.0, -2.0, 0.0));
+        let mut s2 = new_sphere();
+        s2.set_transform(&Transform::translate(-2.0, 2.0, 0.0));
+        let mut s3 = new_sphere();
+        s3.set_transform(&Transform::scaling(4.0, 4.0, 4.0));
+        let mut g = Group::new();
+        g.add_child(s1.clone());
+        g.add_child(s2.clone());
+        g.add_child(s3.clone());
+
+        g.divide(1);
+        assert_eq!(g.get_children().len(), 2);
+        assert_eq!(g.get_children()[0].get_transform(), s3.get_transform());
+        let subgroup = match &g.get_children()[1] {
+            Object::Group(subgroup) => subgroup,
+            _ => panic!("Expected a group"),
+        };
+        assert_eq!(subgroup.get_children().len(), 2);
+        for (child, expected) in subgroup.get_children().iter().zip([s1, s2]) {
+            match child {
+                Object::Group(g) => {
+                    assert_eq!(g.get_children().len(), 1);
+                    assert_eq!(
+                        g.get_children()[0].get_transform(),
+                        expected.get_transform()
+                    );
+                }
+                _ => panic!("Expected a group"),
+            }
+        }
+    }
+
+    #[test]
+    fn subdividing_a_group_with_too_few_children() {
+        let mut s1 = new_sphere();
+        s1.set_transform(&Transform::translate(-2.0, 0.0, 0.0));
+        let mut s2 = new_sphere();
+        s2.set_transform(&Transform::translate(2.0, 1.0, 0.0));
+        let mut s3 = new_sphere();
+        s3.set_transform(&Transform::translate(2.0, -1.0, 0.0));
+        let mut subgroup = Group::new();
+        subgroup.add_child(s1);
+        subgroup.add_child(s2);
+        subgroup.add_child(s3);
+        let s4 = new_sphere();
+        let mut g = Group::new();
+        g.add_child(new_group(subgroup));
+        g.add_child(s4);
+
+        g.divide(3);
+        assert_eq!(g.get_children().len(), 2);
+        let subgroup = match &g.get_children()[0] {
+            Object::Group(subgroup) => subgroup,
+            _ => panic!("Expected a group"),
+        };
+        assert_eq!(subgroup.get_children().len(), 2);
+    }
+
+    #[test]
+    fn dividing_a_group_reduces_the_number_of_intersection_tests() {
+        let mut g = Group::new();
+        for i in 0..16 {
+            let mut s = new_test_shape();
+            s.set_transform(&Transform::translate(i as f64 * 3.0, 0.0, 0.0));
+            g.add_child(s);
+        }
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+
+        TestShape::reset_intersect_count();
+        let _xs = g.local_intersect(r);
+        let undivided = TestShape::get_intersect_count();
+
+        g.divide(1);
+        TestShape::reset_intersect_count();
+        let _xs = g.local_intersect(r);
+        let divided = TestShape::get_intersect_count();
+
+        assert_eq!(undivided, 16);
+        assert!(divided < undivided);
+        assert_eq!(divided, 1);
+    }
+
+    #[test]
+    fn dividing_a_group_keeps_the_children_in_place() {
+        let mut g = Group::new();
+        g.set_transform(&Transform::scaling(2.0, 2.0, 2.0));
+        for i in 0..8 {
+            let mut s = new_sphere();
+            s.set_transform(&Transform::translate(i as f64 * 3.0, 0.0, 0.0));
+            g.add_child(s);
+        }
+        let r = Ray::new(
+            Point::new_point(18.0, 0.0, -10.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let g = new_group(g);
+        let before = r.intersect(&g);
+
+        let mut divided = match g {
+            Object::Group(g) => g,
+            _ => panic!("Expected a group"),
+        };
+        divided.divide(2);
+        let after = r.intersect(&new_group(divided));
+
+        assert_eq!(before.len(), 2);
+        assert_eq!(after.len(), 2);
+        for (b, a) in before.iter().zip(after.iter()) {
+            assert!(is_float_equal(&b.get_time(), a.get_time()));
+            let point = r.position(a.get_time());
+            assert_eq!(
+                b.get_object().normal_at(point),
+                a.get_object().normal_at(point)
+            );
+        }
+    }
+
+    #[test]
+    fn turning_off_shadows_on_a_group_turns_them_off_for_its_children() {
+        let mut g = Group::new();
+        g.add_child(new_sphere());
+        let mut g = new_group(g);
+        g.set_casts_shadow(false);
+        assert!(!g.casts_shadow());
+        match &g {
+            Object::Group(g) => assert!(!g.get_children()[0].casts_shadow()),
+            _ => unreachable!(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(unused)]
 use super::*;
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::Intersection,
     materials::Material,
     matrices::Matrix,
@@ -10,6 +11,7 @@ use crate::ray_tracer::{
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Plane {
     base: BaseShape,
     parent: Option<BaseShape>,
@@ -22,6 +24,11 @@ impl Plane {
                 position: Some(Point::new_point(0.0, 0.0, 0.0)),
                 transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
                 material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
             },
             parent: None,
         }
@@ -42,24 +49,67 @@ impl Shapes for Plane {
         self.base.position.unwrap()
     }
     fn set_transform(&mut self, transform: &Matrix) {
-        let mut trans = *transform;
-        trans.calculate_inverse().unwrap();
-        self.base.transform = Some(trans);
+        self.base.set_transform(transform);
     }
     fn get_transform(&self) -> Matrix {
         self.base.transform.unwrap()
     }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
     fn set_material(&mut self, material: &Material) {
-        self.base.material = Some(*material);
+        self.base.material = Some(material.clone());
     }
     fn get_material(&self) -> Material {
-        self.base.material.unwrap()
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
     }
     fn set_parent(&mut self, parent: &BaseShape) {
-        self.parent = Some(*parent);
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
+    }
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
     }
-    fn get_parent(&self) -> BaseShape {
-        self.parent.unwrap()
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new_point(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new_point(f64::INFINITY, 0.0, f64::INFINITY),
+        )
     }
     #[allow(unused_variables)]
     fn local_normal_at(&self, point: Point) -> Vector {
@@ -120,8 +170,8 @@ mod tests {
         );
         let xs = p.local_intersect(r);
         assert_eq!(xs.len(), 1);
-        assert_eq!(xs.get(0).unwrap().get_time(), 1.0);
-        assert_eq!(*xs.get(0).unwrap().get_object(), p_o);
+        assert_eq!(xs.first().unwrap().get_time(), 1.0);
+        assert_eq!(*xs.first().unwrap().get_object(), p_o);
     }
 
     #[test]
@@ -134,7 +184,7 @@ mod tests {
         );
         let xs = p.local_intersect(r);
         assert_eq!(xs.len(), 1);
-        assert_eq!(xs.get(0).unwrap().get_time(), 1.0);
-        assert_eq!(*xs.get(0).unwrap().get_object(), p_o);
+        assert_eq!(xs.first().unwrap().get_time(), 1.0);
+        assert_eq!(*xs.first().unwrap().get_object(), p_o);
     }
 }
@@ -0,0 +1,270 @@
+#![allow(unused)]
+use super::*;
+use crate::ray_tracer::{
+    bounds::Bounds,
+    intersections::Intersection,
+    materials::Material,
+    matrices::Matrix,
+    rays::Ray,
+    tuples::{Point, Tuple, Vector},
+    utils::EPSILON,
+};
+
+/// A triangle with a normal at each corner. The normal anywhere else is blended from the three,
+/// so a mesh of them looks smooth instead of faceted.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SmoothTriangle {
+    base: BaseShape,
+    parent: Option<BaseShape>,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+    e1: Vector,
+    e2: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        Self {
+            base: BaseShape {
+                position: Some(Point::new_point(0.0, 0.0, 0.0)),
+                transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
+                material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
+            },
+            parent: None,
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+
+    /// The barycentric coordinates (u, v) of a point on the triangle, where u is the weight of
+    /// `p2` and v the weight of `p3`.
+    fn barycentric(&self, point: &Point) -> (f64, f64) {
+        let p = *point - self.p1;
+        let d00 = Tuple::dot(&self.e1, &self.e1);
+        let d01 = Tuple::dot(&self.e1, &self.e2);
+        let d11 = Tuple::dot(&self.e2, &self.e2);
+        let d20 = Tuple::dot(&p, &self.e1);
+        let d21 = Tuple::dot(&p, &self.e2);
+        let denominator = d00 * d11 - d01 * d01;
+
+        (
+            (d11 * d20 - d01 * d21) / denominator,
+            (d00 * d21 - d01 * d20) / denominator,
+        )
+    }
+}
+
+/// A flat triangle facing -z, with every corner normal pointing straight out of its face.
+impl Default for SmoothTriangle {
+    fn default() -> Self {
+        let face_normal = Vector::new_vector(0.0, 0.0, -1.0);
+        Self::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Point::new_point(-1.0, 0.0, 0.0),
+            Point::new_point(1.0, 0.0, 0.0),
+            face_normal,
+            face_normal,
+            face_normal,
+        )
+    }
+}
+
+impl Shapes for SmoothTriangle {
+    fn set_position(&mut self, pos: &Point) {
+        self.base.position = Some(*pos);
+    }
+    fn get_position(&self) -> Point {
+        self.base.position.unwrap()
+    }
+    fn set_transform(&mut self, transform: &Matrix) {
+        self.base.set_transform(transform);
+    }
+    fn get_transform(&self) -> Matrix {
+        self.base.transform.unwrap()
+    }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
+    fn set_material(&mut self, material: &Material) {
+        self.base.material = Some(material.clone());
+    }
+    fn get_material(&self) -> Material {
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
+    }
+    fn set_parent(&mut self, parent: &BaseShape) {
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
+    }
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        let mut bounds = Bounds::empty();
+        bounds.add_point(&self.p1);
+        bounds.add_point(&self.p2);
+        bounds.add_point(&self.p3);
+        bounds
+    }
+    fn local_normal_at(&self, point: Point) -> Vector {
+        let (u, v) = self.barycentric(&point);
+        (self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)).normalize()
+    }
+    fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
+        // Möller-Trumbore
+        let dir_cross_e2 = Tuple::cross(&local_ray.direction, &self.e2);
+        let det = Tuple::dot(&self.e1, &dir_cross_e2);
+        if f64::abs(det) < EPSILON {
+            return Vec::new();
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = local_ray.origin - self.p1;
+        let u = f * Tuple::dot(&p1_to_origin, &dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return Vec::new();
+        }
+
+        let origin_cross_e1 = Tuple::cross(&p1_to_origin, &self.e1);
+        let v = f * Tuple::dot(&local_ray.direction, &origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return Vec::new();
+        }
+
+        let t = f * Tuple::dot(&self.e2, &origin_cross_e1);
+        vec![Intersection::new(t, Object::SmoothTriangle(self.clone()))]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_ray_strikes_a_smooth_triangle() {
+        let tri = SmoothTriangle::default();
+        let r = Ray::new(
+            Point::new_point(-0.2, 0.3, -2.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = tri.local_intersect(r);
+        assert_eq!(xs.len(), 1);
+        assert_eq!(xs[0].get_time(), 2.0);
+    }
+
+    #[test]
+    fn a_ray_misses_the_edges_of_a_smooth_triangle() {
+        let tri = SmoothTriangle::default();
+        let examples = [
+            Point::new_point(1.0, 1.0, -2.0),
+            Point::new_point(-1.0, 1.0, -2.0),
+            Point::new_point(0.0, -1.0, -2.0),
+        ];
+        for origin in examples {
+            let r = Ray::new(origin, Vector::new_vector(0.0, 0.0, 1.0));
+            assert!(tri.local_intersect(r).is_empty());
+        }
+        let parallel = Ray::new(
+            Point::new_point(0.0, -1.0, -2.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        assert!(tri.local_intersect(parallel).is_empty());
+    }
+
+    #[test]
+    fn the_default_smooth_triangle_is_flat() {
+        let tri = SmoothTriangle::default();
+        for point in [
+            Point::new_point(0.0, 1.0, 0.0),
+            Point::new_point(0.0, 0.0, 0.0),
+            Point::new_point(-0.2, 0.3, 0.0),
+        ] {
+            assert_eq!(
+                tri.local_normal_at(point),
+                Vector::new_vector(0.0, 0.0, -1.0)
+            );
+        }
+    }
+
+    #[test]
+    fn a_smooth_triangle_interpolates_its_normals() {
+        let tri = SmoothTriangle::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Point::new_point(-1.0, 0.0, 0.0),
+            Point::new_point(1.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+            Vector::new_vector(-1.0, 0.0, 0.0),
+            Vector::new_vector(1.0, 0.0, 0.0),
+        );
+        assert_eq!(
+            tri.local_normal_at(Point::new_point(0.0, 1.0, 0.0)),
+            Vector::new_vector(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            tri.local_normal_at(Point::new_point(-1.0, 0.0, 0.0)),
+            Vector::new_vector(-1.0, 0.0, 0.0)
+        );
+        // u = 0.45, v = 0.25
+        assert_eq!(
+            tri.local_normal_at(Point::new_point(-0.2, 0.3, 0.0)),
+            Vector::new_vector(-0.5547, 0.83205, 0.0)
+        );
+    }
+
+    #[test]
+    fn the_bounds_of_a_smooth_triangle() {
+        let b = SmoothTriangle::default().bounds();
+        assert_eq!(b.get_min(), Point::new_point(-1.0, 0.0, 0.0));
+        assert_eq!(b.get_max(), Point::new_point(1.0, 1.0, 0.0));
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(unused)]
 use super::*;
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::Intersection,
     materials::Material,
     matrices::Matrix,
@@ -9,6 +10,7 @@ use crate::ray_tracer::{
 };
 
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sphere {
     base: BaseShape,
     parent: Option<BaseShape>,
@@ -21,6 +23,11 @@ impl Sphere {
                 position: Some(Point::new_point(0.0, 0.0, 0.0)),
                 transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
                 material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
             },
             parent: None,
         }
@@ -41,28 +48,74 @@ impl Shapes for Sphere {
         self.base.position.unwrap()
     }
     fn set_transform(&mut self, transform: &Matrix) {
-        let mut trans = *transform;
-        trans.calculate_inverse().unwrap();
-        self.base.transform = Some(trans);
+        self.base.set_transform(transform);
     }
     fn get_transform(&self) -> Matrix {
         self.base.transform.unwrap()
     }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
     fn set_material(&mut self, material: &Material) {
-        self.base.material = Some(*material);
+        self.base.material = Some(material.clone());
     }
     fn get_material(&self) -> Material {
-        self.base.material.unwrap()
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
     }
     fn set_parent(&mut self, parent: &BaseShape) {
-        self.parent = Some(*parent);
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
+    }
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
     }
-    fn get_parent(&self) -> BaseShape {
-        self.parent.unwrap()
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
     }
     fn local_normal_at(&self, point: Point) -> Vector {
         point - Point::new_point(0.0, 0.0, 0.0)
     }
+    /// A ray that misses returns nothing, and is rejected before taking any square root.
+    /// A ray that just touches the sphere returns two intersections at the same `t`, like one
+    /// going through it, so that every hit is paired with a place where the ray leaves again.
     fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
         let sphere_to_ray = local_ray.origin - self.get_position();
         let a = Tuple::dot(&local_ray.direction, &local_ray.direction);
@@ -70,22 +123,16 @@ impl Shapes for Sphere {
         let c = Tuple::dot(&sphere_to_ray, &sphere_to_ray) - 1.0;
 
         let discriminant = b.powi(2) - 4.0 * a * c;
-        let discriminant_sqrt = discriminant.sqrt();
-
         if discriminant < 0.0 {
-            Vec::new()
-        } else {
-            vec![
-                Intersection::new(
-                    (-b - discriminant_sqrt) / (2.0 * a),
-                    Object::Sphere(self.clone())
-                ),
-                Intersection::new(
-                    (-b + discriminant_sqrt) / (2.0 * a),
-                    Object::Sphere(self.clone())
-                ),
-            ]
+            return Vec::new();
         }
+
+        let discriminant_sqrt = discriminant.sqrt();
+        let times = [
+            (-b - discriminant_sqrt) / (2.0 * a),
+            (-b + discriminant_sqrt) / (2.0 * a),
+        ];
+        Intersection::new_all(&times, || Object::Sphere(self.clone()))
     }
 }
 
@@ -138,4 +185,26 @@ mod tests {
         ));
         assert_eq!(n, n.normalize())
     }
+    #[test]
+    fn a_tangent_ray_gives_two_equal_intersections() {
+        let s = Sphere::new();
+        let r = Ray::new(
+            Tuple::new_point(1.0, 0.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = s.local_intersect(r);
+        assert_eq!(xs.len(), 2);
+        assert_eq!(xs[0].get_time(), 5.0);
+        assert_eq!(xs[1].get_time(), 5.0);
+        assert_eq!(xs[0].get_object(), xs[1].get_object());
+    }
+    #[test]
+    fn a_ray_that_clearly_misses_gives_no_intersections() {
+        let s = Sphere::new();
+        let r = Ray::new(
+            Tuple::new_point(0.0, 100.0, -5.0),
+            Tuple::new_vector(0.0, 0.0, 1.0),
+        );
+        assert!(s.local_intersect(r).is_empty());
+    }
 }
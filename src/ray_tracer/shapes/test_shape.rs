@@ -1,6 +1,7 @@
 #![allow(unused)]
 use super::*;
 use crate::ray_tracer::{
+    bounds::Bounds,
     intersections::Intersection,
     materials::Material,
     matrices::Matrix,
@@ -9,15 +10,21 @@ use crate::ray_tracer::{
 };
 
 /// Ugly hack for testing purposes
+/// Kept per thread so tests running in parallel don't overwrite each other's ray.
 mod saved_ray {
     use crate::ray_tracer::rays::Ray;
+    use std::cell::Cell;
 
-    pub(super) static mut SAVED_RAY: Option<Ray> = None;
+    thread_local! {
+        pub(super) static SAVED_RAY: Cell<Option<Ray>> = const { Cell::new(None) };
+        pub(super) static INTERSECT_COUNT: Cell<usize> = const { Cell::new(0) };
+    }
 }
-use saved_ray::SAVED_RAY;
+use saved_ray::{INTERSECT_COUNT, SAVED_RAY};
 
 #[cfg(test)]
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TestShape {
     base: BaseShape,
     parent: Option<BaseShape>,
@@ -29,12 +36,24 @@ impl TestShape {
                 position: Some(Point::new_point(0.0, 0.0, 0.0)),
                 transform: Some(Matrix::new_identity().calculate_inverse().unwrap()),
                 material: Some(Material::new()),
+                metadata: None,
+                epsilon: None,
+                id: BaseShape::next_id(),
+                motion: None,
+                casts_shadow: true,
             },
             parent: None,
         }
     }
     pub(super) fn get_saved_ray() -> Option<Ray> {
-        unsafe { SAVED_RAY }
+        SAVED_RAY.with(|ray| ray.get())
+    }
+    /// Number of times `local_intersect` has been called on any test shape in this thread.
+    pub(super) fn get_intersect_count() -> usize {
+        INTERSECT_COUNT.with(|count| count.get())
+    }
+    pub(super) fn reset_intersect_count() {
+        INTERSECT_COUNT.with(|count| count.set(0));
     }
 }
 
@@ -52,31 +71,74 @@ impl Shapes for TestShape {
         self.base.position.unwrap()
     }
     fn set_transform(&mut self, transform: &Matrix) {
-        let transform = transform.clone().calculate_inverse().unwrap();
-        self.base.transform = Some(transform);
+        self.base.set_transform(transform);
     }
     fn get_transform(&self) -> Matrix {
         self.base.transform.unwrap()
     }
+    fn get_transform_ref(&self) -> &Matrix {
+        self.base.transform.as_ref().unwrap()
+    }
     fn set_material(&mut self, material: &Material) {
-        self.base.material = Some(*material);
+        self.base.material = Some(material.clone());
     }
     fn get_material(&self) -> Material {
-        self.base.material.unwrap()
+        self.base.material.clone().unwrap()
+    }
+    fn material(&self) -> &Material {
+        self.base.material.as_ref().unwrap()
     }
     fn set_parent(&mut self, parent: &BaseShape) {
-        self.parent = Some(*parent);
+        self.parent = Some(parent.clone());
+    }
+    fn get_parent(&self) -> Option<&BaseShape> {
+        self.parent.as_ref()
+    }
+    fn clear_parent(&mut self) {
+        self.parent = None;
+    }
+    fn set_metadata(&mut self, metadata: Option<String>) {
+        self.base.metadata = metadata;
+    }
+    fn get_metadata(&self) -> Option<&str> {
+        self.base.metadata.as_deref()
     }
-    fn get_parent(&self) -> BaseShape {
-        self.parent.unwrap()
+    fn set_epsilon(&mut self, epsilon: Option<f64>) {
+        self.base.epsilon = epsilon;
+    }
+    fn get_epsilon(&self) -> Option<f64> {
+        self.base.epsilon
+    }
+    fn get_id(&self) -> usize {
+        self.base.id
+    }
+    fn renew_id(&mut self) {
+        self.base.renew_id();
+    }
+    fn set_motion(&mut self, motion: Option<(Matrix, Matrix)>) {
+        self.base.motion = motion;
+    }
+    fn get_motion(&self) -> Option<(Matrix, Matrix)> {
+        self.base.motion
+    }
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.casts_shadow = casts_shadow;
+    }
+    fn get_casts_shadow(&self) -> bool {
+        self.base.casts_shadow
+    }
+    fn bounds(&self) -> Bounds {
+        Bounds::new(
+            Point::new_point(-1.0, -1.0, -1.0),
+            Point::new_point(1.0, 1.0, 1.0),
+        )
     }
     fn local_normal_at(&self, point: Point) -> Vector {
         Vector::new_vector(point.x, point.y, point.z)
     }
     fn local_intersect(&self, local_ray: Ray) -> Vec<Intersection> {
-        unsafe {
-            SAVED_RAY = Some(local_ray);
-        }
+        SAVED_RAY.with(|ray| ray.set(Some(local_ray)));
+        INTERSECT_COUNT.with(|count| count.set(count.get() + 1));
         Vec::new()
     }
 }
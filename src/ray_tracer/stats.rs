@@ -0,0 +1,157 @@
+//! Counters for what a render spends its time on.
+//! Counting is behind the `stats` feature, which is off by default. Without it the counters stay
+//! at 0 and the calls that bump them compile to nothing.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "stats")]
+thread_local! {
+    /// `local_intersect` calls on this thread since the last [`take_intersection_tests`].
+    /// Shapes don't know which world they're in, so the count is collected here and handed to the
+    /// world's stats when a world intersection is done.
+    static INTERSECTION_TESTS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Counts a call to a shape's `local_intersect` on the current thread.
+#[inline]
+pub(crate) fn count_intersection_test() {
+    #[cfg(feature = "stats")]
+    INTERSECTION_TESTS.with(|tests| tests.set(tests.get() + 1));
+}
+
+/// Returns the intersection tests counted on the current thread, and starts over from 0.
+#[inline]
+pub(crate) fn take_intersection_tests() -> usize {
+    #[cfg(feature = "stats")]
+    return INTERSECTION_TESTS.with(|tests| tests.replace(0));
+    #[cfg(not(feature = "stats"))]
+    0
+}
+
+/// Counters collected while rendering a world.
+/// The counters are atomic so a world can keep counting while it is shared between render threads.
+#[derive(Debug, Default)]
+pub struct RenderStats {
+    primary_rays: AtomicUsize,
+    shadow_rays: AtomicUsize,
+    reflection_rays: AtomicUsize,
+    refraction_rays: AtomicUsize,
+    intersection_tests: AtomicUsize,
+}
+
+impl RenderStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of rays cast from the camera, one per pixel sample.
+    pub fn primary_rays(&self) -> usize {
+        self.primary_rays.load(Ordering::Relaxed)
+    }
+    /// Number of rays cast towards lights to check for shadows.
+    pub fn shadow_rays(&self) -> usize {
+        self.shadow_rays.load(Ordering::Relaxed)
+    }
+    /// Number of reflected rays cast, i.e. the number of mirror bounces.
+    pub fn reflection_rays(&self) -> usize {
+        self.reflection_rays.load(Ordering::Relaxed)
+    }
+    /// Number of refracted rays cast through transparent surfaces.
+    pub fn refraction_rays(&self) -> usize {
+        self.refraction_rays.load(Ordering::Relaxed)
+    }
+    /// Number of times a ray was tested against a single shape, including the shapes inside
+    /// groups. Groups whose bounding box is missed save the tests of all their children.
+    pub fn intersection_tests(&self) -> usize {
+        self.intersection_tests.load(Ordering::Relaxed)
+    }
+
+    pub fn reset(&self) {
+        self.primary_rays.store(0, Ordering::Relaxed);
+        self.shadow_rays.store(0, Ordering::Relaxed);
+        self.reflection_rays.store(0, Ordering::Relaxed);
+        self.refraction_rays.store(0, Ordering::Relaxed);
+        self.intersection_tests.store(0, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub(crate) fn add_primary_ray(&self) {
+        Self::add(&self.primary_rays, 1);
+    }
+    #[inline]
+    pub(crate) fn add_shadow_ray(&self) {
+        Self::add(&self.shadow_rays, 1);
+    }
+    #[inline]
+    pub(crate) fn add_reflection_ray(&self) {
+        Self::add(&self.reflection_rays, 1);
+    }
+    #[inline]
+    pub(crate) fn add_refraction_ray(&self) {
+        Self::add(&self.refraction_rays, 1);
+    }
+    #[inline]
+    pub(crate) fn add_intersection_tests(&self, tests: usize) {
+        Self::add(&self.intersection_tests, tests);
+    }
+
+    #[inline]
+    #[allow(unused_variables)]
+    fn add(counter: &AtomicUsize, amount: usize) {
+        #[cfg(feature = "stats")]
+        counter.fetch_add(amount, Ordering::Relaxed);
+    }
+}
+
+/// Statistics are bookkeeping, not part of the scene, so they never make two worlds unequal.
+impl PartialEq for RenderStats {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+#[cfg(all(test, feature = "stats"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counting_and_resetting_reflection_rays() {
+        let stats = RenderStats::new();
+        assert_eq!(stats.reflection_rays(), 0);
+
+        stats.add_reflection_ray();
+        stats.add_reflection_ray();
+        assert_eq!(stats.reflection_rays(), 2);
+
+        stats.reset();
+        assert_eq!(stats.reflection_rays(), 0);
+    }
+
+    #[test]
+    fn counting_every_kind_of_ray_and_intersection_tests() {
+        let stats = RenderStats::new();
+        stats.add_primary_ray();
+        stats.add_shadow_ray();
+        stats.add_shadow_ray();
+        stats.add_refraction_ray();
+        stats.add_intersection_tests(5);
+        assert_eq!(stats.primary_rays(), 1);
+        assert_eq!(stats.shadow_rays(), 2);
+        assert_eq!(stats.refraction_rays(), 1);
+        assert_eq!(stats.intersection_tests(), 5);
+
+        stats.reset();
+        assert_eq!(stats.shadow_rays(), 0);
+        assert_eq!(stats.intersection_tests(), 0);
+    }
+
+    #[test]
+    fn intersection_tests_are_counted_per_thread() {
+        take_intersection_tests();
+        count_intersection_test();
+        count_intersection_test();
+        std::thread::spawn(count_intersection_test).join().unwrap();
+        assert_eq!(take_intersection_tests(), 2);
+        assert_eq!(take_intersection_tests(), 0);
+    }
+}
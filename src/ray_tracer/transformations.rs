@@ -1,4 +1,8 @@
-use crate::ray_tracer::{matrices::Matrix, tuples::Tuple};
+use crate::ray_tracer::{
+    matrices::Matrix,
+    tuples::{Tuple, Vector},
+    utils::is_float_equal,
+};
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct Transform;
@@ -48,6 +52,20 @@ impl Transform {
         ])
         .unwrap()
     }
+    /// Rotates by `angle` radians around `axis`, which doesn't need to be normalized.
+    /// Follows the same handedness as [`Transform::rotation_x`] and friends.
+    pub fn rotation_axis(axis: &Vector, angle: f64) -> Matrix {
+        let Tuple { x, y, z, .. } = axis.normalize();
+        let (s, c) = f64::sin_cos(angle);
+        let t = 1.0 - c;
+        Matrix::new(vec![
+            vec![t * x * x + c, t * x * y - s * z, t * x * z + s * y, 0.0],
+            vec![t * x * y + s * z, t * y * y + c, t * y * z - s * x, 0.0],
+            vec![t * x * z - s * y, t * y * z + s * x, t * z * z + c, 0.0],
+            vec![0.0, 0.0, 0.0, 1.0],
+        ])
+        .unwrap()
+    }
     pub fn shearing(x_y: f64, x_z: f64, y_x: f64, y_z: f64, z_x: f64, z_y: f64) -> Matrix {
         Matrix::new(vec![
             vec![1.0, x_y, x_z, 0.0],
@@ -73,6 +91,171 @@ impl Transform {
 
         orientation * Transform::translate(-from.x, -from.y, -from.z)
     }
+
+    /// Splits a transformation into its translation, scale and rotation, such that
+    /// `translate(t) * rotation_z(r.z) * rotation_y(r.y) * rotation_x(r.x) * scaling(s)` gives
+    /// back the original matrix. The rotation is returned as Euler angles in radians.
+    ///
+    /// This is a best-effort decomposition. A matrix containing shearing, or a scaling that is
+    /// applied after a rotation, can't be written in this form, and the result is only an
+    /// approximation. A mirroring is always reported as a negative x scale, and when the y
+    /// rotation is ±π/2 the x and z rotations can't be told apart, so all of it is put on z.
+    pub fn decompose(transform: &Matrix) -> (Vector, Vector, Vector) {
+        let m = |row: usize, col: usize| transform.get_element(row, col);
+        let translation = Tuple::new_vector(m(0, 3), m(1, 3), m(2, 3));
+
+        let columns = [0, 1, 2].map(|col| Tuple::new_vector(m(0, col), m(1, col), m(2, col)));
+        let mut scale = Tuple::new_vector(
+            columns[0].magnitude(),
+            columns[1].magnitude(),
+            columns[2].magnitude(),
+        );
+        if Tuple::dot(&Tuple::cross(&columns[0], &columns[1]), &columns[2]) < 0.0 {
+            scale.x = -scale.x;
+        }
+
+        // The pure rotation part, with the scaling divided out of each column
+        let r = |row: usize, col: usize| {
+            let s = [scale.x, scale.y, scale.z][col];
+            if s == 0.0 {
+                0.0
+            } else {
+                m(row, col) / s
+            }
+        };
+        let y = f64::asin((-r(2, 0)).clamp(-1.0, 1.0));
+        let (x, z) = if is_float_equal(&y.cos(), 0.0) {
+            (0.0, f64::atan2(-r(0, 1), r(1, 1)))
+        } else {
+            (f64::atan2(r(2, 1), r(2, 2)), f64::atan2(r(1, 0), r(0, 0)))
+        };
+
+        (translation, scale, Tuple::new_vector(x, y, z))
+    }
+
+    /// Blends between the transformations `start` at `t = 0` and `end` at `t = 1`, for moving
+    /// objects. Both are split up with [`Transform::decompose`]; the translations and scales
+    /// are interpolated linearly and the rotations along the shortest arc between them, so a
+    /// spinning object keeps its size all the way round.
+    /// The inverse isn't calculated.
+    pub fn interpolate(start: &Matrix, end: &Matrix, t: f64) -> Matrix {
+        let (start_translation, start_scale, start_rotation) = Transform::decompose(start);
+        let (end_translation, end_scale, end_rotation) = Transform::decompose(end);
+
+        let translation = start_translation.lerp(&end_translation, t);
+        let scale = start_scale.lerp(&end_scale, t);
+        let rotation = slerp(
+            &to_quaternion(&euler_rotation(&start_rotation)),
+            &to_quaternion(&euler_rotation(&end_rotation)),
+            t,
+        );
+
+        Transform::translate(translation.x, translation.y, translation.z)
+            * from_quaternion(&rotation)
+            * Transform::scaling(scale.x, scale.y, scale.z)
+    }
+}
+
+/// A rotation as a unit quaternion, stored as `[w, x, y, z]`.
+type Quaternion = [f64; 4];
+
+/// The rotation matrix for Euler angles from [`Transform::decompose`].
+fn euler_rotation(angles: &Vector) -> Matrix {
+    Transform::rotation_z(angles.z)
+        * Transform::rotation_y(angles.y)
+        * Transform::rotation_x(angles.x)
+}
+
+/// Converts the upper 3x3 of a rotation matrix to a quaternion, starting from its largest
+/// component to keep the square root away from zero.
+fn to_quaternion(rotation: &Matrix) -> Quaternion {
+    let r = |row: usize, col: usize| rotation.get_element(row, col);
+    let trace = r(0, 0) + r(1, 1) + r(2, 2);
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [
+            0.25 * s,
+            (r(2, 1) - r(1, 2)) / s,
+            (r(0, 2) - r(2, 0)) / s,
+            (r(1, 0) - r(0, 1)) / s,
+        ]
+    } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+        let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+        [
+            (r(2, 1) - r(1, 2)) / s,
+            0.25 * s,
+            (r(0, 1) + r(1, 0)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+        ]
+    } else if r(1, 1) > r(2, 2) {
+        let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+        [
+            (r(0, 2) - r(2, 0)) / s,
+            (r(0, 1) + r(1, 0)) / s,
+            0.25 * s,
+            (r(1, 2) + r(2, 1)) / s,
+        ]
+    } else {
+        let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+        [
+            (r(1, 0) - r(0, 1)) / s,
+            (r(0, 2) + r(2, 0)) / s,
+            (r(1, 2) + r(2, 1)) / s,
+            0.25 * s,
+        ]
+    }
+}
+
+fn from_quaternion(q: &Quaternion) -> Matrix {
+    let [w, x, y, z] = *q;
+    Matrix::new(vec![
+        vec![
+            1.0 - 2.0 * (y * y + z * z),
+            2.0 * (x * y - z * w),
+            2.0 * (x * z + y * w),
+            0.0,
+        ],
+        vec![
+            2.0 * (x * y + z * w),
+            1.0 - 2.0 * (x * x + z * z),
+            2.0 * (y * z - x * w),
+            0.0,
+        ],
+        vec![
+            2.0 * (x * z - y * w),
+            2.0 * (y * z + x * w),
+            1.0 - 2.0 * (x * x + y * y),
+            0.0,
+        ],
+        vec![0.0, 0.0, 0.0, 1.0],
+    ])
+    .unwrap()
+}
+
+/// Spherical interpolation between two rotations, the short way round.
+fn slerp(a: &Quaternion, b: &Quaternion, t: f64) -> Quaternion {
+    let mut dot: f64 = a.iter().zip(b).map(|(a, b)| a * b).sum();
+    // q and -q are the same rotation; pick the one on a's side
+    let b = if dot < 0.0 {
+        dot = -dot;
+        b.map(|c| -c)
+    } else {
+        *b
+    };
+
+    let blended: Quaternion = if dot > 0.9995 {
+        // Nearly the same rotation, where the angle is too small to divide by
+        std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+    } else {
+        let angle = dot.acos();
+        let (wa, wb) = (
+            ((1.0 - t) * angle).sin() / angle.sin(),
+            (t * angle).sin() / angle.sin(),
+        );
+        std::array::from_fn(|i| a[i] * wa + b[i] * wb)
+    };
+    let length = blended.iter().map(|c| c * c).sum::<f64>().sqrt();
+    blended.map(|c| c / length)
 }
 
 #[cfg(test)]
@@ -202,6 +385,29 @@ mod tests {
         assert_eq!(full_quarter * p, Tuple::new_point(-1.0, 0.0, 0.0));
     }
 
+    #[test]
+    fn rotating_around_the_z_axis_matches_rotation_z() {
+        let axis = Tuple::new_vector(0.0, 0.0, 1.0);
+        assert_eq!(
+            Transform::rotation_axis(&axis, PI / 3.0),
+            Transform::rotation_z(PI / 3.0)
+        );
+    }
+
+    #[test]
+    fn rotating_around_an_unnormalized_diagonal_axis() {
+        // A third of a turn around (1, 1, 1) cycles the coordinate axes
+        let r = Transform::rotation_axis(&Tuple::new_vector(2.0, 2.0, 2.0), 2.0 * PI / 3.0);
+        assert_eq!(
+            r * Tuple::new_point(1.0, 0.0, 0.0),
+            Tuple::new_point(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            r * Tuple::new_vector(0.0, 0.0, 1.0),
+            Tuple::new_vector(1.0, 0.0, 0.0)
+        );
+    }
+
     #[test]
     fn a_shearing_transformation_moves_x_in_proportion_to_y() {
         let transform = Transform::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0);
@@ -306,4 +512,63 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    fn decomposing_a_translation_and_scaling() {
+        let transform = Transform::translate(1.0, -2.0, 3.0) * Transform::scaling(2.0, 0.5, 4.0);
+        let (translation, scale, rotation) = Transform::decompose(&transform);
+        assert_eq!(translation, Tuple::new_vector(1.0, -2.0, 3.0));
+        assert_eq!(scale, Tuple::new_vector(2.0, 0.5, 4.0));
+        assert_eq!(rotation, Tuple::new_vector(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn decomposing_a_rotation() {
+        let transform = Transform::translate(0.0, 5.0, 0.0)
+            * Transform::rotation_z(PI / 3.0)
+            * Transform::rotation_y(-PI / 6.0)
+            * Transform::rotation_x(PI / 4.0)
+            * Transform::scaling(1.0, 2.0, 3.0);
+        let (translation, scale, rotation) = Transform::decompose(&transform);
+        assert_eq!(translation, Tuple::new_vector(0.0, 5.0, 0.0));
+        assert_eq!(scale, Tuple::new_vector(1.0, 2.0, 3.0));
+        assert_eq!(rotation, Tuple::new_vector(PI / 4.0, -PI / 6.0, PI / 3.0));
+    }
+
+    #[test]
+    fn interpolating_between_transformations() {
+        let start = Transform::translate(0.0, 0.0, 0.0) * Transform::scaling(1.0, 1.0, 1.0);
+        let end = Transform::translate(4.0, -2.0, 0.0) * Transform::scaling(3.0, 1.0, 1.0);
+        assert_eq!(
+            Transform::interpolate(&start, &end, 0.5),
+            Transform::translate(2.0, -1.0, 0.0) * Transform::scaling(2.0, 1.0, 1.0)
+        );
+        assert_eq!(Transform::interpolate(&start, &end, 0.0), start);
+        assert_eq!(Transform::interpolate(&start, &end, 1.0), end);
+    }
+
+    #[test]
+    fn interpolating_rotations_keeps_the_shape_rigid() {
+        // Blending the matrices element by element would squash everything flat halfway
+        let start = Matrix::new_identity();
+        let end = Transform::rotation_y(PI);
+        let halfway = Transform::interpolate(&start, &end, 0.5);
+        assert_eq!(halfway, Transform::rotation_y(PI / 2.0));
+        assert!(halfway.validate_transform().is_ok());
+
+        let start = Transform::translate(1.0, 0.0, 0.0) * Transform::rotation_x(PI / 6.0);
+        let end = Transform::translate(1.0, 0.0, 0.0) * Transform::rotation_x(PI / 2.0);
+        assert_eq!(
+            Transform::interpolate(&start, &end, 0.5),
+            Transform::translate(1.0, 0.0, 0.0) * Transform::rotation_x(PI / 3.0)
+        );
+    }
+
+    #[test]
+    fn decomposing_a_mirroring() {
+        let transform = Transform::scaling(-1.0, 1.0, 1.0);
+        let (_, scale, rotation) = Transform::decompose(&transform);
+        assert_eq!(scale, Tuple::new_vector(-1.0, 1.0, 1.0));
+        assert_eq!(rotation, Tuple::new_vector(0.0, 0.0, 0.0));
+    }
 }
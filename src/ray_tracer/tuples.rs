@@ -2,10 +2,15 @@ use crate::ray_tracer::utils::is_float_equal;
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
 #[derive(Debug, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Tuple {
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub x: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub y: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub z: f64,
+    #[cfg_attr(feature = "serde", serde(with = "crate::ray_tracer::utils::serde_f64"))]
     pub w: f64,
 }
 
@@ -34,6 +39,18 @@ impl Tuple {
     pub fn new_point(x: f64, y: f64, z: f64) -> Self {
         Tuple { x, y, z, w: 1.0 }
     }
+    /// Creates a point from spherical coordinates around the origin, with y as the polar axis
+    /// since the scenes are y-up. `theta` is the polar angle from +y, and `phi` is the azimuth in
+    /// the xz-plane, from +x towards +z. Both are in radians.
+    ///
+    /// `from_spherical(r, 0.0, phi)` is always straight above the origin, at `(0, r, 0)`.
+    pub fn from_spherical(r: f64, theta: f64, phi: f64) -> Self {
+        Tuple::new_point(
+            r * theta.sin() * phi.cos(),
+            r * theta.cos(),
+            r * theta.sin() * phi.sin(),
+        )
+    }
 
     ////////////////////////////////////////////////////////////////////////////
     // Vector-land!
@@ -41,6 +58,14 @@ impl Tuple {
     pub fn new_vector(x: f64, y: f64, z: f64) -> Self {
         Tuple { x, y, z, w: 0.0 }
     }
+    /// Creates a vector from cylindrical coordinates around the y-axis. `theta` is the angle in
+    /// radians in the xz-plane, from +x towards +z, and `y` is the height.
+    ///
+    /// Adding it to a point places something on a ring around that point, e.g. lights or
+    /// cameras around a subject.
+    pub fn from_cylindrical(r: f64, theta: f64, y: f64) -> Self {
+        Tuple::new_vector(r * theta.cos(), y, r * theta.sin())
+    }
     pub fn magnitude(&self) -> f64 {
         assert_eq!(self.w, 0.0, "Magnitude is only valid for vectors!");
         let pow_x = f64::powi(self.x, 2);
@@ -78,9 +103,47 @@ impl Tuple {
             a.x * b.y - a.y * b.x,
         )
     }
+    /// Reflects `vector` around `normal`, which must have a length of 1.
+    /// Use [`Tuple::reflect_unnormalized`] if it might not.
     pub fn reflect(vector: &Self, normal: &Self) -> Self {
         *vector - *normal * 2.0 * Tuple::dot(vector, normal)
     }
+    /// Like [`Tuple::reflect`], but normalizes `normal` first.
+    pub fn reflect_unnormalized(vector: &Self, normal: &Self) -> Self {
+        Tuple::reflect(vector, &normal.normalize())
+    }
+    /// The angle between two vectors in radians, from 0 to PI.
+    /// Neither vector may have zero length, or the result is NaN.
+    pub fn angle_between(&self, other: &Self) -> f64 {
+        let cos = Tuple::dot(&self.normalize(), &other.normalize());
+        // Rounding can push the cosine of (anti)parallel vectors just past 1
+        f64::acos(cos.clamp(-1.0, 1.0))
+    }
+    /// The part of this vector that points along `other`.
+    /// `other` may not have zero length, or the result is NaN.
+    pub fn project_onto(&self, other: &Self) -> Self {
+        *other * (Tuple::dot(self, other) / Tuple::dot(other, other))
+    }
+    /// The part of this vector that is perpendicular to `other`, so that
+    /// `v.project_onto(&o) + v.reject_from(&o) == v`.
+    /// `other` may not have zero length, or the result is NaN.
+    pub fn reject_from(&self, other: &Self) -> Self {
+        *self - self.project_onto(other)
+    }
+
+    /// Checks whether every component is within
+    /// [`EPSILON`](crate::ray_tracer::utils::EPSILON) of `other`'s. This is what `==` uses.
+    pub fn approx_eq(&self, other: &Self) -> bool {
+        is_float_equal(&self.x, other.x)
+            && is_float_equal(&self.y, other.y)
+            && is_float_equal(&self.z, other.z)
+            && is_float_equal(&self.w, other.w)
+    }
+
+    /// Interpolates component by component between `self` at `t = 0` and `other` at `t = 1`.
+    pub fn lerp(&self, other: &Self, t: f64) -> Self {
+        *self + (*other - *self) * t
+    }
 
     pub fn is_point(&self) -> bool {
         is_float_equal(&self.w, 1.0)
@@ -151,15 +214,14 @@ impl Div<f64> for Tuple {
 
 impl PartialEq for Tuple {
     fn eq(&self, other: &Self) -> bool {
-        is_float_equal(&self.x, other.x)
-            && is_float_equal(&self.y, other.y)
-            && is_float_equal(&self.z, other.z)
-            && is_float_equal(&self.w, other.w)
+        self.approx_eq(other)
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::f64::consts::PI;
+
     use super::*;
 
     #[test]
@@ -374,10 +436,90 @@ mod tests {
         assert_eq!(r, Tuple::new_vector(1.0, 1.0, 0.0));
     }
     #[test]
+    fn interpolating_between_vectors() {
+        let a = Tuple::new_vector(1.0, -2.0, 0.0);
+        let b = Tuple::new_vector(3.0, 2.0, -4.0);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+        assert_eq!(a.lerp(&b, 0.5), Tuple::new_vector(2.0, 0.0, -2.0));
+    }
+    #[test]
+    fn reflecting_off_a_normal_that_is_not_normalized() {
+        let v = Tuple::new_vector(1.0, -1.0, 0.0);
+        let n = Tuple::new_vector(0.0, 2.0, 0.0);
+        assert_eq!(
+            Tuple::reflect_unnormalized(&v, &n),
+            Tuple::new_vector(1.0, 1.0, 0.0)
+        );
+        assert_eq!(Tuple::reflect(&v, &n), Tuple::new_vector(1.0, 7.0, 0.0));
+    }
+    #[test]
     fn reflecting_a_vector_off_a_slanted_surface() {
         let v = Tuple::new_vector(0.0, -1.0, 0.0);
         let n = Tuple::new_vector(f64::sqrt(2.0) / 2.0, f64::sqrt(2.0) / 2.0, 0.0);
         let r = Tuple::reflect(&v, &n);
         assert_eq!(r, Tuple::new_vector(1.0, 0.0, 0.0));
     }
+    #[test]
+    fn the_angle_between_vectors() {
+        let x = Tuple::new_vector(1.0, 0.0, 0.0);
+        let y = Tuple::new_vector(0.0, 3.0, 0.0);
+        assert!(is_float_equal(&x.angle_between(&y), PI / 2.0));
+        assert!(is_float_equal(&x.angle_between(&x), 0.0));
+        assert!(is_float_equal(&x.angle_between(&-x), PI));
+        let diagonal = Tuple::new_vector(1.0, 1.0, 0.0);
+        assert!(is_float_equal(&x.angle_between(&diagonal), PI / 4.0));
+    }
+    #[test]
+    fn projecting_a_vector_onto_an_axis() {
+        let v = Tuple::new_vector(2.0, 3.0, -4.0);
+        let axis = Tuple::new_vector(0.0, 5.0, 0.0);
+        assert_eq!(v.project_onto(&axis), Tuple::new_vector(0.0, 3.0, 0.0));
+        assert_eq!(v.reject_from(&axis), Tuple::new_vector(2.0, 0.0, -4.0));
+        assert_eq!(v.project_onto(&axis) + v.reject_from(&axis), v);
+    }
+
+    #[test]
+    fn approx_eq_tolerates_rounding_but_not_real_differences() {
+        let p = Tuple::new_point(0.1 + 0.2, 1.0, -1.0);
+        assert!(p.approx_eq(&Tuple::new_point(0.3, 1.0, -1.0)));
+        assert!(!p.approx_eq(&Tuple::new_point(0.3001, 1.0, -1.0)));
+        assert!(!p.approx_eq(&Tuple::new_vector(0.3, 1.0, -1.0)));
+    }
+
+    #[test]
+    fn points_from_spherical_coordinates() {
+        assert_eq!(
+            Tuple::from_spherical(1.0, 0.0, 0.0),
+            Tuple::new_point(0.0, 1.0, 0.0)
+        );
+        assert_eq!(
+            Tuple::from_spherical(2.0, PI / 2.0, 0.0),
+            Tuple::new_point(2.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            Tuple::from_spherical(2.0, PI / 2.0, PI / 2.0),
+            Tuple::new_point(0.0, 0.0, 2.0)
+        );
+        assert_eq!(
+            Tuple::from_spherical(1.0, PI, 0.0),
+            Tuple::new_point(0.0, -1.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn vectors_from_cylindrical_coordinates() {
+        assert_eq!(
+            Tuple::from_cylindrical(1.0, 0.0, 0.0),
+            Tuple::new_vector(1.0, 0.0, 0.0)
+        );
+        assert_eq!(
+            Tuple::from_cylindrical(3.0, PI / 2.0, 2.0),
+            Tuple::new_vector(0.0, 2.0, 3.0)
+        );
+        assert_eq!(
+            Tuple::from_cylindrical(3.0, PI, -1.0),
+            Tuple::new_vector(-3.0, -1.0, 0.0)
+        );
+    }
 }
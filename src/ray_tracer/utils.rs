@@ -3,9 +3,61 @@ pub const EPSILON: f64 = 0.00005;
 
 /// Compares two f64 and asserts whether they are within a difference defined by `utils::EPSILON`
 pub fn is_float_equal(actual: &f64, comparison: f64) -> bool {
-    (actual - comparison).abs() < EPSILON
+    is_float_equal_eps(actual, comparison, EPSILON)
 }
 
+/// Compares two f64 and asserts whether they are within a difference of `eps`.
+/// Useful where `utils::EPSILON` is too strict, like determinants of large matrices.
+pub fn is_float_equal_eps(actual: &f64, comparison: f64, eps: f64) -> bool {
+    (actual - comparison).abs() < eps
+}
+
+/// Smooth Hermite interpolation from 0 at `edge0` to 1 at `edge1`, flat at both ends.
+/// `x` outside the edges is clamped.
+pub fn smoothstep(edge0: f64, edge1: f64, x: f64) -> f64 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// (De)serializes `f64`s that may be infinite or NaN, which formats like JSON can't store as
+/// numbers. Those values are written as the strings `"inf"`, `"-inf"` and `"nan"`.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_f64 {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Repr<'a> {
+        Number(f64),
+        Text(&'a str),
+    }
+
+    pub(crate) fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_finite() {
+            serializer.serialize_f64(*value)
+        } else if value.is_nan() {
+            serializer.serialize_str("nan")
+        } else if value.is_sign_positive() {
+            serializer.serialize_str("inf")
+        } else {
+            serializer.serialize_str("-inf")
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(value) => Ok(value),
+            Repr::Text("inf") => Ok(f64::INFINITY),
+            Repr::Text("-inf") => Ok(f64::NEG_INFINITY),
+            Repr::Text("nan") => Ok(f64::NAN),
+            Repr::Text(other) => Err(serde::de::Error::custom(format!(
+                "expected a number, got '{other}'"
+            ))),
+        }
+    }
+}
+
+#[allow(dead_code)]
 pub(crate) struct F64 {
     float: f64,
 }
@@ -15,3 +67,30 @@ impl PartialEq for F64 {
         is_float_equal(&self.float, other.float)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smoothstep_eases_between_the_edges() {
+        assert_eq!(smoothstep(2.0, 4.0, 2.0), 0.0);
+        assert_eq!(smoothstep(2.0, 4.0, 4.0), 1.0);
+        assert_eq!(smoothstep(2.0, 4.0, 3.0), 0.5);
+        assert!(is_float_equal(&smoothstep(2.0, 4.0, 2.5), 0.15625));
+        assert_eq!(smoothstep(2.0, 4.0, -1.0), 0.0);
+        assert_eq!(smoothstep(2.0, 4.0, 10.0), 1.0);
+    }
+
+    #[test]
+    fn comparing_floats_with_a_custom_tolerance() {
+        assert!(is_float_equal_eps(&1.0, 1.0, 0.0001));
+        assert!(is_float_equal_eps(&1.0, 1.00004, EPSILON));
+        assert!(!is_float_equal_eps(&1.0, 1.00006, EPSILON));
+
+        let determinant = 125_000.002;
+        assert!(is_float_equal_eps(&determinant, 125_000.0, 0.01));
+        assert!(!is_float_equal_eps(&determinant, 125_000.0, EPSILON));
+        assert!(!is_float_equal(&determinant, 125_000.0));
+    }
+}
@@ -1,18 +1,62 @@
 use crate::ray_tracer::{
+    camera::Camera,
     colors::Color,
-    intersections::{prepare_computations, schlick, IntersectComp},
+    error::RayTracerError,
+    intersections::{
+        prepare_computations_with_epsilon, schlick, IntersectComp, Intersection, Intersections,
+    },
     lights::Light,
+    materials::{Material, ResolvedMaterial},
+    patterns::{cube_map::uv_cube, Pattern},
     rays::Ray,
+    sampler,
     shapes::*,
+    stats::RenderStats,
     transformations::Transform,
-    tuples::{Point, Tuple},
-    utils::is_float_equal,
+    tuples::{Point, Tuple, Vector},
+    utils::{is_float_equal, EPSILON},
 };
 
+/// Recursion depth used by [`World::color_at_default_depth`] unless set otherwise.
+pub const DEFAULT_MAX_REFLECTIONS: usize = 5;
+
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct World {
+    /// Objects added with [`World::add_object`] get new ids, so clones of each other are still
+    /// told apart, e.g. coincident glass surfaces that only differ in their material.
     pub objects: Vec<Object>,
     pub lights: Vec<Light>,
+    /// When set, light passing through transparent objects on its way to a surface is let
+    /// through according to their transparency and brightened by this fraction instead of being
+    /// blocked, as a cheap approximation of caustics. It never ends up brighter than light that
+    /// isn't blocked at all. Off by default.
+    pub caustic_gain: Option<f64>,
+    /// How many reflections and refractions [`World::color_at_default_depth`] follows.
+    pub max_reflections: usize,
+    /// Color of rays that don't hit anything. Black by default.
+    pub background: Color,
+    /// Patterns surrounding the world like the inside of a huge cube, ordered like
+    /// [`CubeFace`](crate::ray_tracer::patterns::CubeFace). When set, rays that don't hit
+    /// anything get the color of the face their direction points at instead of `background`,
+    /// which gives mirrors a sky to reflect. Each face is sampled like in
+    /// [`Pattern::cube_map`]. Off by default.
+    pub environment: Option<[Pattern; 6]>,
+    /// Color that distant surfaces fade towards.
+    pub fog_color: Color,
+    /// How quickly surfaces fade into `fog_color` with distance. 0 turns fog off.
+    pub fog_density: f64,
+    /// When set, reflections off opaque materials fade with the viewing angle like they do off
+    /// transparent ones, following Schlick's approximation. How much is reflected head-on depends
+    /// on the material's `refractive_index`; 1.0 reflects nothing. Off by default.
+    pub use_fresnel: bool,
+    /// How far hit points are moved off surfaces before casting shadow, reflection and
+    /// refraction rays from them. Larger values fix speckled self-shadowing ("acne") on big
+    /// objects, smaller values keep shadows attached on tiny ones. Shapes with an epsilon of
+    /// their own use that instead. [`EPSILON`] by default.
+    pub shadow_epsilon: f64,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    stats: RenderStats,
 }
 
 impl World {
@@ -20,6 +64,15 @@ impl World {
         World {
             objects: Vec::new(),
             lights: Vec::new(),
+            caustic_gain: None,
+            max_reflections: DEFAULT_MAX_REFLECTIONS,
+            background: Color::new(0.0, 0.0, 0.0),
+            environment: None,
+            fog_color: Color::new(0.0, 0.0, 0.0),
+            fog_density: 0.0,
+            use_fresnel: false,
+            shadow_epsilon: EPSILON,
+            stats: RenderStats::new(),
         }
     }
     pub fn new_default_world() -> World {
@@ -39,52 +92,340 @@ impl World {
                 &Point::new_point(-10.0, 10.0, -10.0),
                 &Color::new(1.0, 1.0, 1.0),
             )],
+            caustic_gain: None,
+            max_reflections: DEFAULT_MAX_REFLECTIONS,
+            background: Color::new(0.0, 0.0, 0.0),
+            environment: None,
+            fog_color: Color::new(0.0, 0.0, 0.0),
+            fog_density: 0.0,
+            use_fresnel: false,
+            shadow_epsilon: EPSILON,
+            stats: RenderStats::new(),
+        }
+    }
+    /// Sets how many reflections and refractions [`World::color_at_default_depth`] follows.
+    pub fn with_max_reflections(mut self, max_reflections: usize) -> Self {
+        self.max_reflections = max_reflections;
+        self
+    }
+    /// Adds an object to the world, giving it and everything in it new ids, so that it is a
+    /// shape of its own even if it is a clone of an object already in the world.
+    pub fn add_object(&mut self, mut object: Object) {
+        object.renew_id();
+        self.objects.push(object);
+    }
+    /// Checks that the world can be rendered.
+    /// Every object, including the ones inside groups, must have a usable transformation, and no
+    /// object or light may contain NaN, since a single NaN spreads through every color computed
+    /// from it.
+    pub fn try_prepare(&self) -> Result<(), RayTracerError> {
+        for object in &self.objects {
+            Self::check_object(object)?;
+        }
+
+        if self.lights.iter().any(Light::has_nan) {
+            return Err(RayTracerError::NaNInput);
         }
+
+        Ok(())
+    }
+    /// The checks of [`World::try_prepare`] for a single object and, for a group, its children.
+    fn check_object(object: &Object) -> Result<(), RayTracerError> {
+        object.get_transform().validate_transform()?;
+
+        let m = object.material();
+        let values = [
+            m.color.red,
+            m.color.green,
+            m.color.blue,
+            m.ambient,
+            m.diffuse,
+            m.specular,
+            m.shininess,
+            m.reflective,
+            m.transparency,
+            m.refractive_index,
+            m.emissive.red,
+            m.emissive.green,
+            m.emissive.blue,
+        ];
+        if values.iter().any(|v| v.is_nan()) {
+            return Err(RayTracerError::NaNInput);
+        }
+
+        if let Object::Group(g) = object {
+            for child in g.get_children() {
+                Self::check_object(child)?;
+            }
+        }
+
+        Ok(())
+    }
+    /// Returns a copy of the world's settings with the given objects and lights, and fresh stats.
+    fn copy_with(&self, objects: Vec<Object>, lights: Vec<Light>) -> World {
+        World {
+            objects,
+            lights,
+            caustic_gain: self.caustic_gain,
+            max_reflections: self.max_reflections,
+            background: self.background,
+            environment: self.environment.clone(),
+            fog_color: self.fog_color,
+            fog_density: self.fog_density,
+            use_fresnel: self.use_fresnel,
+            shadow_epsilon: self.shadow_epsilon,
+            stats: RenderStats::new(),
+        }
+    }
+    /// Returns a copy of the world where every object uses `material`.
+    /// Geometry and lights are kept, so the result shows the shapes without their surfaces.
+    pub(crate) fn with_material_override(&self, material: &Material) -> World {
+        let mut objects = self.objects.clone();
+        for object in &mut objects {
+            object.override_material(material);
+        }
+        self.copy_with(objects, self.lights.clone())
+    }
+    /// Returns a copy of the world with every moving object placed where it is at `time`.
+    pub(crate) fn at_time(&self, time: f64) -> World {
+        let mut objects = self.objects.clone();
+        for object in &mut objects {
+            object.move_to_time(time);
+        }
+        self.copy_with(objects, self.lights.clone())
+    }
+    /// Returns a copy of the world with `seed` mixed into the seed of every light, so area
+    /// lights pick different sample points. A seed of 0 leaves the lights unchanged.
+    pub(crate) fn with_light_seed(&self, seed: u64) -> World {
+        let mut lights = self.lights.clone();
+        for light in &mut lights {
+            light.set_seed(light.get_seed() ^ sampler::mix(seed));
+        }
+        self.copy_with(self.objects.clone(), lights)
+    }
+    /// Intersects the ray with every object in the world, returning all the hits sorted by
+    /// distance along the ray. Same as [`Ray::intersect_world`].
+    pub fn intersect(&self, r: &Ray) -> Intersections {
+        r.intersect_world(self)
+    }
+    /// Statistics gathered while rendering this world.
+    pub fn get_stats(&self) -> &RenderStats {
+        &self.stats
     }
     pub(crate) fn shade_hit(&self, comps: &IntersectComp, remaining: usize) -> Color {
-        let shadowed = self.is_shadowed(&comps.over_point);
+        let material = comps.object.material();
 
-        let surface = comps.object.get_material().lighting(
-            &comps.object.clone(),
-            &self.lights[0],
-            &comps.over_point,
-            &comps.eyev,
-            &comps.normalv,
-            shadowed,
-        );
+        // Direct lighting is the sum of the contributions of every light
+        let mut surface = Color::new(0.0, 0.0, 0.0);
+        for light in &self.lights {
+            surface = surface + self.light_contribution(comps, light);
+        }
 
         let reflected = self.reflected_color(comps, remaining);
         let refracted = self.refracted_color(comps, remaining);
 
-        let material = comps.object.get_material();
-        if material.reflective > 0.0 && material.transparency > 0.0 {
+        let shaded = if material.reflective > 0.0 && material.transparency > 0.0 {
             let reflectance = schlick(comps);
 
             surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else if material.reflective > 0.0 && self.use_fresnel {
+            surface + reflected * schlick(comps) + refracted
         } else {
             surface + reflected + refracted
+        };
+
+        shaded + material.emissive
+    }
+
+    /// For debugging scenes with several lights: the direct lighting each light adds to a hit,
+    /// as (index into `lights`, color), before they are summed up. Reflections and refractions
+    /// aren't included. See [`World::light_breakdown_at`] for a breakdown along a ray.
+    pub fn shade_hit_breakdown(&self, comps: &IntersectComp) -> Vec<(usize, Color)> {
+        self.lights
+            .iter()
+            .enumerate()
+            .map(|(index, light)| (index, self.light_contribution(comps, light)))
+            .collect()
+    }
+
+    /// The direct lighting `light` adds to a hit, taking shadows into account.
+    fn light_contribution(&self, comps: &IntersectComp, light: &Light) -> Color {
+        let intensity = self.light_visibility(&comps.over_point, light);
+        comps.object.material().lighting(
+            &comps.object,
+            light,
+            &comps.over_point,
+            &comps.eyev,
+            &comps.normalv,
+            intensity,
+        )
+    }
+
+    /// Like [`World::shade_hit_breakdown`], for the first surface the ray hits. `None` if the
+    /// ray doesn't hit anything.
+    pub fn light_breakdown_at(&self, r: &Ray) -> Option<Vec<(usize, Color)>> {
+        let int = r.intersect_world(self);
+        let hit = int.hit()?;
+        let comps = self.prepare_computations(&hit, r, &int);
+        Some(self.shade_hit_breakdown(&comps))
+    }
+
+    /// For debugging a single pixel: follows the ray `camera` shoots through pixel (`x`, `y`)
+    /// and reports what it hit and how the hit was shaded, see [`TraceReport`].
+    /// `num_reflections` should be the same as the one given to [`Camera::render`] for the
+    /// reported color to match the rendered pixel.
+    pub fn trace_pixel(
+        &self,
+        camera: &Camera,
+        x: usize,
+        y: usize,
+        num_reflections: usize,
+    ) -> TraceReport {
+        let ray = camera.ray_for_pixel(x, y);
+        let color = self.color_at(&ray, num_reflections);
+        let int = ray.intersect_world(self);
+        let Some(hit) = int.hit() else {
+            return TraceReport {
+                ray,
+                hit: None,
+                n1: 1.0,
+                n2: 1.0,
+                reflectance: None,
+                color,
+            };
+        };
+        let comps = self.prepare_computations(&hit, &ray, &int);
+        // Only where `shade_hit` weighs the reflection by it
+        let material = comps.object.material();
+        let uses_schlick =
+            material.reflective > 0.0 && (material.transparency > 0.0 || self.use_fresnel);
+        TraceReport {
+            ray,
+            hit: Some(hit),
+            n1: comps.n1,
+            n2: comps.n2,
+            reflectance: uses_schlick.then(|| schlick(&comps)),
+            color,
         }
     }
 
+    /// The color seen along a camera ray. Fog is applied here, by the distance along the camera
+    /// ray, and not again to the reflected and refracted rays traced from its hit.
     pub(crate) fn color_at(&self, r: &Ray, remaining: usize) -> Color {
+        match self.shade_first_hit(r, remaining) {
+            Some((color, t)) => self.apply_fog(color, t),
+            None => self.environment_at(&r.get_direction()),
+        }
+    }
+
+    /// Like `color_at`, but follows up to `max_reflections` reflections and refractions.
+    pub fn color_at_default_depth(&self, r: &Ray) -> Color {
+        self.color_at(r, self.max_reflections)
+    }
+
+    /// The color seen along a reflected or refracted ray, without fog. A ray that misses
+    /// everything gets the `environment` color instead of the world's, if one is given.
+    fn color_at_with_environment(
+        &self,
+        r: &Ray,
+        remaining: usize,
+        environment: Option<Color>,
+    ) -> Color {
+        match self.shade_first_hit(r, remaining) {
+            Some((color, _)) => color,
+            None => environment.unwrap_or_else(|| self.environment_at(&r.get_direction())),
+        }
+    }
+
+    /// The shaded color of the first surface the ray hits and how far along the ray it is.
+    fn shade_first_hit(&self, r: &Ray, remaining: usize) -> Option<(Color, f64)> {
         let int = r.intersect_world(self);
-        match int.hit() {
-            None => Color::new(0.0, 0.0, 0.0),
-            Some(int_hit) => {
-                let comp = prepare_computations(&int_hit, r, &int);
-                self.shade_hit(&comp, remaining)
-            }
+        let int_hit = int.hit()?;
+        let comp = self.prepare_computations(&int_hit, r, &int);
+        Some((self.shade_hit(&comp, remaining), int_hit.get_time()))
+    }
+
+    /// Color seen along `direction` when nothing is in the way: the face of the `environment`
+    /// cube it points at, or the `background`.
+    fn environment_at(&self, direction: &Vector) -> Color {
+        let Some(faces) = &self.environment else {
+            return self.background;
+        };
+        let largest = direction
+            .x
+            .abs()
+            .max(direction.y.abs())
+            .max(direction.z.abs());
+        if largest == 0.0 {
+            return self.background;
         }
+        let on_cube = Tuple::new_point(
+            direction.x / largest,
+            direction.y / largest,
+            direction.z / largest,
+        );
+        let (face, u, v) = uv_cube(on_cube);
+        faces[face as usize].inner_pattern_at(Tuple::new_point(u, 0.0, v))
+    }
+
+    /// Prepares the shading of `hit`, one of the `intersections` found along `r`, using the
+    /// world's `shadow_epsilon`.
+    pub fn prepare_computations(
+        &self,
+        hit: &Intersection,
+        r: &Ray,
+        intersections: &Intersections,
+    ) -> IntersectComp {
+        prepare_computations_with_epsilon(hit, r, intersections, self.shadow_epsilon)
+    }
+
+    /// Blends `color` towards the fog color by how far along the ray it was seen.
+    fn apply_fog(&self, color: Color, distance: f64) -> Color {
+        if self.fog_density <= 0.0 {
+            return color;
+        }
+        let amount = 1.0 - f64::exp(-self.fog_density * distance);
+        color.lerp(&self.fog_color, amount)
+    }
+
+    /// Returns the material inputs used to shade the first surface the ray hits, after patterns
+    /// are applied, or `None` if the ray doesn't hit anything.
+    pub fn resolved_material_at(&self, r: &Ray) -> Option<ResolvedMaterial> {
+        let int = r.intersect_world(self);
+        let hit = int.hit()?;
+        let comps = self.prepare_computations(&hit, r, &int);
+        Some(
+            comps
+                .object
+                .material()
+                .resolve_at(&comps.object, &comps.over_point),
+        )
     }
 
-    pub(crate) fn is_shadowed(&self, point: &Point) -> bool {
-        let v = self.lights.first().unwrap().get_position() - *point; // TODO: Support multiple lights
+    /// How much of `light` reaches `point`, from 0.0 when it's fully occluded to 1.0 when
+    /// nothing is in the way. Area lights are sampled at several points, so a point in their
+    /// penumbra gets a fraction in between.
+    pub fn light_visibility(&self, point: &Point, light: &Light) -> f64 {
+        let samples = light.samples(point);
+        let lit = samples
+            .iter()
+            .map(|sample| self.light_transmission(sample, point))
+            .sum::<f64>();
+        lit / samples.len() as f64
+    }
+
+    /// Checks whether any object is between the point and the light.
+    pub(crate) fn is_shadowed(&self, light_position: &Point, point: &Point) -> bool {
+        let v = *light_position - *point;
         let distance = v.magnitude();
         let direction = v.normalize();
 
         let r = Ray::new(*point, direction);
+        self.stats.add_shadow_ray();
         let intersections = r.intersect_world(self);
-        let h = intersections.hit();
+        let h = intersections
+            .iter()
+            .find(|i| i.get_time().is_sign_positive() && i.get_object().casts_shadow());
 
         if let Some(hit) = h {
             if hit.get_time() < distance {
@@ -94,14 +435,52 @@ impl World {
         false
     }
 
+    /// How much of the light at `light_position` reaches `point`.
+    /// Without `caustic_gain` this is 0.0 when something is in the way and 1.0 otherwise.
+    /// With it, a path that only crosses transparent objects lets through the product of their
+    /// transparencies times `1.0 + caustic_gain`, up to 1.0.
+    pub(crate) fn light_transmission(&self, light_position: &Point, point: &Point) -> f64 {
+        let gain = match self.caustic_gain {
+            None => {
+                return if self.is_shadowed(light_position, point) {
+                    0.0
+                } else {
+                    1.0
+                }
+            }
+            Some(gain) => gain,
+        };
+
+        let v = *light_position - *point;
+        let distance = v.magnitude();
+        let r = Ray::new(*point, v.normalize());
+        self.stats.add_shadow_ray();
+        let transmitted = r
+            .intersect_world(self)
+            .list
+            .iter()
+            .filter(|i| {
+                i.get_time() >= 0.0 && i.get_time() < distance && i.get_object().casts_shadow()
+            })
+            .try_fold(1.0, |through, i| {
+                let transparency = i.get_object().material().transparency;
+                (transparency > 0.0).then_some(through * transparency)
+            });
+
+        transmitted.map_or(0.0, |through| (through * (1.0 + gain.max(0.0))).min(1.0))
+    }
+
     pub(crate) fn reflected_color(&self, comps: &IntersectComp, remaining: usize) -> Color {
-        if is_float_equal(&comps.object.get_material().reflective, 0.0) || remaining < 1 {
+        if is_float_equal(&comps.object.material().reflective, 0.0) || remaining < 1 {
             return Color::new(0.0, 0.0, 0.0);
         }
 
         let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+        self.stats.add_reflection_ray();
 
-        self.color_at(&reflect_ray, remaining - 1) * comps.object.get_material().reflective
+        let material = comps.object.material();
+        self.color_at_with_environment(&reflect_ray, remaining - 1, material.environment)
+            * material.reflective
     }
 
     fn refracted_color(&self, comps: &IntersectComp, remaining: usize) -> Color {
@@ -109,7 +488,7 @@ impl World {
             return Color::new(0.0, 0.0, 0.0);
         }
 
-        if is_float_equal(&comps.object.get_material().transparency, 0.0) {
+        if is_float_equal(&comps.object.material().transparency, 0.0) {
             return Color::new(0.0, 0.0, 0.0);
         }
 
@@ -125,8 +504,11 @@ impl World {
         let cos_t = (1.0 - sin2_t).sqrt();
         let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
         let refract_ray = Ray::new(comps.under_point, direction);
+        self.stats.add_refraction_ray();
 
-        self.color_at(&refract_ray, remaining - 1) * comps.object.get_material().transparency
+        let material = comps.object.material();
+        self.color_at_with_environment(&refract_ray, remaining - 1, material.environment)
+            * material.transparency
     }
 }
 
@@ -136,13 +518,76 @@ impl Default for World {
     }
 }
 
+/// What happened along the ray through one pixel, from [`World::trace_pixel`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceReport {
+    /// The primary ray from the camera, in world space.
+    pub ray: Ray,
+    /// The first surface the ray hits, if any.
+    pub hit: Option<Intersection>,
+    /// Refractive index of the material the ray is leaving at the hit. 1.0 on a miss.
+    pub n1: f64,
+    /// Refractive index of the material the ray is entering at the hit. 1.0 on a miss.
+    pub n2: f64,
+    /// The fraction of light reflected at the hit according to Schlick's approximation, which
+    /// weighs reflection against refraction on transparent, reflective materials, and reflection
+    /// alone on reflective ones when `use_fresnel` is set. `None` when the hit isn't shaded that
+    /// way, or on a miss.
+    pub reflectance: Option<f64>,
+    /// The color seen along the ray, following up to the given number of reflections and
+    /// refractions, before the camera's exposure is applied.
+    pub color: Color,
+}
+
+/// Builds a [`World`] step by step, checking it with [`World::try_prepare`] at the end.
+///
+/// Settings that aren't given keep the defaults of [`World::new`].
+#[derive(Debug, Default)]
+pub struct WorldBuilder {
+    world: World,
+}
+
+impl WorldBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add_object(mut self, object: Object) -> Self {
+        self.world.add_object(object);
+        self
+    }
+    pub fn add_light(mut self, light: Light) -> Self {
+        self.world.lights.push(light);
+        self
+    }
+    /// Sets the color of rays that don't hit anything.
+    pub fn background(mut self, color: Color) -> Self {
+        self.world.background = color;
+        self
+    }
+    /// Surrounds the world with a pattern per cube face, see [`World::environment`].
+    pub fn environment(mut self, faces: [Pattern; 6]) -> Self {
+        self.world.environment = Some(faces);
+        self
+    }
+    /// Sets how many reflections and refractions [`World::color_at_default_depth`] follows.
+    pub fn max_reflections(mut self, max_reflections: usize) -> Self {
+        self.world.max_reflections = max_reflections;
+        self
+    }
+    /// Returns the world, or an error if it can't be rendered, e.g. because an object has a
+    /// transformation that can't be inverted or something contains NaN.
+    pub fn build(self) -> Result<World, RayTracerError> {
+        self.world.try_prepare()?;
+        Ok(self.world)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::ray_tracer::{
-        intersections::{Intersection, Intersections},
-        patterns::Pattern,
+        intersections::prepare_computations,
+        patterns::{CubeFace, Pattern},
         tuples::Vector,
-        utils::is_float_equal,
     };
 
     use super::*;
@@ -181,6 +626,101 @@ mod tests {
         assert!(w.objects.contains(&s2));
     }
 
+    #[test]
+    fn building_the_default_world() {
+        let mut s1 = new_sphere();
+        let mut s1_mat = s1.get_material();
+        s1_mat.color = Color::new(0.8, 1.0, 0.6);
+        s1_mat.diffuse = 0.7;
+        s1_mat.specular = 0.2;
+        s1.set_material(&s1_mat);
+        let mut s2 = new_sphere();
+        s2.set_transform(&Transform::scaling(0.5, 0.5, 0.5));
+
+        let w = WorldBuilder::new()
+            .add_object(s1)
+            .add_object(s2)
+            .add_light(Light::point_light(
+                &Point::new_point(-10.0, 10.0, -10.0),
+                &Color::new(1.0, 1.0, 1.0),
+            ))
+            .build()
+            .unwrap();
+        assert_eq!(w, default_world());
+
+        let w = WorldBuilder::new()
+            .background(Color::new(0.1, 0.2, 0.3))
+            .max_reflections(2)
+            .build()
+            .unwrap();
+        assert_eq!(w.background, Color::new(0.1, 0.2, 0.3));
+        assert_eq!(w.max_reflections, 2);
+    }
+
+    #[test]
+    fn building_a_world_with_a_nan_light_is_an_error() {
+        let result = WorldBuilder::new()
+            .add_light(Light::point_light(
+                &Point::new_point(f64::NAN, 10.0, -10.0),
+                &Color::new(1.0, 1.0, 1.0),
+            ))
+            .build();
+        assert_eq!(result.err(), Some(RayTracerError::NaNInput));
+    }
+
+    #[test]
+    fn preparing_the_default_world_succeeds() {
+        let w = default_world();
+        assert_eq!(w.try_prepare(), Ok(()));
+    }
+
+    #[test]
+    fn preparing_a_world_with_a_nan_light_is_an_error() {
+        let mut w = default_world();
+        w.lights = vec![Light::point_light(
+            &Point::new_point(f64::NAN, 10.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+        assert_eq!(w.try_prepare(), Err(RayTracerError::NaNInput));
+    }
+
+    #[test]
+    fn preparing_a_world_with_nan_inside_a_group_is_an_error() {
+        let mut w = default_world();
+        let mut child = new_sphere();
+        let mut m = child.get_material();
+        m.shininess = f64::NAN;
+        child.set_material(&m);
+        let mut inner = Group::new();
+        inner.add_child(child);
+        let mut outer = Group::new();
+        outer.add_child(new_group(inner));
+        w.objects.push(new_group(outer));
+        assert_eq!(w.try_prepare(), Err(RayTracerError::NaNInput));
+    }
+
+    #[test]
+    fn preparing_a_world_checks_every_light_field() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let origin = Point::new_point(0.0, 10.0, 0.0);
+        let edge = Vector::new_vector(1.0, 0.0, 0.0);
+        let nan_edge = Vector::new_vector(f64::NAN, 0.0, 0.0);
+        let down = Vector::new_vector(0.0, -1.0, 0.0);
+        let mut attenuated = Light::point_light(&origin, &white);
+        attenuated.set_attenuation(1.0, f64::NAN, 0.0);
+        let lights = [
+            Light::area_light(&origin, &nan_edge, 2, &edge, 2, &white),
+            Light::spot_light(&origin, &down, f64::NAN, 0.5, &white),
+            Light::directional_light(&nan_edge, &white),
+            attenuated,
+        ];
+        for light in lights {
+            let mut w = default_world();
+            w.lights = vec![light];
+            assert_eq!(w.try_prepare(), Err(RayTracerError::NaNInput));
+        }
+    }
+
     #[test]
     fn intersect_a_world_with_a_ray() {
         let w = default_world();
@@ -196,6 +736,16 @@ mod tests {
         assert!(is_float_equal(&xs.get_element(2).unwrap().get_time(), 5.5));
         assert!(is_float_equal(&xs.get_element(3).unwrap().get_time(), 6.0));
     }
+    #[test]
+    fn intersecting_the_world_directly() {
+        let w = default_world();
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let times: Vec<f64> = w.intersect(&r).iter().map(|i| i.get_time()).collect();
+        assert_eq!(times, vec![4.0, 4.5, 5.5, 6.0]);
+    }
 
     #[test]
     fn shading_an_intersection() {
@@ -206,7 +756,7 @@ mod tests {
         );
         let shape = w.objects.first().unwrap();
         let i = Intersection::new(4.0, shape.clone());
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let c = w.shade_hit(&comps, 1);
         assert_eq!(c, Color::new(0.38066, 0.47583, 0.2855));
     }
@@ -223,7 +773,7 @@ mod tests {
         );
         let shape = w.objects[1].clone();
         let i = Intersection::new(0.5, shape);
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let c = w.shade_hit(&comps, 1);
         assert_eq!(c, Color::new(0.90498, 0.90498, 0.90498));
     }
@@ -247,11 +797,209 @@ mod tests {
             Vector::new_vector(0.0, 0.0, 1.0),
         );
         let i = Intersection::new(4.0, s2);
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let c = w.shade_hit(&comps, 1);
         assert_eq!(c, Color::new(0.1, 0.1, 0.1));
     }
     #[test]
+    fn an_emissive_surface_glows_in_full_shadow() {
+        let mut w = World::new();
+        w.lights = vec![Light::point_light(
+            &Point::new_point(0.0, 0.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+        w.objects.push(new_sphere());
+
+        let mut s2 = new_sphere();
+        s2.set_transform(&Transform::translate(0.0, 0.0, 10.0));
+        let mut material = s2.get_material();
+        material.emissive = Color::new(0.9, 0.6, 0.2);
+        s2.set_material(&material);
+        w.objects.push(s2.clone());
+
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, 5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let i = Intersection::new(4.0, s2);
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
+        let c = w.shade_hit(&comps, 1);
+        assert_eq!(c, Color::new(1.0, 0.7, 0.3));
+    }
+    #[test]
+    fn the_resolved_material_follows_the_pattern() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let mut floor = new_plane();
+        let mut material = floor.get_material();
+        material.pattern = Some(Pattern::stripe(white, black));
+        material.shininess = 50.0;
+        floor.set_material(&material);
+        let mut w = World::new();
+        w.objects.push(floor);
+
+        let resolved_at = |x: f64| {
+            let r = Ray::new(
+                Point::new_point(x, 1.0, 0.0),
+                Vector::new_vector(0.0, -1.0, 0.0),
+            );
+            w.resolved_material_at(&r).unwrap()
+        };
+        assert_eq!(resolved_at(0.5).color, white);
+        assert_eq!(resolved_at(1.5).color, black);
+        assert_eq!(resolved_at(2.5).color, white);
+        assert!(is_float_equal(&resolved_at(0.5).shininess, 50.0));
+
+        let miss = Ray::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(w.resolved_material_at(&miss), None);
+    }
+    #[test]
+    fn shade_hit_sums_the_contributions_of_all_lights() {
+        let left = Light::point_light(
+            &Point::new_point(-10.0, 0.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        let right = Light::point_light(
+            &Point::new_point(10.0, 0.0, -10.0),
+            &Color::new(0.5, 0.5, 0.5),
+        );
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let shade_with = |lights: Vec<Light>| {
+            let mut w = World::new();
+            w.objects.push(new_sphere());
+            w.lights = lights;
+            let i = Intersection::new(4.0, w.objects[0].clone());
+            let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
+            w.shade_hit(&comps, 1)
+        };
+
+        let c_left = shade_with(vec![left]);
+        let c_right = shade_with(vec![right]);
+        let c_both = shade_with(vec![left, right]);
+        assert_eq!(c_both, c_left + c_right);
+    }
+    #[test]
+    fn the_breakdown_of_a_hit_sums_to_the_shaded_color() {
+        let mut w = World::new();
+        w.objects.push(new_sphere());
+        w.lights = vec![
+            Light::point_light(
+                &Point::new_point(-10.0, 0.0, -10.0),
+                &Color::new(1.0, 1.0, 1.0),
+            ),
+            Light::point_light(
+                &Point::new_point(10.0, 0.0, -10.0),
+                &Color::new(0.5, 0.5, 0.5),
+            ),
+        ];
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let xs = w.intersect(&r);
+        let comps = w.prepare_computations(&xs.hit().unwrap(), &r, &xs);
+
+        let breakdown = w.shade_hit_breakdown(&comps);
+        assert_eq!(breakdown.len(), 2);
+        assert_eq!(breakdown[0].0, 0);
+        assert_eq!(breakdown[1].0, 1);
+        assert_ne!(breakdown[0].1, breakdown[1].1);
+        assert_eq!(breakdown[0].1 + breakdown[1].1, w.shade_hit(&comps, 1));
+        assert_eq!(w.light_breakdown_at(&r), Some(breakdown));
+    }
+    #[test]
+    fn caustic_gain_brightens_a_surface_under_a_glass_sphere() {
+        let mut w = World::new();
+        w.lights = vec![Light::point_light(
+            &Point::new_point(0.0, 10.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+        w.objects.push(glass_sphere());
+        let mut floor = new_plane();
+        floor.set_transform(&Transform::translate(0.0, -2.0, 0.0));
+        w.objects.push(floor.clone());
+
+        let r = Ray::new(
+            Point::new_point(0.0, -1.5, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        let i = Intersection::new(0.5, floor);
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
+
+        let plain = w.shade_hit(&comps, 1);
+        assert_eq!(plain, Color::new(0.1, 0.1, 0.1));
+
+        w.caustic_gain = Some(0.25);
+        let caustic = w.shade_hit(&comps, 1);
+        assert!(caustic.red > plain.red);
+
+        // Never brighter than without the sphere in the way
+        let glass = w.objects.remove(0);
+        let lit = w.shade_hit(&comps, 1);
+        assert_eq!(caustic, lit);
+
+        // A cloudier sphere lets less through, gain and all
+        let mut cloudy = glass.get_material();
+        cloudy.transparency = 0.5;
+        let mut cloudy_sphere = glass;
+        cloudy_sphere.set_material(&cloudy);
+        w.objects.insert(0, cloudy_sphere);
+        let dimmed = w.shade_hit(&comps, 1);
+        assert!(dimmed.red > plain.red && dimmed.red < lit.red);
+        let p = comps.over_point;
+        assert!(is_float_equal(
+            &w.light_visibility(&p, &w.lights[0]),
+            0.3125
+        ));
+
+        w.caustic_gain = None;
+        assert_eq!(w.shade_hit(&comps, 1), plain);
+    }
+    #[test]
+    fn closely_spaced_planes_need_a_smaller_epsilon() {
+        // A clear plane lying just above a red one, closer than the default epsilon
+        let mut w = World::new();
+        let mut glass = new_plane();
+        let mut glass_mat = glass.get_material();
+        glass_mat.ambient = 0.0;
+        glass_mat.diffuse = 0.0;
+        glass_mat.specular = 0.0;
+        glass_mat.transparency = 1.0;
+        glass.set_material(&glass_mat);
+        let mut sticker = new_plane();
+        sticker.set_transform(&Transform::translate(0.0, -0.00001, 0.0));
+        let mut sticker_mat = sticker.get_material();
+        sticker_mat.color = Color::new(1.0, 0.0, 0.0);
+        sticker_mat.ambient = 1.0;
+        sticker_mat.diffuse = 0.0;
+        sticker_mat.specular = 0.0;
+        sticker.set_material(&sticker_mat);
+        w.objects = vec![glass, sticker];
+        w.lights = vec![Light::point_light(
+            &Point::new_point(0.0, 10.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+
+        let r = Ray::new(
+            Point::new_point(0.0, 1.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+
+        // The default epsilon pushes the refracted ray past the red plane
+        assert_eq!(w.color_at(&r, 5), Color::new(0.0, 0.0, 0.0));
+
+        for object in &mut w.objects {
+            object.set_epsilon(Some(0.000001));
+        }
+        assert_eq!(w.color_at(&r, 5), Color::new(1.0, 0.0, 0.0));
+    }
+    #[test]
     fn the_color_when_a_ray_misses() {
         let w = default_world();
         let r = Ray::new(
@@ -262,6 +1010,229 @@ mod tests {
         assert_eq!(c, Color::new(0.0, 0.0, 0.0));
     }
     #[test]
+    fn a_larger_shadow_epsilon_removes_acne() {
+        let mut w = World::new();
+        w.lights.push(Light::point_light(
+            &Point::new_point(0.0, 1000.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        ));
+        // A sphere so big that rounding errors in its object space are larger than EPSILON
+        let mut ground = new_sphere();
+        ground.set_transform(
+            &(Transform::translate(0.0, -1.0e12, 0.0) * Transform::scaling(1.0e12, 1.0e12, 1.0e12)),
+        );
+        w.objects.push(ground);
+
+        let count_shadowed = |w: &World| {
+            let mut shadowed = 0;
+            for i in 0..5 {
+                for j in 0..5 {
+                    let r = Ray::new(
+                        Point::new_point(i as f64 * 0.37, 5.0, j as f64 * 0.41),
+                        Vector::new_vector(0.0, -1.0, 0.0),
+                    );
+                    let xs = w.intersect(&r);
+                    let comps = w.prepare_computations(&xs.hit().unwrap(), &r, &xs);
+                    if w.is_shadowed(&w.lights[0].get_position(), &comps.over_point) {
+                        shadowed += 1;
+                    }
+                }
+            }
+            shadowed
+        };
+        assert!(count_shadowed(&w) > 0);
+        w.shadow_epsilon = 0.01;
+        assert_eq!(count_shadowed(&w), 0);
+    }
+    #[test]
+    fn a_missed_ray_returns_the_background() {
+        let mut w = default_world();
+        w.background = Color::new(0.8, 0.8, 0.8);
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(w.color_at(&r, 1), Color::new(0.8, 0.8, 0.8));
+    }
+    #[test]
+    fn a_distant_hit_is_more_fogged_than_a_near_one() {
+        let mut w = default_world();
+        w.fog_color = Color::new(1.0, 1.0, 1.0);
+        w.fog_density = 0.05;
+        let near = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let far = Ray::new(
+            Point::new_point(0.0, 0.0, -50.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        let near_color = w.color_at(&near, 1);
+        let far_color = w.color_at(&far, 1);
+        assert!(far_color.red > near_color.red);
+        assert!(far_color.green > near_color.green);
+        assert!(far_color.blue > near_color.blue);
+
+        w.fog_density = 0.0;
+        assert_eq!(w.color_at(&near, 1), w.color_at(&far, 1));
+    }
+    #[test]
+    fn reflections_are_only_fogged_by_the_distance_along_the_camera_ray() {
+        let mut w = default_world();
+        let mut mirror = new_plane();
+        let mut mirror_mat = mirror.get_material();
+        mirror_mat.reflective = 0.5;
+        mirror.set_material(&mirror_mat);
+        mirror.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        w.objects.push(mirror);
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -3.0),
+            Vector::new_vector(0.0, -(2.0_f64.sqrt()) / 2.0, 2.0_f64.sqrt() / 2.0),
+        );
+        let clear = w.color_at(&r, 5);
+
+        w.fog_color = Color::new(1.0, 1.0, 1.0);
+        w.fog_density = 0.2;
+        // The mirror is √2 along the camera ray; the sphere it reflects is further away, but
+        // its reflection is seen at the mirror
+        let amount = 1.0 - f64::exp(-0.2 * 2.0_f64.sqrt());
+        assert_eq!(w.color_at(&r, 5), clear.lerp(&w.fog_color, amount));
+    }
+    #[test]
+    fn a_mirror_with_an_environment_override_reflects_it_on_a_miss() {
+        let mut w = World::new();
+        w.lights = vec![Light::point_light(
+            &Point::new_point(-10.0, 10.0, -10.0),
+            &Color::new(1.0, 1.0, 1.0),
+        )];
+        let red = Color::new(1.0, 0.0, 0.0);
+        let mut mirror = new_sphere();
+        let mut mirror_mat = mirror.get_material();
+        mirror_mat.ambient = 0.0;
+        mirror_mat.diffuse = 0.0;
+        mirror_mat.specular = 0.0;
+        mirror_mat.reflective = 1.0;
+        mirror_mat.environment = Some(red);
+        mirror.set_material(&mirror_mat);
+        w.objects.push(mirror);
+
+        let hit = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 0.0, 1.0),
+        );
+        assert_eq!(w.color_at(&hit, 1), red);
+
+        let miss = Ray::new(
+            Point::new_point(0.0, 0.0, -5.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(w.color_at(&miss, 1), Color::new(0.0, 0.0, 0.0));
+
+        mirror_mat.environment = None;
+        w.objects[0].set_material(&mirror_mat);
+        assert_eq!(w.color_at(&hit, 1), Color::new(0.0, 0.0, 0.0));
+    }
+    #[test]
+    fn a_missed_ray_samples_the_environment_face_it_points_at() {
+        let colors = [
+            Color::new(1.0, 1.0, 0.0),
+            Color::new(0.0, 1.0, 1.0),
+            Color::new(1.0, 0.0, 0.0),
+            Color::new(0.0, 1.0, 0.0),
+            Color::new(0.2, 0.4, 1.0),
+            Color::new(0.3, 0.3, 0.3),
+        ];
+        let mut w = World::new();
+        w.environment = Some(colors.map(Pattern::solid));
+        let up = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+        assert_eq!(w.color_at(&up, 1), colors[CubeFace::Up as usize]);
+        let down_and_right = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.5, -1.0, 0.2),
+        );
+        assert_eq!(
+            w.color_at(&down_and_right, 1),
+            colors[CubeFace::Down as usize]
+        );
+
+        // A mirror reflects the sky above it
+        let mut mirror = new_plane();
+        let mut mirror_mat = mirror.get_material();
+        mirror_mat.ambient = 0.0;
+        mirror_mat.diffuse = 0.0;
+        mirror_mat.specular = 0.0;
+        mirror_mat.reflective = 1.0;
+        mirror.set_material(&mirror_mat);
+        mirror.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        w.objects.push(mirror);
+        let down = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        assert_eq!(w.color_at(&down, 1), colors[CubeFace::Up as usize]);
+    }
+    #[test]
+    fn tracing_a_pixel_reports_the_hit_and_its_color() {
+        let w = default_world();
+        let mut c = Camera::new(11, 11, std::f64::consts::PI / 2.0);
+        c.set_transform(Transform::view_transform(
+            &Point::new_point(0.0, 0.0, -5.0),
+            &Point::new_point(0.0, 0.0, 0.0),
+            &Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+
+        let report = w.trace_pixel(&c, 5, 5, 1);
+        assert_eq!(report.ray, c.ray_for_pixel(5, 5));
+        let hit = report.hit.as_ref().unwrap();
+        assert!(is_float_equal(&hit.get_time(), 4.0));
+        assert_eq!(hit.get_object().get_id(), w.objects[0].get_id());
+        assert_eq!((report.n1, report.n2), (1.0, 1.0));
+        assert_eq!(report.reflectance, None);
+        assert_eq!(report.color, w.color_at(&report.ray, 1));
+        assert_eq!(report.color, Color::new(0.38066, 0.47583, 0.2855));
+        assert_eq!(report.color, *c.render(&w, 1).pixel_at(5, 5));
+
+        let miss = w.trace_pixel(&c, 0, 0, 1);
+        assert_eq!(miss.hit, None);
+        assert_eq!(miss.reflectance, None);
+        assert_eq!(miss.color, w.background);
+    }
+    #[test]
+    fn tracing_a_pixel_follows_the_given_depth_and_reports_reflectance_where_it_is_used() {
+        let mut w = default_world();
+        let mut m = w.objects[0].get_material();
+        m.reflective = 0.5;
+        w.objects[0].set_material(&m);
+        let mut floor = new_plane();
+        floor.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        w.objects.push(floor);
+        let mut c = Camera::new(11, 11, std::f64::consts::PI / 2.0);
+        c.set_transform(Transform::view_transform(
+            &Point::new_point(0.0, 0.0, -5.0),
+            &Point::new_point(0.0, 0.0, 0.0),
+            &Vector::new_vector(0.0, 1.0, 0.0),
+        ));
+
+        // Reflective but opaque: plain reflection, no Schlick. The pixel looks at the lower
+        // half of the sphere, which reflects the floor
+        let (x, y) = (5, 6);
+        let report = w.trace_pixel(&c, x, y, 0);
+        let hit = report.hit.as_ref().unwrap();
+        assert_eq!(hit.get_object().get_id(), w.objects[0].get_id());
+        assert_eq!(report.reflectance, None);
+        assert_eq!(report.color, *c.render(&w, 0).pixel_at(x, y));
+        let deeper = w.trace_pixel(&c, x, y, 3);
+        assert_eq!(deeper.color, *c.render(&w, 3).pixel_at(x, y));
+        assert_ne!(deeper.color, report.color);
+
+        w.use_fresnel = true;
+        let fresnel = w.trace_pixel(&c, x, y, 3);
+        assert!(fresnel.reflectance.is_some_and(|r| r > 0.0 && r < 1.0));
+    }
+    #[test]
     fn the_color_when_a_ray_hits() {
         let w = default_world();
         let r = Ray::new(
@@ -294,6 +1265,7 @@ mod tests {
         let new_world = World {
             lights: default_world().lights,
             objects: vec![outer, inner],
+            ..World::new()
         };
         let r = Ray::new(
             Point::new_point(0.0, 0.0, 0.75),
@@ -307,25 +1279,64 @@ mod tests {
     fn there_is_no_shadow_when_nothing_is_collinear_with_point_and_light() {
         let w = World::new_default_world();
         let p = Point::new_point(0.0, 10.0, 0.0);
-        assert!(!w.is_shadowed(&p));
+        assert!(!w.is_shadowed(&w.lights[0].get_position(), &p));
     }
     #[test]
     fn the_shadow_when_an_object_is_between_the_point_and_the_light() {
         let w = World::new_default_world();
         let p = Point::new_point(10.0, -10.0, 10.0);
-        assert!(w.is_shadowed(&p));
+        assert!(w.is_shadowed(&w.lights[0].get_position(), &p));
     }
     #[test]
     fn there_is_no_shadow_when_an_object_is_behind_the_light() {
         let w = World::new_default_world();
         let p = Point::new_point(-20.0, 20.0, -20.0);
-        assert!(!w.is_shadowed(&p));
+        assert!(!w.is_shadowed(&w.lights[0].get_position(), &p));
     }
     #[test]
     fn there_is_no_shadow_when_an_object_is_behind_the_point() {
         let w = World::new_default_world();
         let p = Point::new_point(-2.0, 2.0, -2.0);
-        assert!(!w.is_shadowed(&p));
+        assert!(!w.is_shadowed(&w.lights[0].get_position(), &p));
+    }
+    #[test]
+    fn an_area_light_is_partially_visible_behind_a_small_occluder() {
+        let mut w = World::new();
+        let mut light = Light::area_light(
+            &Point::new_point(-1.0, 4.0, -1.0),
+            &Vector::new_vector(2.0, 0.0, 0.0),
+            4,
+            &Vector::new_vector(0.0, 0.0, 2.0),
+            4,
+            &Color::new(1.0, 1.0, 1.0),
+        );
+        light.set_jitter(false);
+        let p = Point::new_point(0.0, 0.0, 0.0);
+        assert_eq!(w.light_visibility(&p, &light), 1.0);
+
+        // Only blocks the four samples in the middle of the light
+        let mut occluder = new_sphere();
+        occluder.set_transform(
+            &(Transform::translate(0.0, 2.0, 0.0) * Transform::scaling(0.2, 0.2, 0.2)),
+        );
+        w.objects.push(occluder);
+        assert_eq!(w.light_visibility(&p, &light), 0.75);
+        assert_eq!(light.intensity_at(&p, &w), 0.75);
+    }
+    #[test]
+    fn an_object_that_does_not_cast_shadows_leaves_the_point_lit() {
+        let mut w = World::new_default_world();
+        let p = Point::new_point(10.0, -10.0, 10.0);
+        for object in &mut w.objects {
+            assert!(object.casts_shadow());
+            object.set_casts_shadow(false);
+        }
+        assert!(!w.is_shadowed(&w.lights[0].get_position(), &p));
+        assert_eq!(w.lights[0].intensity_at(&p, &w), 1.0);
+
+        // Only the outer sphere is needed to block the light
+        w.objects[0].set_casts_shadow(true);
+        assert!(w.is_shadowed(&w.lights[0].get_position(), &p));
     }
 
     #[test]
@@ -336,9 +1347,11 @@ mod tests {
             Vector::new_vector(0.0, 0.0, 1.0),
         );
         let shape = w.objects.get_mut(1).unwrap();
-        shape.get_material().ambient = 1.0;
+        let mut shape_mat = shape.get_material();
+        shape_mat.ambient = 1.0;
+        shape.set_material(&shape_mat);
         let i = Intersection::new(1.0, shape.clone());
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let color = w.reflected_color(&comps, 1);
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
@@ -346,7 +1359,9 @@ mod tests {
     fn the_reflected_color_for_a_reflective_material() {
         let mut w = World::new_default_world();
         let mut shape = new_plane();
-        shape.get_material().reflective = 0.5;
+        let mut shape_mat = shape.get_material();
+        shape_mat.reflective = 0.5;
+        shape.set_material(&shape_mat);
         shape.set_transform(&Transform::translate(0.0, -1.0, 0.0));
         w.objects.push(shape.clone());
         let r = Ray::new(
@@ -354,7 +1369,7 @@ mod tests {
             Vector::new_vector(0.0, -(2.0_f64.sqrt()) / 2.0, 2.0_f64.sqrt() / 2.0),
         );
         let i = Intersection::new(2.0_f64.sqrt(), shape);
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let color = w.reflected_color(&comps, 1);
         assert_eq!(color, Color::new(0.19032, 0.2379, 0.14274));
     }
@@ -362,7 +1377,9 @@ mod tests {
     fn shade_hit_with_a_reflective_material() {
         let mut w = World::new_default_world();
         let mut shape = new_plane();
-        shape.get_material().reflective = 0.5;
+        let mut shape_mat = shape.get_material();
+        shape_mat.reflective = 0.5;
+        shape.set_material(&shape_mat);
         shape.set_transform(&Transform::translate(0.0, -1.0, 0.0));
         w.objects.push(shape.clone());
         let r = Ray::new(
@@ -370,11 +1387,72 @@ mod tests {
             Vector::new_vector(0.0, -(2.0_f64.sqrt()) / 2.0, 2.0_f64.sqrt() / 2.0),
         );
         let i = Intersection::new(2.0_f64.sqrt(), shape);
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let color = w.shade_hit(&comps, 1);
         assert_eq!(color, Color::new(0.87677, 0.92436, 0.82918));
     }
     #[test]
+    fn fresnel_reflects_more_at_grazing_angles() {
+        let mut mirror = new_plane();
+        let mut mirror_mat = mirror.get_material();
+        mirror_mat.reflective = 1.0;
+        mirror_mat.refractive_index = 1.5;
+        mirror.set_material(&mirror_mat);
+
+        let reflectance = |direction: Vector| {
+            let r = Ray::new(Point::new_point(0.0, 1.0, 0.0) - direction, direction);
+            let i = Intersection::new(1.0, mirror.clone());
+            let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
+            schlick(&comps)
+        };
+        let head_on = reflectance(Vector::new_vector(0.0, -1.0, 0.0));
+        let grazing = reflectance(Vector::new_vector(0.0, -0.1, 1.0).normalize());
+        assert!(is_float_equal(&head_on, 0.04));
+        assert!(grazing > 0.5);
+    }
+    #[test]
+    fn fresnel_dims_head_on_reflections_of_opaque_materials() {
+        let mut w = World::new_default_world();
+        w.lights.clear();
+        let mut mirror = new_plane();
+        let mut mirror_mat = mirror.get_material();
+        mirror_mat.reflective = 1.0;
+        mirror_mat.refractive_index = 1.5;
+        mirror_mat.ambient = 0.0;
+        mirror.set_material(&mirror_mat);
+        mirror.set_transform(&Transform::translate(0.0, -5.0, 0.0));
+        w.objects.push(mirror);
+        w.background = Color::new(1.0, 1.0, 1.0);
+
+        let r = Ray::new(
+            Point::new_point(3.0, 0.0, 0.0),
+            Vector::new_vector(0.0, -1.0, 0.0),
+        );
+        assert_eq!(w.color_at(&r, 1), Color::new(1.0, 1.0, 1.0));
+        w.use_fresnel = true;
+        assert_eq!(w.color_at(&r, 1), Color::new(0.04, 0.04, 0.04));
+    }
+    #[test]
+    fn color_at_default_depth_follows_max_reflections() {
+        let mut w = World::new_default_world();
+        assert_eq!(w.max_reflections, DEFAULT_MAX_REFLECTIONS);
+        let mut shape = new_plane();
+        let mut shape_mat = shape.get_material();
+        shape_mat.reflective = 0.5;
+        shape.set_material(&shape_mat);
+        shape.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        w.objects.push(shape);
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, -3.0),
+            Vector::new_vector(0.0, -(2.0_f64.sqrt()) / 2.0, 2.0_f64.sqrt() / 2.0),
+        );
+        assert_eq!(w.color_at_default_depth(&r), w.color_at(&r, 5));
+
+        let w = w.with_max_reflections(0);
+        assert_eq!(w.color_at_default_depth(&r), w.color_at(&r, 0));
+        assert_ne!(w.color_at_default_depth(&r), w.color_at(&r, 5));
+    }
+    #[test]
     fn color_at_with_mutually_reflective_surfaces() {
         let mut w = World::new();
         w.lights.push(Light::point_light(
@@ -382,7 +1460,9 @@ mod tests {
             &Color::new(1.0, 1.0, 1.0),
         ));
         let mut lower = new_plane();
-        lower.get_material().reflective = 1.0;
+        let mut lower_mat = lower.get_material();
+        lower_mat.reflective = 1.0;
+        lower.set_material(&lower_mat);
         lower.set_transform(&Transform::translate(0.0, -1.0, 0.0));
         let mut upper = lower.clone();
         upper.set_transform(&Transform::translate(0.0, 1.0, 0.0));
@@ -400,10 +1480,38 @@ mod tests {
         assert_ne!(color, Color::new(0.0, 0.0, 0.0));
     }
     #[test]
+    #[cfg(feature = "stats")]
+    fn mutually_reflective_surfaces_stop_at_the_recursion_ceiling() {
+        let mut w = World::new();
+        w.lights.push(Light::point_light(
+            &Point::new_point(0.0, 0.0, 0.0),
+            &Color::new(1.0, 1.0, 1.0),
+        ));
+        let mut lower = new_plane();
+        let mut lower_mat = lower.get_material();
+        lower_mat.reflective = 1.0;
+        lower.set_material(&lower_mat);
+        lower.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        let mut upper = lower.clone();
+        upper.set_transform(&Transform::translate(0.0, 1.0, 0.0));
+        w.objects.push(lower);
+        w.objects.push(upper);
+        let r = Ray::new(
+            Point::new_point(0.0, 0.0, 0.0),
+            Vector::new_vector(0.0, 1.0, 0.0),
+        );
+
+        let max_recursion = 3;
+        w.color_at(&r, max_recursion);
+        assert_eq!(w.get_stats().reflection_rays(), 3);
+    }
+    #[test]
     fn the_reflected_color_at_the_maximum_recursive_depth() {
         let mut w = World::new_default_world();
         let mut shape = new_plane();
-        shape.get_material().reflective = 0.5;
+        let mut shape_mat = shape.get_material();
+        shape_mat.reflective = 0.5;
+        shape.set_material(&shape_mat);
         shape.set_transform(&Transform::translate(0.0, -1.0, 0.0));
         w.objects.push(shape.clone());
         let r = Ray::new(
@@ -411,7 +1519,7 @@ mod tests {
             Vector::new_vector(0.0, -(2.0_f64.sqrt()) / 2.0, 2.0_f64.sqrt() / 2.0),
         );
         let i = Intersection::new(2.0_f64.sqrt(), shape);
-        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&vec![i]));
+        let comps = prepare_computations(&i.clone(), &r, &Intersections::new(&[i]));
         let color = w.reflected_color(&comps, 0);
         assert_eq!(color, Color::new(0.0, 0.0, 0.0));
     }
@@ -435,9 +1543,11 @@ mod tests {
     #[test]
     fn the_refracted_color_at_the_maximum_recursive_depth() {
         let w = World::new_default_world();
-        let shape = w.objects[0].clone();
-        shape.get_material().transparency = 1.0;
-        shape.get_material().refractive_index = 1.5;
+        let mut shape = w.objects[0].clone();
+        let mut shape_mat = shape.get_material();
+        shape_mat.transparency = 1.0;
+        shape_mat.refractive_index = 1.5;
+        shape.set_material(&shape_mat);
         let r = Ray::new(
             Point::new_point(0.0, 0.0, -5.0),
             Vector::new_vector(0.0, 0.0, 1.0),
@@ -453,9 +1563,11 @@ mod tests {
     #[test]
     fn the_refracted_color_under_total_internal_reflection() {
         let w = World::new_default_world();
-        let shape = w.objects[0].clone();
-        shape.get_material().transparency = 1.0;
-        shape.get_material().refractive_index = 1.5;
+        let mut shape = w.objects[0].clone();
+        let mut shape_mat = shape.get_material();
+        shape_mat.transparency = 1.0;
+        shape_mat.refractive_index = 1.5;
+        shape.set_material(&shape_mat);
         let r = Ray::new(
             Point::new_point(0.0, 0.0, 2.0_f64.sqrt() / 2.0),
             Vector::new_vector(0.0, 1.0, 0.0),
@@ -471,13 +1583,17 @@ mod tests {
     #[test]
     #[allow(non_snake_case)]
     fn the_refracted_color_with_a_refracted_ray() {
-        let w = default_world();
-        w.objects[0].get_material().ambient = 1.0;
-        w.objects[0].get_material().pattern = Some(Pattern::test_pattern_default());
+        let mut w = default_world();
+        let mut a_mat = w.objects[0].get_material();
+        a_mat.ambient = 1.0;
+        a_mat.pattern = Some(Pattern::test_pattern_default());
+        w.objects[0].set_material(&a_mat);
         let A = w.objects[0].clone();
 
-        w.objects[1].get_material().transparency = 1.0;
-        w.objects[1].get_material().refractive_index = 1.5;
+        let mut b_mat = w.objects[1].get_material();
+        b_mat.transparency = 1.0;
+        b_mat.refractive_index = 1.5;
+        w.objects[1].set_material(&b_mat);
         let B = w.objects[1].clone();
 
         let r = Ray::new(
@@ -500,14 +1616,18 @@ mod tests {
 
         let mut floor = new_plane();
         floor.set_transform(&Transform::translate(0.0, -1.0, 0.0));
-        floor.get_material().transparency = 0.5;
-        floor.get_material().refractive_index = 1.5;
+        let mut floor_mat = floor.get_material();
+        floor_mat.transparency = 0.5;
+        floor_mat.refractive_index = 1.5;
+        floor.set_material(&floor_mat);
         w.objects.push(floor.clone());
 
         let mut ball = new_sphere();
         ball.set_transform(&Transform::translate(0.0, -3.5, -0.5));
-        ball.get_material().color = Color::new(1.0, 0.0, 0.0);
-        ball.get_material().ambient = 0.5;
+        let mut ball_mat = ball.get_material();
+        ball_mat.color = Color::new(1.0, 0.0, 0.0);
+        ball_mat.ambient = 0.5;
+        ball.set_material(&ball_mat);
         w.objects.push(ball.clone());
 
         let r = Ray::new(
@@ -525,15 +1645,19 @@ mod tests {
 
         let mut floor = new_plane();
         floor.set_transform(&Transform::translate(0.0, -1.0, 0.0));
-        floor.get_material().reflective = 0.5;
-        floor.get_material().transparency = 0.5;
-        floor.get_material().refractive_index = 1.5;
+        let mut floor_mat = floor.get_material();
+        floor_mat.reflective = 0.5;
+        floor_mat.transparency = 0.5;
+        floor_mat.refractive_index = 1.5;
+        floor.set_material(&floor_mat);
         w.objects.push(floor.clone());
 
         let mut ball = new_sphere();
         ball.set_transform(&Transform::translate(0.0, -3.5, -0.5));
-        ball.get_material().color = Color::new(1.0, 0.0, 0.0);
-        ball.get_material().ambient = 0.5;
+        let mut ball_mat = ball.get_material();
+        ball_mat.color = Color::new(1.0, 0.0, 0.0);
+        ball_mat.ambient = 0.5;
+        ball.set_material(&ball_mat);
         w.objects.push(ball.clone());
 
         let r = Ray::new(
@@ -545,4 +1669,27 @@ mod tests {
         let color = w.shade_hit(&comps, 5);
         assert_eq!(color, Color::new(0.93391, 0.69643, 0.69243));
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn a_world_survives_a_json_round_trip() {
+        let mut w = default_world();
+        w.caustic_gain = Some(0.25);
+
+        let mut floor = new_plane();
+        floor.set_transform(&Transform::translate(0.0, -1.0, 0.0));
+        let mut floor_mat = floor.get_material();
+        floor_mat.pattern = Some(Pattern::checker(
+            Color::new(1.0, 1.0, 1.0),
+            Color::new(0.0, 0.0, 0.0),
+        ));
+        floor.set_material(&floor_mat);
+        w.objects.push(floor);
+        w.objects.push(new_cylinder(None));
+
+        let json = serde_json::to_string(&w).unwrap();
+        let loaded: World = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, w);
+        assert!(loaded.objects[2].get_transform().is_inverted());
+    }
 }